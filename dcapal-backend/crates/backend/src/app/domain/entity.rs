@@ -1,8 +1,9 @@
-use chrono::{Duration, Timelike, Utc};
+use chrono::{Duration, NaiveDate, Timelike, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::{DateTime, app::infra::utils::Expiring};
+use crate::{DateTime, app::infra::utils::Expiring, config::PriceProvider};
 
 /// Stable identifier used for a supported asset.
 pub type AssetId = String;
@@ -47,7 +48,7 @@ pub enum Asset {
     Fiat(Fiat),
 }
 
-#[derive(Debug, Clone, Copy, strum_macros::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
 /// The broad asset category used when selecting market-data catalogs.
 pub enum AssetKind {
     Crypto,
@@ -78,33 +79,65 @@ impl Asset {
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema)]
-/// A price and the timestamp at which it was observed.
+#[serde(rename_all = "camelCase")]
+/// A price, the time its provider observed it, and the time it was fetched.
 pub struct Price {
     pub price: f64,
-    /// Serialized as Unix seconds in the REST/OpenAPI representation.
+    /// When the provider observed the price, as Unix seconds. For a candle
+    /// close this is the end of the candle, not the time of the request.
+    #[schema(value_type = i64, format = Int64)]
+    #[serde(alias = "ts", with = "chrono::serde::ts_seconds")]
+    pub observed_at: DateTime,
+    /// When the price was fetched from its provider, as Unix seconds.
     #[schema(value_type = i64, format = Int64)]
-    #[serde(with = "chrono::serde::ts_seconds")]
-    pub ts: DateTime,
+    #[serde(
+        with = "chrono::serde::ts_seconds",
+        default = "Price::unknown_fetched_at"
+    )]
+    pub fetched_at: DateTime,
+    /// The market data provider that served the price. Absent for derived
+    /// rates and for prices stored before providers were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<PriceProvider>,
 }
 
 impl Price {
     const VALIDITY_MINS: u32 = 5;
 
-    /// Creates a price observation with its source timestamp.
-    pub fn new(price: f64, ts: DateTime) -> Self {
-        Self { price, ts }
+    /// Creates a price observed at `observed_at` and fetched at `fetched_at`.
+    pub fn new(price: f64, observed_at: DateTime, fetched_at: DateTime) -> Self {
+        Self {
+            price,
+            observed_at,
+            fetched_at,
+            provider: None,
+        }
+    }
+
+    /// Records the market data provider that served the price.
+    pub fn with_provider(mut self, provider: PriceProvider) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Prices stored before fetch times were recorded report the Unix epoch.
+    fn unknown_fetched_at() -> DateTime {
+        DateTime::UNIX_EPOCH
     }
 }
 
 impl Expiring for Price {
     fn is_outdated(&self) -> bool {
         let now = Utc::now();
-        if now.date_naive() > self.ts.date_naive() || now.time().hour() > self.ts.time().hour() {
+        let observed_at = self.observed_at;
+        if now.date_naive() > observed_at.date_naive()
+            || now.time().hour() > observed_at.time().hour()
+        {
             return true;
         }
 
         let now_mins = now.time().minute();
-        let ts_mins = self.ts.time().minute();
+        let ts_mins = observed_at.time().minute();
 
         let now_validity_range = (now_mins / Self::VALIDITY_MINS) * Self::VALIDITY_MINS;
         let ts_validity_range = (ts_mins / Self::VALIDITY_MINS) * Self::VALIDITY_MINS;
@@ -113,12 +146,23 @@ impl Expiring for Price {
     }
 
     fn time_to_live(&self) -> std::time::Duration {
-        (self.ts + Duration::minutes(Self::VALIDITY_MINS as i64) - Utc::now())
+        (self.observed_at + Duration::minutes(Self::VALIDITY_MINS as i64) - Utc::now())
             .to_std()
             .unwrap_or_else(|_| std::time::Duration::from_secs(0))
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+/// Why an asset could not be priced in a quote asset.
+pub enum PriceUnavailableReason {
+    /// The asset is not in the market-data catalog.
+    UnknownAsset,
+    /// No priced market, direct or through pivots, connects the two assets.
+    PriceNotAvailable,
+}
+
 #[derive(Debug, Clone, Copy, strum_macros::Display)]
 /// Supported intervals for historical OHLC market-data queries.
 pub enum OHLCFrequency {
@@ -127,6 +171,14 @@ pub enum OHLCFrequency {
 }
 
 impl OHLCFrequency {
+    /// Returns the duration of a candle.
+    pub fn period(&self) -> Duration {
+        match self {
+            OHLCFrequency::Minutes5 => Duration::minutes(5),
+            OHLCFrequency::Daily => Duration::days(1),
+        }
+    }
+
     /// Returns the historical range needed to cover the requested interval.
     pub fn ohlc_range(&self, ts: DateTime) -> (DateTime, DateTime) {
         match self {
@@ -170,6 +222,26 @@ impl OHLCFrequency {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+/// Order constraints an exchange enforces on a market.
+pub struct TradingRules {
+    /// Decimals allowed in an order quantity, in base units.
+    pub lot_decimals: Option<u32>,
+    /// Smallest order quantity, in base units.
+    pub min_quantity: Option<Decimal>,
+    /// Smallest order cost, in quote units.
+    pub min_cost: Option<Decimal>,
+    /// Decimals allowed in an order price, in quote units.
+    pub price_decimals: Option<u32>,
+}
+
+impl TradingRules {
+    /// Returns the smallest orderable quantity increment, in base units.
+    pub fn quantity_step(&self) -> Option<Decimal> {
+        self.lot_decimals.map(|d| Decimal::new(1, d))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 /// A base/quote market with an optional cached price observation.
 pub struct Market {
@@ -179,6 +251,8 @@ pub struct Market {
     pub quote: Asset,
     #[serde(flatten)]
     price: Option<Price>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trading_rules: Option<TradingRules>,
 }
 
 impl Market {
@@ -190,9 +264,20 @@ impl Market {
             base,
             quote,
             price,
+            trading_rules: None,
         }
     }
 
+    /// Returns the exchange order constraints, if the provider published them.
+    pub fn trading_rules(&self) -> Option<&TradingRules> {
+        self.trading_rules.as_ref()
+    }
+
+    /// Replaces the exchange order constraints.
+    pub fn set_trading_rules(&mut self, rules: Option<TradingRules>) {
+        self.trading_rules = rules;
+    }
+
     /// Returns the latest cached price, if one is available.
     pub fn price(&self) -> &Option<Price> {
         &self.price
@@ -208,6 +293,15 @@ impl Market {
         self.base.is_fiat() && self.quote.is_fiat()
     }
 
+    /// Returns the asset kind selecting the market's price provider chain.
+    pub fn kind(&self) -> AssetKind {
+        if self.is_fiat() {
+            AssetKind::Fiat
+        } else {
+            AssetKind::Crypto
+        }
+    }
+
     /// Returns whether the cached price has crossed its five-minute validity window.
     pub fn is_price_outdated(&self) -> bool {
         let last_price = self.price();
@@ -227,3 +321,349 @@ impl Expiring for Market {
             .unwrap_or_else(|| std::time::Duration::from_secs(0))
     }
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+/// Sampling interval of a stored price series.
+///
+/// Each sample is keyed by the provider's bucket open time in UTC. A bucket is
+/// only stored once it has closed, so the provider's running candle never
+/// reaches the series.
+pub enum SeriesTimeframe {
+    Daily,
+    Weekly,
+}
+
+impl SeriesTimeframe {
+    /// Number of buckets fetched when a series is first ingested. It matches
+    /// the deepest history Kraken returns in a single OHLC response.
+    const BACKFILL_BUCKETS: i32 = 720;
+
+    /// Returns the length of one sampling bucket.
+    pub fn period(&self) -> Duration {
+        match self {
+            SeriesTimeframe::Daily => Duration::days(1),
+            SeriesTimeframe::Weekly => Duration::weeks(1),
+        }
+    }
+
+    /// Returns how far back a first ingestion reaches.
+    pub fn backfill_depth(&self) -> Duration {
+        self.period() * Self::BACKFILL_BUCKETS
+    }
+
+    /// Returns whether the bucket opened at `observed_at` has closed by `now`.
+    pub fn is_closed(&self, observed_at: DateTime, now: DateTime) -> bool {
+        observed_at + self.period() <= now
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Provider-bound identity of a price series.
+///
+/// A ticker alone is ambiguous across providers and exchanges, so a series is
+/// identified by the provider, the provider's instrument symbol, and the
+/// exchange or market where the provider exposes one (empty otherwise).
+pub struct SeriesKey {
+    pub provider: PriceProvider,
+    pub instrument: String,
+    pub market: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A closed price-series sample as reported by a market data provider.
+pub struct SeriesObservation {
+    pub observed_at: DateTime,
+    pub value: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+/// How much of a requested range the stored price series covers.
+pub enum CoverageStatus {
+    /// Every expected bucket in the requested range has a stored sample.
+    Complete,
+    /// The series was ingested but the stored samples do not span the range.
+    Partial,
+    /// The series has not been ingested yet.
+    Pending,
+}
+
+impl CoverageStatus {
+    /// Classifies the coverage of `[from, to]` given the stored samples.
+    ///
+    /// `samples` must be ordered by observation time. The bucket still open at
+    /// `now` is never stored, so the range end is relaxed by one period.
+    pub fn of(
+        samples: &[SeriesObservation],
+        is_ingested: bool,
+        timeframe: SeriesTimeframe,
+        from: DateTime,
+        to: DateTime,
+        now: DateTime,
+    ) -> Self {
+        if !is_ingested {
+            return CoverageStatus::Pending;
+        }
+
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return CoverageStatus::Partial;
+        };
+
+        let period = timeframe.period();
+        let covers_start = first.observed_at <= from + period;
+        let covers_end = last.observed_at + period * 2 >= to.min(now);
+
+        if covers_start && covers_end {
+            CoverageStatus::Complete
+        } else {
+            CoverageStatus::Partial
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+/// An official overnight reference rate.
+pub enum RateType {
+    /// The New York Fed Effective Federal Funds Rate.
+    FedEffr,
+    /// The ECB euro short-term rate (€STR).
+    EcbEstr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A reference rate observation as reported by its publisher.
+pub struct RateObservation {
+    pub rate_type: RateType,
+    /// The business day of the transactions the rate is computed from.
+    pub observed_on: NaiveDate,
+    /// The rate in percent, e.g. `3.63` for 3.63%.
+    pub percent: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A stored reference rate observation.
+///
+/// Publishers announce an observation on a later business day than the one
+/// it describes, so the two dates are kept apart.
+pub struct ReferenceRate {
+    pub observation: RateObservation,
+    /// When ingestion first found the observation published. It trails the
+    /// publisher announcement by at most one refresh interval.
+    pub published_at: DateTime,
+    /// When the observation was last retrieved from its publisher.
+    pub fetched_at: DateTime,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+/// Market-data work tracked in the durable fetch-job queue.
+pub enum FetchJobKind {
+    /// Discover new market assets and markets from the provider catalog.
+    Discover,
+    /// Refresh the latest price of every known market.
+    Reprice,
+    /// Ingest the missing samples of one price series.
+    Backfill,
+    /// Refresh the latest observation of every reference rate.
+    Rates,
+}
+
+impl FetchJobKind {
+    /// Returns how many times a job is attempted before it is marked failed.
+    pub fn max_attempts(&self) -> i32 {
+        match self {
+            FetchJobKind::Discover => 5,
+            FetchJobKind::Reprice => 3,
+            FetchJobKind::Backfill => 6,
+            FetchJobKind::Rates => 5,
+        }
+    }
+
+    /// Returns how long a failed job waits before its next attempt.
+    ///
+    /// The delay doubles after each attempt, starting from a kind-specific base
+    /// and capped so a failing job is still retried a few times a day.
+    pub fn retry_backoff(&self, attempts: i32) -> Duration {
+        let base = match self {
+            FetchJobKind::Discover => Duration::minutes(5),
+            FetchJobKind::Reprice => Duration::seconds(30),
+            FetchJobKind::Backfill => Duration::minutes(5),
+            FetchJobKind::Rates => Duration::minutes(5),
+        };
+        let exp = attempts.saturating_sub(1).clamp(0, 16) as u32;
+
+        (base * 2_i32.pow(exp)).min(Duration::hours(6))
+    }
+
+    /// Returns how long a running job may go without finishing before another
+    /// worker can reclaim it.
+    pub fn claim_lease(&self) -> Duration {
+        Duration::minutes(30)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+/// Lifecycle state of a fetch job.
+pub enum FetchJobState {
+    /// Waiting for its first attempt.
+    Queued,
+    /// Claimed by a worker.
+    Running,
+    /// Failed at least once and waiting for its `retry_after` time.
+    RetryWait,
+    /// Completed successfully.
+    Succeeded,
+    /// Exhausted its attempts.
+    Failed,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rust_decimal::dec;
+
+    use super::*;
+
+    fn daily_samples(from: DateTime, days: i64) -> Vec<SeriesObservation> {
+        (0..days)
+            .map(|d| SeriesObservation {
+                observed_at: from + Duration::days(d),
+                value: dec!(1),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn price_time_to_live_follows_observation_age() {
+        let now = Utc::now();
+
+        let stale = Price::new(1., now - Duration::days(1), now);
+        assert!(stale.is_outdated());
+        assert_eq!(stale.time_to_live(), std::time::Duration::ZERO);
+
+        let fresh = Price::new(1., now, now);
+        assert!(fresh.time_to_live() > std::time::Duration::from_secs(4 * 60));
+    }
+
+    #[test]
+    fn price_reads_legacy_timestamps() {
+        let price: Price = serde_json::from_str(r#"{"price":2.5,"ts":1772409600}"#).unwrap();
+
+        let observed_at = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+        assert_eq!(price.observed_at, observed_at);
+        assert_eq!(price.fetched_at, DateTime::UNIX_EPOCH);
+        assert_eq!(price.provider, None);
+
+        let json = serde_json::to_value(price.with_provider(PriceProvider::Kraken)).unwrap();
+        assert_eq!(json["observedAt"], 1772409600);
+        assert_eq!(json["provider"], "kraken");
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_cap() {
+        let kind = FetchJobKind::Discover;
+
+        assert_eq!(kind.retry_backoff(1), Duration::minutes(5));
+        assert_eq!(kind.retry_backoff(2), Duration::minutes(10));
+        assert_eq!(kind.retry_backoff(4), Duration::minutes(40));
+        assert_eq!(kind.retry_backoff(100), Duration::hours(6));
+    }
+
+    #[test]
+    fn open_bucket_is_not_closed() {
+        let open = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+
+        let now = open + Duration::hours(12);
+        assert!(!SeriesTimeframe::Daily.is_closed(open, now));
+        assert!(SeriesTimeframe::Daily.is_closed(open, open + Duration::days(1)));
+        assert!(!SeriesTimeframe::Weekly.is_closed(open, open + Duration::days(6)));
+    }
+
+    #[test]
+    fn coverage_is_pending_until_ingested() {
+        let from = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let to = from + Duration::days(10);
+
+        let status = CoverageStatus::of(&[], false, SeriesTimeframe::Daily, from, to, to);
+        assert_eq!(status, CoverageStatus::Pending);
+    }
+
+    #[test]
+    fn coverage_is_complete_when_samples_span_the_range() {
+        let from = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let to = from + Duration::days(10);
+        let samples = daily_samples(from, 10);
+
+        let status = CoverageStatus::of(&samples, true, SeriesTimeframe::Daily, from, to, to);
+        assert_eq!(status, CoverageStatus::Complete);
+    }
+
+    #[test]
+    fn coverage_is_partial_when_history_starts_late() {
+        let from = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let to = from + Duration::days(10);
+        let samples = daily_samples(from + Duration::days(5), 5);
+
+        let status = CoverageStatus::of(&samples, true, SeriesTimeframe::Daily, from, to, to);
+        assert_eq!(status, CoverageStatus::Partial);
+
+        let status = CoverageStatus::of(&[], true, SeriesTimeframe::Daily, from, to, to);
+        assert_eq!(status, CoverageStatus::Partial);
+    }
+}
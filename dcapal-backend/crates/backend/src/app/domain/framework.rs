@@ -0,0 +1,78 @@
+//! Allocation frameworks: published rules suggesting target weights.
+//!
+//! DcaPal owns the version of each framework it implements, so the meaning of
+//! guidance computed under a version never changes.
+
+use rust_decimal::{Decimal, dec};
+
+/// Version identifier of the The Bull short-rate framework.
+pub const THE_BULL_V1: &str = "the-bull-v1";
+
+/// Highest age accepted by the frameworks, in completed years.
+pub const MAX_AGE_YEARS: u32 = 120;
+
+/// The equity/defensive split suggested by `the-bull-v1`.
+///
+/// The source formula is `equity = 125 - age - 5 * risk_free_rate`, with the
+/// rate in percentage points, and the defensive share is its remainder. The
+/// source does not bound its result: clamping the equity share to `[0, 100]`
+/// is a DcaPal guardrail, reported by [`TheBullSplit::is_clamped`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TheBullSplit {
+    /// Equity share given by the source formula, in percent.
+    pub raw_equity_pct: Decimal,
+    /// Equity share after the guardrail, in percent.
+    pub equity_pct: Decimal,
+    /// Defensive share after the guardrail, in percent.
+    pub defensive_pct: Decimal,
+}
+
+impl TheBullSplit {
+    /// Computes the split for an investor aged `age_years` completed years,
+    /// given a risk-free rate in percent.
+    pub fn new(age_years: u32, risk_free_pct: Decimal) -> Self {
+        let raw_equity_pct = dec!(125) - Decimal::from(age_years) - dec!(5) * risk_free_pct;
+        let equity_pct = raw_equity_pct.clamp(Decimal::ZERO, Decimal::ONE_HUNDRED);
+
+        Self {
+            raw_equity_pct,
+            equity_pct,
+            defensive_pct: Decimal::ONE_HUNDRED - equity_pct,
+        }
+    }
+
+    /// Whether the guardrail changed the source formula result.
+    pub fn is_clamped(&self) -> bool {
+        self.raw_equity_pct != self.equity_pct
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bull_matches_the_source_example() {
+        let split = TheBullSplit::new(40, dec!(3.5));
+
+        assert_eq!(split.raw_equity_pct, dec!(67.5));
+        assert_eq!(split.equity_pct, dec!(67.5));
+        assert_eq!(split.defensive_pct, dec!(32.5));
+        assert!(!split.is_clamped());
+    }
+
+    #[test]
+    fn the_bull_clamps_out_of_range_splits() {
+        let split = TheBullSplit::new(18, dec!(0.5));
+        assert_eq!(split.raw_equity_pct, dec!(104.5));
+        assert_eq!(split.equity_pct, dec!(100));
+        assert_eq!(split.defensive_pct, dec!(0));
+        assert!(split.is_clamped());
+
+        let split = TheBullSplit::new(110, dec!(4));
+        assert_eq!(split.raw_equity_pct, dec!(-5));
+        assert_eq!(split.equity_pct, dec!(0));
+        assert_eq!(split.defensive_pct, dec!(100));
+        assert!(split.is_clamped());
+    }
+}
@@ -1,36 +1,152 @@
+use std::sync::Arc;
+
 use chrono::Utc;
 use tracing::{error, warn};
 
-use super::entity::{Market, Price};
-use crate::{config::PriceProvider, ports::outbound::adapter::PriceProviders};
+use super::entity::{Asset, AssetKind, Market, Price};
+use crate::{
+    DateTime, config,
+    error::{DcaError, Result},
+    ports::outbound::{
+        adapter::{MarketDataProvider, PriceProviders},
+        repository::market_data::MarketDataRepository,
+    },
+};
 
+/// Fetches the price of `market` from the provider chain configured for its
+/// asset kind, falling back to the next provider when one has no price or fails.
 pub async fn fetch_market_price(
     market: &Market,
     providers: &PriceProviders,
-    provider: PriceProvider,
+    config: &config::Providers,
+) -> Option<Price> {
+    let chain = providers.chain(&config.price_chain(market.kind()));
+    fetch_latest_price(market, &chain, Utc::now()).await
+}
+
+async fn fetch_latest_price(
+    market: &Market,
+    chain: &[Arc<dyn MarketDataProvider>],
+    now: DateTime,
 ) -> Option<Price> {
-    let now = Utc::now();
-    let price = match provider {
-        PriceProvider::CryptoWatch => providers.cw.fetch_market_price(market, now).await,
-        PriceProvider::Kraken => providers.kraken.fetch_market_price(market, now).await,
-        PriceProvider::Yahoo => providers.yahoo.fetch_market_price(market, now).await,
+    for provider in chain {
+        match provider.fetch_latest(market, now).await {
+            Ok(Some(px)) => return Some(px.with_provider(provider.id())),
+            Ok(None) => warn!(
+                "Cannot fetch {} price from {} for any frequency (ts={now})",
+                market.id,
+                provider.id()
+            ),
+            Err(e) => error!(
+                "Cannot fetch {} price from {} for any frequency (ts={now}): {e:?}",
+                market.id,
+                provider.id()
+            ),
+        }
+    }
+
+    None
+}
+
+/// Discovers new assets and markets from the first provider of the crypto
+/// chain able to serve them.
+pub async fn discover_markets(
+    repo: &MarketDataRepository,
+    providers: &PriceProviders,
+    config: &config::Providers,
+) -> Result<(Vec<Asset>, Vec<Market>)> {
+    let mut last_error = None;
+    for provider in providers.chain(&config.price_chain(AssetKind::Crypto)) {
+        match provider.discover(repo).await {
+            Ok(discovered) => return Ok(discovered),
+            Err(e) => {
+                warn!("Cannot discover markets from {}: {e:?}", provider.id());
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| DcaError::Generic("No market discovery provider configured".into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{
+        app::domain::entity::{Crypto, Fiat},
+        config::PriceProvider,
     };
 
-    match price {
-        Ok(Some(px)) => Some(Price::new(px, now)),
-        Ok(None) => {
-            warn!(
-                "Cannot fetch {} price for any frequency (ts={now})",
-                market.id
-            );
-            None
+    struct StubProvider {
+        id: PriceProvider,
+        price: Result<Option<f64>>,
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for StubProvider {
+        fn id(&self) -> PriceProvider {
+            self.id
         }
-        Err(e) => {
-            error!(
-                "Cannot fetch {} price for any frequency (ts={now}): {e:?}",
-                market.id
-            );
-            None
+
+        async fn fetch_latest(&self, _market: &Market, ts: DateTime) -> Result<Option<Price>> {
+            match &self.price {
+                Ok(px) => Ok(px.map(|px| Price::new(px, ts, ts))),
+                Err(e) => Err(DcaError::Generic(e.to_string())),
+            }
         }
     }
+
+    fn stub(id: PriceProvider, price: Result<Option<f64>>) -> Arc<dyn MarketDataProvider> {
+        Arc::new(StubProvider { id, price })
+    }
+
+    fn btcusd() -> Market {
+        Market::new(
+            "btcusd".into(),
+            Asset::Crypto(Crypto::new_with_id("btc".into())),
+            Asset::Fiat(Fiat::new("usd".into(), "USD".into())),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_next_provider() {
+        let chain = [
+            stub(PriceProvider::Kraken, Ok(None)),
+            stub(
+                PriceProvider::CryptoWatch,
+                Err(DcaError::Generic("down".into())),
+            ),
+            stub(PriceProvider::Yahoo, Ok(Some(42.))),
+        ];
+
+        let price = fetch_latest_price(&btcusd(), &chain, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(price.price, 42.);
+        assert_eq!(price.provider, Some(PriceProvider::Yahoo));
+    }
+
+    #[tokio::test]
+    async fn stops_at_first_provider_with_a_price() {
+        let chain = [
+            stub(PriceProvider::Kraken, Ok(Some(41.))),
+            stub(PriceProvider::Yahoo, Ok(Some(42.))),
+        ];
+
+        let price = fetch_latest_price(&btcusd(), &chain, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(price.provider, Some(PriceProvider::Kraken));
+
+        let chain = [stub(PriceProvider::Kraken, Ok(None))];
+        assert!(
+            fetch_latest_price(&btcusd(), &chain, Utc::now())
+                .await
+                .is_none()
+        );
+    }
 }
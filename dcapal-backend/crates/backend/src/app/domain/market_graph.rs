@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet};
+
+use super::entity::{AssetId, Market, MarketId, Price};
+use crate::config::PriceProvider;
+
+/// A priced market traversed in one direction: one unit of `from` is worth
+/// `price.price` units of `to`.
+#[derive(Debug, Clone)]
+struct Edge {
+    to: AssetId,
+    market: MarketId,
+    price: Price,
+}
+
+/// A conversion rate obtained by chaining priced markets.
+#[derive(Debug, Clone)]
+pub struct ConversionPath {
+    /// The chained rate. Its timestamps are those of the stalest market.
+    pub price: Price,
+    /// The markets traversed, in path order.
+    pub markets: Vec<MarketId>,
+}
+
+impl ConversionPath {
+    fn hops(&self) -> usize {
+        self.markets.len()
+    }
+}
+
+/// The graph of priced markets, each usable in both directions.
+#[derive(Debug, Default)]
+pub struct MarketGraph {
+    edges: HashMap<AssetId, Vec<Edge>>,
+}
+
+impl MarketGraph {
+    /// Builds the graph from `markets`, skipping the ones without a price.
+    pub fn new<'a>(markets: impl IntoIterator<Item = &'a Market>) -> Self {
+        let mut edges: HashMap<AssetId, Vec<Edge>> = HashMap::new();
+        for m in markets {
+            let Some(px) = m.price() else {
+                continue;
+            };
+
+            if px.price <= 0. || !px.price.is_finite() {
+                continue;
+            }
+
+            let (base, quote) = (m.base.id().clone(), m.quote.id().clone());
+            edges.entry(base.clone()).or_default().push(Edge {
+                to: quote.clone(),
+                market: m.id.clone(),
+                price: *px,
+            });
+            edges.entry(quote).or_default().push(Edge {
+                to: base,
+                market: m.id.clone(),
+                price: Price {
+                    price: 1. / px.price,
+                    ..*px
+                },
+            });
+        }
+
+        Self { edges }
+    }
+
+    /// Builds the graph from the `markets` last priced by `provider`.
+    pub fn with_provider<'a>(
+        markets: impl IntoIterator<Item = &'a Market>,
+        provider: PriceProvider,
+    ) -> Self {
+        Self::new(
+            markets
+                .into_iter()
+                .filter(|m| m.price().is_some_and(|px| px.provider == Some(provider))),
+        )
+    }
+
+    /// Returns the conversion path from `base` to `quote` over the `markets`
+    /// last priced by `provider` when they connect the two assets, over every
+    /// market otherwise.
+    pub fn find_preferred_path<'a>(
+        markets: impl IntoIterator<Item = &'a Market> + Clone,
+        provider: PriceProvider,
+        base: &AssetId,
+        quote: &AssetId,
+        max_hops: usize,
+    ) -> Option<ConversionPath> {
+        Self::with_provider(markets.clone(), provider)
+            .find_path(base, quote, max_hops)
+            .or_else(|| Self::new(markets).find_path(base, quote, max_hops))
+    }
+
+    /// Returns the conversion path from `base` to `quote` chaining at most
+    /// `max_hops` markets.
+    ///
+    /// Paths with fewer hops win. Among paths of the same length, the one
+    /// whose stalest market was observed most recently wins.
+    pub fn find_path(
+        &self,
+        base: &AssetId,
+        quote: &AssetId,
+        max_hops: usize,
+    ) -> Option<ConversionPath> {
+        let mut best: Option<ConversionPath> = None;
+        let mut visited = HashSet::from([base.clone()]);
+        let mut path = Vec::new();
+        self.visit(base, quote, max_hops, &mut visited, &mut path, &mut best);
+
+        best
+    }
+
+    fn visit<'a>(
+        &'a self,
+        from: &AssetId,
+        quote: &AssetId,
+        max_hops: usize,
+        visited: &mut HashSet<AssetId>,
+        path: &mut Vec<&'a Edge>,
+        best: &mut Option<ConversionPath>,
+    ) {
+        if from == quote {
+            let candidate = chain(path);
+            if best.as_ref().is_none_or(|b| is_better(&candidate, b)) {
+                *best = Some(candidate);
+            }
+            return;
+        }
+
+        // No longer path can beat the best one found so far
+        let hops_left = best.as_ref().map_or(max_hops, |b| b.hops().min(max_hops));
+        if path.len() >= hops_left {
+            return;
+        }
+
+        for edge in self.edges.get(from).into_iter().flatten() {
+            if !visited.insert(edge.to.clone()) {
+                continue;
+            }
+
+            path.push(edge);
+            self.visit(&edge.to, quote, max_hops, visited, path, best);
+            path.pop();
+            visited.remove(&edge.to);
+        }
+    }
+}
+
+fn chain(path: &[&Edge]) -> ConversionPath {
+    let first = path[0].price;
+    let price = path.iter().skip(1).fold(first, |acc, e| Price {
+        price: acc.price * e.price.price,
+        observed_at: acc.observed_at.min(e.price.observed_at),
+        fetched_at: acc.fetched_at.min(e.price.fetched_at),
+        // A chained rate keeps its provider only when every market shares it
+        provider: acc.provider.filter(|p| e.price.provider == Some(*p)),
+    });
+
+    ConversionPath {
+        price,
+        markets: path.iter().map(|e| e.market.clone()).collect(),
+    }
+}
+
+fn is_better(candidate: &ConversionPath, best: &ConversionPath) -> bool {
+    (
+        candidate.hops(),
+        std::cmp::Reverse(candidate.price.observed_at),
+    ) < (best.hops(), std::cmp::Reverse(best.price.observed_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::{
+        DateTime,
+        app::domain::entity::{Asset, Crypto, Fiat},
+    };
+
+    fn crypto(id: &str) -> Asset {
+        Asset::Crypto(Crypto::new_with_id(id.into()))
+    }
+
+    fn fiat(id: &str) -> Asset {
+        Asset::Fiat(Fiat::new(id.into(), id.to_uppercase()))
+    }
+
+    fn market(base: Asset, quote: Asset, price: f64, observed_at: DateTime) -> Market {
+        let id = format!("{}{}", base.id(), quote.id());
+        Market::new(
+            id,
+            base,
+            quote,
+            Some(Price::new(price, observed_at, observed_at)),
+        )
+    }
+
+    #[test]
+    fn chains_markets_through_pivots() {
+        let now = Utc::now();
+        let markets = [
+            market(crypto("alt"), crypto("btc"), 0.001, now),
+            market(
+                crypto("btc"),
+                fiat("eur"),
+                50_000.,
+                now - Duration::minutes(3),
+            ),
+            market(fiat("usd"), fiat("chf"), 0.9, now),
+            market(fiat("eur"), fiat("usd"), 1.1, now),
+        ];
+        let graph = MarketGraph::new(&markets);
+
+        let path = graph.find_path(&"alt".into(), &"eur".into(), 3).unwrap();
+        assert_eq!(path.markets, vec!["altbtc", "btceur"]);
+        assert!((path.price.price - 50.).abs() < 1e-9);
+        assert_eq!(path.price.observed_at, now - Duration::minutes(3));
+        assert_eq!(path.price.provider, None);
+
+        let path = graph.find_path(&"chf".into(), &"eur".into(), 3).unwrap();
+        assert_eq!(path.markets, vec!["usdchf", "eurusd"]);
+        assert!((path.price.price - 1. / 0.9 / 1.1).abs() < 1e-9);
+
+        assert!(graph.find_path(&"alt".into(), &"chf".into(), 3).is_none());
+        assert!(graph.find_path(&"alt".into(), &"chf".into(), 4).is_some());
+    }
+
+    #[test]
+    fn prefers_fewer_hops_then_fresher_prices() {
+        let now = Utc::now();
+        let stale = now - Duration::hours(1);
+        let markets = [
+            market(crypto("alt"), fiat("usd"), 2., stale),
+            market(crypto("alt"), fiat("eur"), 1.8, now),
+            market(crypto("alt"), crypto("btc"), 0.0001, stale),
+            market(fiat("eur"), fiat("usd"), 1.1, now),
+            market(crypto("btc"), fiat("chf"), 18_000., stale),
+            market(crypto("btc"), fiat("eur"), 20_000., stale),
+            market(fiat("usd"), fiat("chf"), 0.9, now),
+        ];
+        let graph = MarketGraph::new(&markets);
+
+        // A direct market wins even when it is stale
+        let path = graph.find_path(&"alt".into(), &"usd".into(), 3).unwrap();
+        assert_eq!(path.markets, vec!["altusd"]);
+
+        // Between two-hop paths, the one with the freshest stalest leg wins
+        let path = graph.find_path(&"eur".into(), &"chf".into(), 3).unwrap();
+        assert_eq!(path.markets, vec!["eurusd", "usdchf"]);
+
+        let path = graph.find_path(&"alt".into(), &"chf".into(), 3).unwrap();
+        assert_eq!(path.hops(), 2);
+        assert_eq!(path.price.observed_at, stale);
+    }
+
+    #[test]
+    fn restricts_to_markets_of_a_provider() {
+        let now = Utc::now();
+        let ecb = |mut m: Market| {
+            let px = m.price().unwrap().with_provider(PriceProvider::Ecb);
+            m.set_price(px);
+            m
+        };
+        let markets = [
+            market(fiat("usd"), fiat("sek"), 10., now),
+            ecb(market(fiat("eur"), fiat("usd"), 1.1, now)),
+            ecb(market(fiat("eur"), fiat("sek"), 11.0425, now)),
+        ];
+
+        let path = MarketGraph::new(&markets)
+            .find_path(&"usd".into(), &"sek".into(), 3)
+            .unwrap();
+        assert_eq!(path.markets, vec!["usdsek"]);
+        assert_eq!(path.price.provider, None);
+
+        let path = MarketGraph::with_provider(&markets, PriceProvider::Ecb)
+            .find_path(&"usd".into(), &"sek".into(), 3)
+            .unwrap();
+        assert_eq!(path.markets, vec!["eurusd", "eursek"]);
+        assert!((path.price.price - 11.0425 / 1.1).abs() < 1e-9);
+        assert_eq!(path.price.provider, Some(PriceProvider::Ecb));
+    }
+
+    #[test]
+    fn prefers_markets_of_a_provider_when_they_connect() {
+        let now = Utc::now();
+        let ecb = |mut m: Market| {
+            let px = m.price().unwrap().with_provider(PriceProvider::Ecb);
+            m.set_price(px);
+            m
+        };
+        let markets = [
+            market(fiat("usd"), fiat("sek"), 10., now),
+            market(fiat("usd"), fiat("try"), 32., now),
+            ecb(market(fiat("eur"), fiat("usd"), 1.1, now)),
+            ecb(market(fiat("eur"), fiat("sek"), 11.0425, now)),
+        ];
+
+        let path = MarketGraph::find_preferred_path(
+            &markets,
+            PriceProvider::Ecb,
+            &"usd".into(),
+            &"sek".into(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(path.markets, vec!["eurusd", "eursek"]);
+        assert_eq!(path.price.provider, Some(PriceProvider::Ecb));
+
+        // No ECB rate reaches TRY: fall back to the other markets
+        let path = MarketGraph::find_preferred_path(
+            &markets,
+            PriceProvider::Ecb,
+            &"eur".into(),
+            &"try".into(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(path.markets, vec!["eurusd", "usdtry"]);
+        assert!((path.price.price - 1.1 * 32.).abs() < 1e-9);
+        assert_eq!(path.price.provider, None);
+    }
+}
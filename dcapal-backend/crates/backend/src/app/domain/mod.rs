@@ -1,2 +1,4 @@
 pub mod entity;
+pub mod framework;
 pub mod market_data_utils;
+pub mod market_graph;
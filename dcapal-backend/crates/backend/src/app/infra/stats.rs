@@ -24,6 +24,7 @@ pub const VISITORS_TOTAL: &str = concatcp!(BASE, '_', "visitors_total");
 pub const REQUESTS_TOTAL: &str = concatcp!(BASE, '_', "requests_total");
 pub const LATENCY_SUMMARY: &str = concatcp!(BASE, '_', "latency_summary");
 pub const IMPORTED_PORTFOLIOS_TOTAL: &str = concatcp!(BASE, '_', "imported_portfolios_total");
+pub const FETCH_JOBS: &str = concatcp!(BASE, '_', "fetch_jobs");
 
 pub async fn latency_stats(req: Request, next: Next) -> Response {
     let path = req.uri().path().to_string();
@@ -0,0 +1,151 @@
+use dcapal_optimizer_wasm::optimize::{self, OptimizeError, advanced::Solution};
+use tokio::task::JoinError;
+use tracing::debug;
+
+use crate::{
+    app::services::{command::AllocateCmd, solve_blocking},
+    error::DcaError,
+};
+
+/// Errors raised while computing an allocation.
+#[derive(Debug, thiserror::Error)]
+pub enum AllocationServiceError {
+    #[error("allocation failed")]
+    Optimize(#[from] OptimizeError),
+    #[error("allocation task failed")]
+    Task(#[from] JoinError),
+}
+
+impl From<AllocationServiceError> for DcaError {
+    fn from(e: AllocationServiceError) -> Self {
+        match e {
+            AllocationServiceError::Optimize(OptimizeError::InvalidInput(msg)) => {
+                DcaError::BadRequest(msg)
+            }
+            AllocationServiceError::Optimize(e) => DcaError::Generic(e.to_string()),
+            AllocationServiceError::Task(e) => DcaError::Generic(e.to_string()),
+        }
+    }
+}
+
+/// Computes allocation recommendations with the advanced optimizer.
+#[derive(Default)]
+pub struct AllocationService;
+
+impl AllocationService {
+    /// Creates an allocation service.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Splits the command budget across its portfolio assets.
+    pub async fn allocate(&self, cmd: AllocateCmd) -> Result<Solution, AllocationServiceError> {
+        let options = cmd.options;
+        let pfolio_ccy = options.pfolio_ccy.clone();
+        let solution = solve_blocking(move || optimize::solve_advanced(&options)).await??;
+
+        debug!(
+            "Allocated {} assets in {} (budget_left={})",
+            solution.assets.len(),
+            pfolio_ccy,
+            solution.budget_left
+        );
+
+        Ok(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcapal_optimizer_wasm::optimize::{
+        FeeStructure, FeeStructureFixed, TransactionFees, advanced::ProblemAsset,
+    };
+    use rust_decimal::{Decimal, dec};
+
+    use super::*;
+    use crate::app::services::command::AllocationParams;
+
+    fn asset(symbol: &str, shares: Decimal, price: Decimal, weight: Decimal) -> ProblemAsset {
+        ProblemAsset {
+            symbol: symbol.to_string(),
+            shares,
+            price,
+            target_weight: weight,
+            is_whole_shares: true,
+            fees: None,
+            quantity_step: None,
+            min_quantity: None,
+            min_order_amount: None,
+            average_buy_price: None,
+            tolerance_band: None,
+            price_ccy: None,
+        }
+    }
+
+    fn params(budget: Decimal) -> AllocationParams {
+        AllocationParams {
+            budget,
+            is_buy_only: true,
+            use_all_budget: false,
+            capital_gains_tax: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn allocates_budget_towards_target_weights() {
+        let assets = vec![
+            asset("VWCE", dec!(10.7), dec!(100), dec!(0.5)),
+            asset("AGGH", dec!(0), dec!(50), dec!(0.5)),
+        ];
+        let cmd = AllocateCmd::try_new(params(dec!(1000)), "eur", assets, None).unwrap();
+
+        // Whole-share holdings are truncated before solving
+        assert_eq!(cmd.options.assets["VWCE"].shares, dec!(10));
+        assert_eq!(cmd.options.current_pfolio_amount, dec!(1000));
+
+        let solution = AllocationService::new().allocate(cmd).await.unwrap();
+        assert_eq!(solution.assets["VWCE"].shares, dec!(10));
+        assert_eq!(solution.assets["AGGH"].shares, dec!(20));
+        assert_eq!(solution.budget_left, dec!(0));
+    }
+
+    #[test]
+    fn rejects_invalid_problems() {
+        let invalid = [
+            (
+                params(dec!(-1)),
+                vec![asset("VWCE", dec!(1), dec!(100), dec!(1))],
+                None,
+            ),
+            (
+                params(dec!(100)),
+                vec![asset("VWCE", dec!(1), dec!(100), dec!(0.9))],
+                None,
+            ),
+            (
+                params(dec!(100)),
+                vec![
+                    asset("VWCE", dec!(1), dec!(100), dec!(0.5)),
+                    asset("VWCE", dec!(1), dec!(100), dec!(0.5)),
+                ],
+                None,
+            ),
+            (
+                params(dec!(100)),
+                vec![asset("VWCE", dec!(1), dec!(100), dec!(1))],
+                Some(TransactionFees {
+                    max_fee_impact: dec!(1.5),
+                    fee_structure: FeeStructure::Fixed(FeeStructureFixed {
+                        fee_amount: dec!(1),
+                    }),
+                    sell_fee_structure: None,
+                }),
+            ),
+        ];
+
+        for (params, assets, fees) in invalid {
+            let cmd = AllocateCmd::try_new(params, "eur", assets, fees);
+            assert!(matches!(cmd, Err(DcaError::BadRequest(_))));
+        }
+    }
+}
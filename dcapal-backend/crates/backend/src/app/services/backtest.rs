@@ -0,0 +1,84 @@
+use std::{collections::HashMap, sync::Arc};
+
+use dcapal_optimizer_wasm::optimize::{
+    self, OptimizeError,
+    backtest::{ProblemOptions, Solution},
+};
+use tokio::task::JoinError;
+use tracing::debug;
+
+use crate::{
+    app::services::{
+        command::BacktestCmd,
+        load_price_samples,
+        price_series::{PriceSeriesService, PriceSeriesServiceError},
+        solve_blocking,
+    },
+    error::DcaError,
+};
+
+/// Errors raised while backtesting a DCA strategy.
+#[derive(Debug, thiserror::Error)]
+pub enum BacktestServiceError {
+    #[error("backtest failed")]
+    Optimize(#[from] OptimizeError),
+    #[error("price series unavailable")]
+    Series(#[from] PriceSeriesServiceError),
+    #[error("backtest task failed")]
+    Task(#[from] JoinError),
+}
+
+impl From<BacktestServiceError> for DcaError {
+    fn from(e: BacktestServiceError) -> Self {
+        match e {
+            BacktestServiceError::Optimize(OptimizeError::InvalidInput(msg)) => {
+                DcaError::BadRequest(msg)
+            }
+            BacktestServiceError::Optimize(e) => DcaError::Generic(e.to_string()),
+            BacktestServiceError::Series(e) => e.into(),
+            BacktestServiceError::Task(e) => DcaError::Generic(e.to_string()),
+        }
+    }
+}
+
+/// Replays DCA strategies over the stored daily price series.
+pub struct BacktestService {
+    price_series: Arc<PriceSeriesService>,
+}
+
+impl BacktestService {
+    /// Creates a backtest service reading prices from `price_series`.
+    pub fn new(price_series: Arc<PriceSeriesService>) -> Self {
+        Self { price_series }
+    }
+
+    /// Loads the price series of the command assets and replays its
+    /// contributions over them.
+    ///
+    /// Series not ingested yet have no samples: the backtest is rejected as
+    /// missing their prices.
+    pub async fn backtest(&self, cmd: BacktestCmd) -> Result<Solution, BacktestServiceError> {
+        let mut prices = HashMap::with_capacity(cmd.series.len());
+        for (symbol, query) in cmd.series {
+            let samples = load_price_samples(&self.price_series, query).await?;
+            prices.insert(symbol, samples);
+        }
+
+        let options = ProblemOptions {
+            advanced: cmd.options,
+            prices,
+            interval_days: cmd.interval_days,
+        };
+        let assets = options.advanced.assets.len();
+        let solution = solve_blocking(move || optimize::solve_backtest(&options)).await??;
+
+        debug!(
+            "Backtested {} assets over {} dates ({} contributions)",
+            assets,
+            solution.points.len(),
+            solution.contributions
+        );
+
+        Ok(solution)
+    }
+}
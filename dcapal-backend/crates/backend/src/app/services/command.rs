@@ -1,9 +1,29 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use chrono::Utc;
+use dcapal_optimizer_wasm::optimize::{TransactionFees, advanced, projection};
 use jsonschema::Validator;
+use rust_decimal::Decimal;
+use uuid::Uuid;
 
 use crate::{
-    app::domain::entity::{Asset, AssetId},
+    DateTime,
+    app::domain::{
+        entity::{Asset, AssetId, RateType, SeriesKey, SeriesTimeframe},
+        framework::MAX_AGE_YEARS,
+    },
+    config::PriceProvider,
     error::{DcaError, Result},
-    ports::outbound::repository::market_data::MarketDataRepository,
+    ports::{
+        inbound::rest,
+        outbound::{
+            adapter::{AsYahooMarket, yahoo_market_id},
+            repository::{market_data::MarketDataRepository, portfolio::PortfolioRepository},
+        },
+    },
 };
 
 pub struct ConversionRateQuery {
@@ -34,6 +54,54 @@ impl ConversionRateQuery {
     }
 }
 
+pub struct ConversionRatesQuery {
+    pub bases: Vec<AssetId>,
+    pub quote: Asset,
+}
+
+impl ConversionRatesQuery {
+    /// Maximum number of assets priced by a single query.
+    pub const MAX_ASSETS: usize = 250;
+
+    /// Validates the quote asset and deduplicates the base assets.
+    ///
+    /// Unknown base assets are not rejected here: they are reported per asset.
+    pub async fn try_new<S: AsRef<str>>(
+        bases: &[S],
+        quote: &str,
+        repo: &MarketDataRepository,
+    ) -> Result<Self> {
+        let mut unique = HashSet::new();
+        let bases = bases
+            .iter()
+            .map(|b| b.as_ref().trim())
+            .filter(|b| !b.is_empty())
+            .filter(|b| unique.insert(b.to_string()))
+            .map(AssetId::from)
+            .collect::<Vec<_>>();
+
+        if bases.is_empty() {
+            return Err(DcaError::BadRequest("No assets to price".to_string()));
+        }
+        if bases.len() > Self::MAX_ASSETS {
+            return Err(DcaError::BadRequest(format!(
+                "Too many assets to price: {} (max {})",
+                bases.len(),
+                Self::MAX_ASSETS
+            )));
+        }
+
+        let quote = AssetId::from(quote);
+        let Some(quote) = repo.find_asset(&quote).await? else {
+            return Err(DcaError::BadRequest(format!(
+                "Unknown quote asset: {quote}"
+            )));
+        };
+
+        Ok(Self { bases, quote })
+    }
+}
+
 pub struct ImportPortfolioCmd {
     pub pfolio: serde_json::Value,
 }
@@ -49,3 +117,450 @@ impl ImportPortfolioCmd {
         Ok(Self { pfolio: payload })
     }
 }
+
+pub struct PriceSeriesQuery {
+    pub key: SeriesKey,
+    /// Currency the series prices are quoted in.
+    pub quote: AssetId,
+    pub timeframe: SeriesTimeframe,
+    pub from: DateTime,
+    pub to: DateTime,
+}
+
+impl PriceSeriesQuery {
+    pub async fn try_new(
+        provider: &str,
+        symbol: &str,
+        market: Option<String>,
+        timeframe: SeriesTimeframe,
+        from: Option<DateTime>,
+        to: Option<DateTime>,
+        repo: &MarketDataRepository,
+    ) -> Result<Self> {
+        let provider = match PriceProvider::from_str(provider) {
+            Ok(p @ (PriceProvider::Kraken | PriceProvider::Yahoo)) => p,
+            _ => {
+                return Err(DcaError::BadRequest(format!(
+                    "Unsupported series provider: {provider}"
+                )));
+            }
+        };
+
+        let instrument = symbol.trim().to_lowercase();
+        if instrument.is_empty() {
+            return Err(DcaError::BadRequest("Empty series symbol".to_string()));
+        }
+
+        // Only series of known markets are registered for ingestion, so that
+        // requests cannot queue work for arbitrary instruments. Kraken
+        // instruments are the markets discovered from its catalog, Yahoo ones
+        // the symbols the known markets are priced under.
+        let known = match provider {
+            PriceProvider::Kraken => repo.find_market(&instrument).await?,
+            _ => match yahoo_market_id(&instrument) {
+                Some(id) => repo
+                    .find_market(&id)
+                    .await?
+                    .filter(|m| m.as_yahoo() == instrument),
+                None => None,
+            },
+        };
+        let Some(quote) = known.map(|m| m.quote.id().clone()) else {
+            return Err(DcaError::BadRequest(format!(
+                "Unknown {provider} market: {instrument}"
+            )));
+        };
+
+        // Neither provider exposes several exchanges per instrument
+        let market = market.unwrap_or_default().trim().to_string();
+        if !market.is_empty() {
+            return Err(DcaError::BadRequest(format!(
+                "Unsupported {provider} series market: {market}"
+            )));
+        }
+
+        let to = to.unwrap_or_else(Utc::now);
+        let from = from.unwrap_or(to - timeframe.backfill_depth());
+        if from >= to {
+            return Err(DcaError::BadRequest(format!(
+                "Invalid series range: from ({from}) must precede to ({to})"
+            )));
+        }
+
+        Ok(PriceSeriesQuery {
+            key: SeriesKey {
+                provider,
+                instrument,
+                market,
+            },
+            quote,
+            timeframe,
+            from,
+            to,
+        })
+    }
+}
+
+/// Highest number of price series a single request may read.
+pub const MAX_REQUEST_SERIES: usize = 20;
+
+fn check_series_count(count: usize) -> Result<()> {
+    if count > MAX_REQUEST_SERIES {
+        return Err(DcaError::BadRequest(format!(
+            "Too many price series: {count} (max {MAX_REQUEST_SERIES})"
+        )));
+    }
+
+    Ok(())
+}
+
+/// How a budget should be invested, independently of the portfolio it goes to.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocationParams {
+    pub budget: Decimal,
+    pub is_buy_only: bool,
+    pub use_all_budget: bool,
+    pub capital_gains_tax: Option<advanced::CapitalGainsTax>,
+}
+
+pub struct AllocateCmd {
+    pub options: advanced::ProblemOptions,
+}
+
+impl AllocateCmd {
+    /// Validates an allocation problem submitted in full by the client.
+    pub fn try_new(
+        params: AllocationParams,
+        pfolio_ccy: &str,
+        assets: Vec<advanced::ProblemAsset>,
+        fees: Option<TransactionFees>,
+    ) -> Result<Self> {
+        let mut problem_assets = HashMap::with_capacity(assets.len());
+        for asset in assets {
+            if problem_assets.contains_key(&asset.symbol) {
+                return Err(DcaError::BadRequest(format!(
+                    "Duplicate asset symbol: {}",
+                    asset.symbol
+                )));
+            }
+            problem_assets.insert(asset.symbol.clone(), asset);
+        }
+
+        let options = advanced::ProblemOptions {
+            pfolio_ccy: pfolio_ccy.to_string(),
+            current_pfolio_amount: Decimal::ZERO,
+            assets: problem_assets,
+            budget: params.budget,
+            fees: fees.unwrap_or_default(),
+            is_buy_only: params.is_buy_only,
+            use_all_budget: params.use_all_budget,
+            capital_gains_tax: params.capital_gains_tax,
+            tolerance_band: None,
+            groups: Vec::new(),
+            fx_rates: HashMap::new(),
+            fx_fees: HashMap::new(),
+        }
+        .validated()
+        .map_err(|e| DcaError::BadRequest(e.to_string()))?;
+
+        Ok(Self { options })
+    }
+
+    /// Resolves a saved portfolio owned by `user_id` into an allocation problem.
+    ///
+    /// Asset prices are the ones last saved with the portfolio. Equities are
+    /// bought in whole shares unless `use_whole_shares` is off.
+    pub async fn try_new_for_portfolio(
+        user_id: Uuid,
+        portfolio_id: Uuid,
+        params: AllocationParams,
+        use_whole_shares: bool,
+        portfolios: &dyn PortfolioRepository,
+    ) -> Result<Self> {
+        let Some((portfolio, assets)) = portfolios
+            .find_user_portfolio_with_assets(user_id, portfolio_id)
+            .await?
+        else {
+            return Err(DcaError::NotFound(format!(
+                "Portfolio {portfolio_id} not found"
+            )));
+        };
+
+        let fees = stored_fees(portfolio.max_fee_impact, portfolio.stored_fee_structure()?);
+
+        let assets = assets
+            .into_iter()
+            .map(|a| {
+                let fees = stored_fees(a.max_fee_impact, a.stored_fee_structure()?);
+
+                Ok(advanced::ProblemAsset {
+                    is_whole_shares: use_whole_shares && is_whole_shares_class(&a.asset_class),
+                    symbol: a.symbol,
+                    shares: a.quantity,
+                    price: a.price,
+                    target_weight: a.target_weight,
+                    fees,
+                    quantity_step: None,
+                    min_quantity: None,
+                    min_order_amount: None,
+                    average_buy_price: a.average_buy_price,
+                    tolerance_band: None,
+                    price_ccy: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::try_new(params, &portfolio.currency, assets, fees)
+    }
+}
+
+/// An asset replayed by a backtest, with the stored series of its prices.
+pub struct BacktestAsset {
+    /// The asset and its initial holding. Its price is replaced by the series.
+    pub asset: advanced::ProblemAsset,
+    /// Market data provider of the series.
+    pub provider: String,
+    /// Exchange or market of the series, when the provider exposes one.
+    pub market: Option<String>,
+}
+
+/// When a backtest contributes, and over which range.
+#[derive(Debug, Clone, Copy)]
+pub struct BacktestSchedule {
+    /// Days between two contributions.
+    pub interval_days: u32,
+    /// Range start. Defaults to the backfill depth of daily series.
+    pub from: Option<DateTime>,
+    /// Range end. Defaults to now.
+    pub to: Option<DateTime>,
+}
+
+/// Highest number of contributions a single backtest may replay.
+pub const MAX_BACKTEST_CONTRIBUTIONS: i64 = 1_000;
+
+pub struct BacktestCmd {
+    pub options: advanced::ProblemOptions,
+    pub interval_days: u32,
+    /// Daily price series of each asset, by symbol.
+    pub series: HashMap<String, PriceSeriesQuery>,
+}
+
+impl BacktestCmd {
+    /// Validates a backtest and resolves the series of its asset prices.
+    ///
+    /// `params.budget` is the amount contributed at each date of the schedule.
+    pub async fn try_new(
+        params: AllocationParams,
+        schedule: BacktestSchedule,
+        pfolio_ccy: &str,
+        assets: Vec<BacktestAsset>,
+        fees: Option<TransactionFees>,
+        repo: &MarketDataRepository,
+    ) -> Result<Self> {
+        check_series_count(assets.len())?;
+
+        if schedule.interval_days == 0 {
+            return Err(DcaError::BadRequest(
+                "Invalid contribution interval: must be at least one day".to_string(),
+            ));
+        }
+
+        // Resolved once, so that every series is read over the same range
+        let to = schedule.to.unwrap_or_else(Utc::now);
+        let from = schedule
+            .from
+            .unwrap_or(to - SeriesTimeframe::Daily.backfill_depth());
+        let contributions = (to - from).num_days() / i64::from(schedule.interval_days);
+        if contributions > MAX_BACKTEST_CONTRIBUTIONS {
+            return Err(DcaError::BadRequest(format!(
+                "Too many contributions: {contributions} (max {MAX_BACKTEST_CONTRIBUTIONS}). Shorten the range or widen the interval"
+            )));
+        }
+
+        let mut series = HashMap::with_capacity(assets.len());
+        let mut problem_assets = Vec::with_capacity(assets.len());
+        for a in assets {
+            let query = PriceSeriesQuery::try_new(
+                &a.provider,
+                &a.asset.symbol,
+                a.market,
+                SeriesTimeframe::Daily,
+                Some(from),
+                Some(to),
+                repo,
+            )
+            .await?;
+
+            // Series prices are replayed as they are, with no conversion
+            if !query.quote.eq_ignore_ascii_case(pfolio_ccy) {
+                return Err(DcaError::BadRequest(format!(
+                    "Series of {} is quoted in {}, not in portfolio currency {pfolio_ccy}",
+                    a.asset.symbol, query.quote
+                )));
+            }
+
+            series.insert(a.asset.symbol.clone(), query);
+            problem_assets.push(a.asset);
+        }
+
+        let options = AllocateCmd::try_new(params, pfolio_ccy, problem_assets, fees)?.options;
+
+        Ok(Self {
+            options,
+            interval_days: schedule.interval_days,
+            series,
+        })
+    }
+}
+
+/// An asset class of a projection.
+pub struct ProjectionClass {
+    pub name: String,
+    pub target_weight: Decimal,
+    pub returns: ProjectionReturns,
+}
+
+/// Where the return model of a projected asset class comes from.
+pub enum ProjectionReturns {
+    /// Set by the client.
+    Configured(projection::ReturnModel),
+    /// Estimated from the stored daily series of an instrument of the class.
+    Estimated {
+        provider: String,
+        symbol: String,
+        market: Option<String>,
+    },
+}
+
+/// The contribution plan of a projection.
+pub struct ProjectionParams {
+    pub initial_value: Decimal,
+    pub contribution: Decimal,
+    pub periods_per_year: u32,
+    pub years: u32,
+    pub paths: u32,
+    pub seed: u64,
+    pub goal: Option<Decimal>,
+}
+
+pub struct ProjectionCmd {
+    /// Projection options. Estimated classes hold a placeholder return model.
+    pub options: projection::ProblemOptions,
+    /// Daily price series of each estimated class, by class name.
+    pub series: HashMap<String, PriceSeriesQuery>,
+}
+
+impl ProjectionCmd {
+    /// Validates a projection and resolves the series its return models are
+    /// estimated from, over `from..to`.
+    pub async fn try_new(
+        params: ProjectionParams,
+        classes: Vec<ProjectionClass>,
+        from: Option<DateTime>,
+        to: Option<DateTime>,
+        repo: &MarketDataRepository,
+    ) -> Result<Self> {
+        let estimated = classes
+            .iter()
+            .filter(|c| matches!(c.returns, ProjectionReturns::Estimated { .. }))
+            .count();
+        check_series_count(estimated)?;
+
+        let mut series = HashMap::new();
+        let mut problem_classes = HashMap::with_capacity(classes.len());
+        for class in classes {
+            if problem_classes.contains_key(&class.name) {
+                return Err(DcaError::BadRequest(format!(
+                    "Duplicate asset class: {}",
+                    class.name
+                )));
+            }
+
+            let returns = match class.returns {
+                ProjectionReturns::Configured(returns) => returns,
+                ProjectionReturns::Estimated {
+                    provider,
+                    symbol,
+                    market,
+                } => {
+                    let query = PriceSeriesQuery::try_new(
+                        &provider,
+                        &symbol,
+                        market,
+                        SeriesTimeframe::Daily,
+                        from,
+                        to,
+                        repo,
+                    )
+                    .await?;
+                    series.insert(class.name.clone(), query);
+
+                    // Replaced by the series estimate
+                    projection::ReturnModel {
+                        expected_return: Decimal::ZERO,
+                        volatility: Decimal::ZERO,
+                    }
+                }
+            };
+
+            let problem_class = projection::AssetClass {
+                target_weight: class.target_weight,
+                returns,
+            };
+            problem_classes.insert(class.name, problem_class);
+        }
+
+        let options = projection::ProblemOptions {
+            classes: problem_classes,
+            initial_value: params.initial_value,
+            contribution: params.contribution,
+            periods_per_year: params.periods_per_year,
+            years: params.years,
+            paths: params.paths,
+            seed: params.seed,
+            goal: params.goal,
+        }
+        .validated()
+        .map_err(|e| DcaError::BadRequest(e.to_string()))?;
+
+        Ok(Self { options, series })
+    }
+}
+
+pub struct TheBullCmd {
+    pub age_years: u32,
+    pub rate_type: RateType,
+}
+
+impl TheBullCmd {
+    /// Validates the investor age, in completed years.
+    pub fn try_new(age_years: u32, rate_type: RateType) -> Result<Self> {
+        if age_years > MAX_AGE_YEARS {
+            return Err(DcaError::BadRequest(format!(
+                "Invalid age ({age_years}). Must be at most {MAX_AGE_YEARS} years"
+            )));
+        }
+
+        Ok(Self {
+            age_years,
+            rate_type,
+        })
+    }
+}
+
+/// Rebuilds a fee policy from its stored maximum impact and structure.
+fn stored_fees(
+    max_fee_impact: Option<Decimal>,
+    fee_structure: Option<rest::FeeStructure>,
+) -> Option<TransactionFees> {
+    fee_structure.map(|fee_structure| TransactionFees {
+        max_fee_impact: max_fee_impact.unwrap_or_else(TransactionFees::default_max_fee_impact),
+        fee_structure: fee_structure.into(),
+        sell_fee_structure: None,
+    })
+}
+
+/// Mirrors the frontend rule: only equities are bought in whole shares.
+fn is_whole_shares_class(asset_class: &str) -> bool {
+    matches!(asset_class.to_uppercase().as_str(), "EQUITY" | "STOCK")
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{Duration, Utc};
+use metrics::gauge;
+use strum::IntoEnumIterator;
+use tracing::{error, warn};
+
+use crate::{
+    app::{
+        domain::entity::{FetchJobKind, FetchJobState},
+        infra::stats,
+    },
+    error::DcaError,
+    ports::outbound::repository::{fetch_job::FetchJobRepository, postgres::types::FetchJobRow},
+};
+
+/// Errors raised while operating the fetch-job queue.
+#[derive(Debug, thiserror::Error)]
+pub enum FetchJobServiceError {
+    #[error("fetch job persistence failed")]
+    Persistence(#[from] DcaError),
+}
+
+impl From<FetchJobServiceError> for DcaError {
+    fn from(e: FetchJobServiceError) -> Self {
+        match e {
+            FetchJobServiceError::Persistence(e) => e,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, FetchJobServiceError>;
+
+/// Schedules, hands out and settles durable market-data fetch jobs.
+pub struct FetchJobService {
+    repo: Arc<dyn FetchJobRepository>,
+}
+
+impl FetchJobService {
+    /// How long succeeded jobs are kept for inspection.
+    const SUCCEEDED_RETENTION_DAYS: i64 = 7;
+
+    /// Creates a fetch-job service using the supplied persistence port.
+    pub fn new(repo: Arc<dyn FetchJobRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Queues a job for `dedup_key` unless one was already queued for it.
+    ///
+    /// Returns whether a new job was queued.
+    pub async fn schedule(
+        &self,
+        kind: FetchJobKind,
+        dedup_key: &str,
+        series_id: Option<uuid::Uuid>,
+    ) -> Result<bool> {
+        let job = self
+            .repo
+            .enqueue(kind, dedup_key, series_id, kind.max_attempts())
+            .await?;
+
+        Ok(job.is_some())
+    }
+
+    /// Claims the next runnable job of `kind`, if any.
+    pub async fn claim(&self, kind: FetchJobKind) -> Result<Option<FetchJobRow>> {
+        let now = Utc::now();
+        Ok(self.repo.claim(kind, now, now - kind.claim_lease()).await?)
+    }
+
+    /// Marks a claimed job as succeeded.
+    pub async fn succeed(&self, job: &FetchJobRow) -> Result<()> {
+        Ok(self.repo.succeed(job.id, Utc::now()).await?)
+    }
+
+    /// Records a failed attempt of a claimed job, retrying it with backoff
+    /// until it runs out of attempts.
+    pub async fn fail(&self, kind: FetchJobKind, job: &FetchJobRow, e: &DcaError) -> Result<()> {
+        let now = Utc::now();
+        let retry_after =
+            (job.attempts < job.max_attempts).then(|| now + kind.retry_backoff(job.attempts));
+
+        match retry_after {
+            Some(ts) => warn!(
+                "Fetch job {kind} '{}' failed (attempt {}/{}). Retrying at {ts}: {e:?}",
+                job.dedup_key, job.attempts, job.max_attempts
+            ),
+            None => error!(
+                "Fetch job {kind} '{}' failed after {} attempts: {e:?}",
+                job.dedup_key, job.attempts
+            ),
+        }
+
+        Ok(self
+            .repo
+            .fail(job.id, &format!("{e:?}"), retry_after, now)
+            .await?)
+    }
+
+    /// Deletes succeeded jobs past their retention period.
+    pub async fn prune_succeeded(&self) -> Result<u64> {
+        let before = Utc::now() - Duration::days(Self::SUCCEEDED_RETENTION_DAYS);
+        Ok(self.repo.prune_succeeded(before).await?)
+    }
+
+    /// Publishes the number of jobs per kind and state to Prometheus.
+    pub async fn refresh_metrics(&self) -> Result<()> {
+        let counts = self
+            .repo
+            .count_by_state()
+            .await?
+            .into_iter()
+            .map(|(kind, state, count)| ((kind, state), count))
+            .collect::<HashMap<_, _>>();
+
+        // Publish every combination so drained states drop back to zero
+        for kind in FetchJobKind::iter() {
+            for state in FetchJobState::iter() {
+                let key = (kind.to_string(), state.to_string());
+                let count = counts.get(&key).copied().unwrap_or_default();
+                gauge!(stats::FETCH_JOBS, &[("kind", key.0), ("state", key.1)]).set(count as f64);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::{
+    app::{
+        domain::{entity::ReferenceRate, framework::TheBullSplit},
+        services::{
+            command::TheBullCmd,
+            reference_rate::{ReferenceRateService, ReferenceRateServiceError},
+        },
+    },
+    error::DcaError,
+};
+
+/// Errors raised while computing allocation framework guidance.
+#[derive(Debug, thiserror::Error)]
+pub enum FrameworkServiceError {
+    #[error("reference rate unavailable")]
+    Rate(#[from] ReferenceRateServiceError),
+}
+
+impl From<FrameworkServiceError> for DcaError {
+    fn from(e: FrameworkServiceError) -> Self {
+        match e {
+            FrameworkServiceError::Rate(e) => e.into(),
+        }
+    }
+}
+
+/// Guidance computed by `the-bull-v1`, along with the rate it used.
+#[derive(Debug, Clone, Copy)]
+pub struct TheBullGuidance {
+    pub age_years: u32,
+    pub rate: ReferenceRate,
+    pub split: TheBullSplit,
+}
+
+/// Computes target weights suggested by allocation frameworks.
+pub struct FrameworkService {
+    rates: Arc<ReferenceRateService>,
+}
+
+impl FrameworkService {
+    /// Creates a framework service reading rates from `rates`.
+    pub fn new(rates: Arc<ReferenceRateService>) -> Self {
+        Self { rates }
+    }
+
+    /// Computes the `the-bull-v1` split with the latest observation of the
+    /// selected rate. Guidance fails when no recent observation is available:
+    /// it never falls back to another rate.
+    pub async fn the_bull_v1(
+        &self,
+        cmd: TheBullCmd,
+    ) -> Result<TheBullGuidance, FrameworkServiceError> {
+        let rate = self.rates.latest(cmd.rate_type).await?;
+        let split = TheBullSplit::new(cmd.age_years, rate.observation.percent);
+
+        debug!(
+            "Computed the-bull-v1 split for age {} with {:?}: {split:?}",
+            cmd.age_years, rate.observation
+        );
+
+        Ok(TheBullGuidance {
+            age_years: cmd.age_years,
+            rate,
+            split,
+        })
+    }
+}
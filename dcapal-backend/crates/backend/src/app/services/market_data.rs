@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use chrono::Utc;
 use parking_lot::RwLock;
@@ -6,13 +9,23 @@ use tracing::{error, info, warn};
 
 use crate::{
     app::{
-        domain::entity::{Asset, AssetId, AssetKind, Market, MarketId, Price},
-        services::command::ConversionRateQuery,
+        domain::{
+            entity::{Asset, AssetId, AssetKind, Market, MarketId, Price, PriceUnavailableReason},
+            market_graph::MarketGraph,
+        },
+        services::command::{ConversionRateQuery, ConversionRatesQuery},
     },
+    config::PriceProvider,
     error::{DcaError, Result},
     ports::outbound::repository::market_data::MarketDataRepository,
 };
 
+/// Intermediate assets a conversion rate may be computed through.
+const CONVERSION_PIVOTS: [&str; 4] = ["usd", "eur", "usdt", "btc"];
+
+/// Maximum number of markets chained to compute a conversion rate.
+const CONVERSION_MAX_HOPS: usize = 3;
+
 pub struct MarketDataService {
     repo: Arc<MarketDataRepository>,
     markets: RwLock<HashMap<MarketId, Arc<Market>>>,
@@ -83,6 +96,11 @@ impl MarketDataService {
         cache.fiats = None;
     }
 
+    /// Drops the cached copy of a market, so the next lookup reloads it.
+    pub fn invalidate_market(&self, id: &MarketId) {
+        self.markets.write().remove(id);
+    }
+
     /// Lookup a [`Market`] by [`MarketId`]
     pub async fn get_market(&self, id: &MarketId) -> Result<Option<Arc<Market>>> {
         {
@@ -157,7 +175,61 @@ impl MarketDataService {
     }
 
     pub async fn get_conversion_rate(&self, cmd: ConversionRateQuery) -> Result<Option<Price>> {
-        let (base, quote) = (cmd.base.id(), cmd.quote.id());
+        self.conversion_rate(cmd.base.id(), cmd.quote.id()).await
+    }
+
+    /// Prices every asset of `cmd` in its quote asset.
+    ///
+    /// The markets needed by uncached rates are loaded in a single repository
+    /// round trip. Assets that cannot be priced are reported with a reason.
+    pub async fn get_conversion_rates(
+        &self,
+        cmd: ConversionRatesQuery,
+    ) -> Result<Vec<(AssetId, std::result::Result<Price, PriceUnavailableReason>)>> {
+        let quote = cmd.quote.id();
+
+        let known = {
+            let crypto = self.get_assets_by_type(AssetKind::Crypto).await;
+            let fiat = self.get_assets_by_type(AssetKind::Fiat).await;
+            crypto
+                .iter()
+                .chain(fiat.iter())
+                .map(|a| a.id().clone())
+                .collect::<HashSet<_>>()
+        };
+
+        // Warm the market cache with every market the uncached rates may use
+        let uncached = {
+            let pricers = self.pricers.read();
+            cmd.bases
+                .iter()
+                .filter(|b| {
+                    known.contains(*b) && !pricers.contains_key(&((*b).clone(), quote.clone()))
+                })
+                .flat_map(|b| conversion_market_ids(b, quote))
+                .collect::<HashSet<_>>()
+        };
+        if !uncached.is_empty() {
+            self.get_markets(&uncached.into_iter().collect::<Vec<_>>())
+                .await?;
+        }
+
+        let mut rates = Vec::with_capacity(cmd.bases.len());
+        for base in cmd.bases {
+            let rate = if !known.contains(&base) {
+                Err(PriceUnavailableReason::UnknownAsset)
+            } else {
+                self.conversion_rate(&base, quote)
+                    .await?
+                    .ok_or(PriceUnavailableReason::PriceNotAvailable)
+            };
+            rates.push((base, rate));
+        }
+
+        Ok(rates)
+    }
+
+    async fn conversion_rate(&self, base: &AssetId, quote: &AssetId) -> Result<Option<Price>> {
         let pair = (base.clone(), quote.clone());
 
         {
@@ -192,135 +264,98 @@ impl MarketDataService {
     ) -> Result<Option<(Price, Vec<MarketId>)>> {
         // Base/base => 1.
         if base == quote {
-            return Ok(Some((Price::new(1., Utc::now()), vec![])));
+            let now = Utc::now();
+            return Ok(Some((Price::new(1., now, now), vec![])));
         }
 
         let base = normalized_asset(base);
         let quote = normalized_asset(quote);
 
-        // Find base/quote market
-        let id = format!("{base}{quote}");
-        let mkt = self.get_market(&id).await?;
-        if let Some(m) = mkt
-            && let Some(px) = m.price()
-        {
-            info!("Computed conversion rate for market {}", m.id);
-            return Ok(Some((*px, vec![id])));
-        }
-
-        // Find quote/base market
-        let id = format!("{quote}{base}");
-        let mkt = self.get_market(&id).await?;
-        if let Some(m) = mkt
-            && let Some(px) = m.price()
-        {
-            let rate = Price::new(1. / px.price, px.ts);
-            info!("Computed conversion rate for market {}", m.id);
-            return Ok(Some((rate, vec![id])));
-        }
+        let ids = conversion_market_ids(&base, &quote);
+        let markets = self.get_markets(&ids).await?;
+
+        // Fiat pairs stick to the ECB reference rates when they connect them,
+        // rather than mixing in venue prices
+        let path = MarketGraph::find_preferred_path(
+            markets.iter().map(|m| m.as_ref()),
+            PriceProvider::Ecb,
+            &base,
+            &quote,
+            CONVERSION_MAX_HOPS,
+        );
 
-        // Find alternative markets
-        let Some((base_usd_id, base_usd_px)) = self.get_base_usd_price(&base, &quote).await? else {
+        let Some(path) = path else {
+            warn!(
+                base = base,
+                quote = quote,
+                "Price not available for any market path from '{base}' to '{quote}'"
+            );
             return Ok(None);
         };
 
-        let usd_ccy = "usd";
-        let base_quote_id = format!("{base}{quote}");
-        let usd_quote_id = format!("{usd_ccy}{quote}");
-        let quote_usd_id = format!("{quote}{usd_ccy}");
-
-        let usd_quote = self.get_market(&usd_quote_id).await?;
-        if let Some(usd_quote) = usd_quote
-            && let Some(usd_quote_px) = usd_quote.price()
-        {
-            let price = base_usd_px.price * usd_quote_px.price;
-            let ts = std::cmp::min(base_usd_px.ts, usd_quote_px.ts);
-            let rate = Price::new(price, ts);
-            info!(
-                "Computed conversion rate for market {} triangulating between markets",
-                base_quote_id
-            );
-            return Ok(Some((rate, vec![base_usd_id, usd_quote_id])));
-        }
-
-        let quote_usd = self.get_market(&quote_usd_id).await?;
-        if let Some(quote_usd) = quote_usd
-            && let Some(quote_usd_px) = quote_usd.price()
-        {
-            let price = base_usd_px.price / quote_usd_px.price;
-            let ts = std::cmp::min(base_usd_px.ts, quote_usd_px.ts);
-            let rate = Price::new(price, ts);
-            info!(
-                "Computed conversion rate for market {} triangulating between markets",
-                base_quote_id
-            );
-            return Ok(Some((rate, vec![base_quote_id, usd_quote_id])));
-        }
-
-        warn!(
-            base = base,
-            quote = quote,
-            "Price not available for markets '{}' and '{}'",
-            usd_quote_id,
-            quote_usd_id
+        info!(
+            "Computed conversion rate {base}/{quote} through markets {:?}",
+            path.markets
         );
-
-        Ok(None)
+        Ok(Some((path.price, path.markets)))
     }
 
-    async fn get_base_usd_price(
-        &self,
-        base: &AssetId,
-        quote: &AssetId,
-    ) -> Result<Option<(MarketId, Price)>> {
-        let base_usd_id = format!("{}{}", base, "usd");
-        let usd_base_id = format!("{}{}", "usd", base);
-
-        let base_usd = self.get_market(&base_usd_id).await?;
-        if let Some(ref m) = base_usd
-            && let Some(px) = m.price()
+    /// Looks up several [`Market`]s at once, loading the uncached ones in a
+    /// single repository round trip. Unknown markets are skipped.
+    async fn get_markets(&self, ids: &[MarketId]) -> Result<Vec<Arc<Market>>> {
+        let mut found = Vec::with_capacity(ids.len());
+        let mut missing = Vec::new();
         {
-            return Ok(Some((base_usd_id, *px)));
+            let markets = self.markets.read();
+            for id in ids {
+                match markets.get(id) {
+                    Some(m) => found.push(m.clone()),
+                    None => missing.push(id),
+                }
+            }
         }
 
-        let usd_base = self.get_market(&usd_base_id).await?;
-        if let Some(ref m) = usd_base
-            && let Some(px) = m.price()
-        {
-            return Ok(Some((
-                usd_base_id,
-                Price {
-                    price: 1. / px.price,
-                    ts: px.ts,
-                },
-            )));
+        if missing.is_empty() {
+            return Ok(found);
         }
 
-        match (base_usd, usd_base) {
-            (None, None) => {
-                warn!(
-                    base = base,
-                    quote = quote,
-                    "Cannot find any of markets: '{}', '{}'",
-                    base_usd_id,
-                    usd_base_id
-                );
-                Ok(None)
-            }
-            (_, _) => {
-                warn!(
-                    base = base,
-                    quote = quote,
-                    "Price not available for any of markets: '{}', '{}'",
-                    base_usd_id,
-                    usd_base_id
-                );
-                Ok(None)
-            }
+        let loaded = self.repo.find_markets(&missing).await?;
+
+        let mut markets = self.markets.write();
+        for m in loaded.into_iter().flatten() {
+            let m = Arc::new(m);
+            markets.insert(m.id.clone(), m.clone());
+            found.push(m);
         }
+
+        Ok(found)
     }
 }
 
+/// Returns the ids of the markets a `base`/`quote` conversion path may use:
+/// every market between the two assets and the conversion pivots.
+fn conversion_market_ids(base: &AssetId, quote: &AssetId) -> Vec<MarketId> {
+    let (base, quote) = (normalized_asset(base), normalized_asset(quote));
+
+    let mut assets = vec![base.clone(), quote.clone()];
+    assets.extend(
+        CONVERSION_PIVOTS
+            .iter()
+            .map(|p| p.to_string())
+            .filter(|p| *p != base && *p != quote),
+    );
+
+    assets
+        .iter()
+        .flat_map(|a| {
+            assets
+                .iter()
+                .filter(move |b| *b != a)
+                .map(move |b| format!("{a}{b}"))
+        })
+        .collect()
+}
+
 fn normalized_asset(id: &AssetId) -> AssetId {
     lazy_static::lazy_static! {
         static ref NORMALIZED: HashMap<&'static str, &'static str> = {
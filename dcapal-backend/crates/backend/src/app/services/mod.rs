@@ -1,4 +1,47 @@
+pub mod allocation;
+pub mod backtest;
 pub mod command;
+pub mod fetch_job;
+pub mod framework;
 pub mod ip2location;
 pub mod market_data;
 pub mod portfolio;
+pub mod price_series;
+pub mod projection;
+pub mod reference_rate;
+
+use dcapal_optimizer_wasm::optimize::backtest::PriceSample;
+use tokio::task::JoinError;
+
+use crate::app::services::{
+    command::PriceSeriesQuery,
+    price_series::{PriceSeriesService, PriceSeriesServiceError},
+};
+
+/// Loads the stored samples of a price series as optimizer price samples.
+pub(crate) async fn load_price_samples(
+    price_series: &PriceSeriesService,
+    query: PriceSeriesQuery,
+) -> Result<Vec<PriceSample>, PriceSeriesServiceError> {
+    let series = price_series.get_series(query).await?;
+    let samples = series
+        .samples
+        .into_iter()
+        .map(|s| PriceSample {
+            timestamp: s.observed_at.timestamp(),
+            price: s.value,
+        })
+        .collect();
+
+    Ok(samples)
+}
+
+/// Runs an optimizer solve on the blocking thread pool: solves are CPU bound
+/// and would otherwise stall the async runtime workers.
+pub(crate) async fn solve_blocking<T, F>(solve: F) -> Result<T, JoinError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(solve).await
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    DateTime,
+    app::{
+        domain::entity::{CoverageStatus, SeriesKey, SeriesObservation, SeriesTimeframe},
+        services::command::PriceSeriesQuery,
+    },
+    error::DcaError,
+    ports::outbound::repository::price_series::PriceSeriesRepository,
+};
+
+/// Errors raised while serving stored price series.
+#[derive(Debug, thiserror::Error)]
+pub enum PriceSeriesServiceError {
+    #[error("price series persistence failed")]
+    Persistence(#[from] DcaError),
+}
+
+impl From<PriceSeriesServiceError> for DcaError {
+    fn from(e: PriceSeriesServiceError) -> Self {
+        match e {
+            PriceSeriesServiceError::Persistence(e) => e,
+        }
+    }
+}
+
+/// The stored samples of a price series over a requested range.
+#[derive(Debug, Clone)]
+pub struct PriceSeries {
+    pub key: SeriesKey,
+    pub timeframe: SeriesTimeframe,
+    pub from: DateTime,
+    pub to: DateTime,
+    pub status: CoverageStatus,
+    pub samples: Vec<SeriesObservation>,
+}
+
+/// Serves provider-bound price series from the application data store.
+pub struct PriceSeriesService {
+    repo: Arc<dyn PriceSeriesRepository>,
+}
+
+impl PriceSeriesService {
+    /// Creates a price-series service using the supplied persistence port.
+    pub fn new(repo: Arc<dyn PriceSeriesRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Returns the stored samples in the requested range.
+    ///
+    /// A series requested for the first time is registered so the ingestion
+    /// worker backfills it; until then it is reported as pending rather than
+    /// making the request wait for the provider.
+    pub async fn get_series(
+        &self,
+        query: PriceSeriesQuery,
+    ) -> Result<PriceSeries, PriceSeriesServiceError> {
+        let series = self.repo.find_or_register(&query.key).await?;
+
+        let samples = self
+            .repo
+            .load_observations(series.id, query.timeframe, query.from, query.to)
+            .await?
+            .into_iter()
+            .map(|row| SeriesObservation {
+                observed_at: row.observed_at,
+                value: row.value,
+            })
+            .collect::<Vec<_>>();
+
+        let status = CoverageStatus::of(
+            &samples,
+            series.last_fetched_at.is_some(),
+            query.timeframe,
+            query.from,
+            query.to,
+            Utc::now(),
+        );
+
+        Ok(PriceSeries {
+            key: query.key,
+            timeframe: query.timeframe,
+            from: query.from,
+            to: query.to,
+            status,
+            samples,
+        })
+    }
+}
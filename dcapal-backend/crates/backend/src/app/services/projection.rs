@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use dcapal_optimizer_wasm::optimize::{
+    self, OptimizeError,
+    projection::{ReturnModel, Solution},
+};
+use tokio::task::JoinError;
+use tracing::debug;
+
+use crate::{
+    app::services::{
+        command::ProjectionCmd,
+        load_price_samples,
+        price_series::{PriceSeriesService, PriceSeriesServiceError},
+        solve_blocking,
+    },
+    error::DcaError,
+};
+
+/// Errors raised while projecting a contribution plan.
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectionServiceError {
+    #[error("projection failed")]
+    Optimize(#[from] OptimizeError),
+    #[error("price series unavailable")]
+    Series(#[from] PriceSeriesServiceError),
+    #[error("projection task failed")]
+    Task(#[from] JoinError),
+}
+
+impl From<ProjectionServiceError> for DcaError {
+    fn from(e: ProjectionServiceError) -> Self {
+        match e {
+            ProjectionServiceError::Optimize(OptimizeError::InvalidInput(msg)) => {
+                DcaError::BadRequest(msg)
+            }
+            ProjectionServiceError::Optimize(e) => DcaError::Generic(e.to_string()),
+            ProjectionServiceError::Series(e) => e.into(),
+            ProjectionServiceError::Task(e) => DcaError::Generic(e.to_string()),
+        }
+    }
+}
+
+/// Projects contribution plans with Monte Carlo simulations.
+pub struct ProjectionService {
+    price_series: Arc<PriceSeriesService>,
+}
+
+impl ProjectionService {
+    /// Creates a projection service estimating returns from `price_series`.
+    pub fn new(price_series: Arc<PriceSeriesService>) -> Self {
+        Self { price_series }
+    }
+
+    /// Estimates the return model of the command classes backed by a price
+    /// series, then simulates the projection.
+    ///
+    /// Series not ingested yet have no samples: the projection is rejected as
+    /// lacking prices to estimate from.
+    pub async fn project(&self, cmd: ProjectionCmd) -> Result<Solution, ProjectionServiceError> {
+        let mut options = cmd.options;
+        for (name, query) in cmd.series {
+            let samples = load_price_samples(&self.price_series, query).await?;
+
+            let returns = ReturnModel::estimate(&samples).map_err(|e| match e {
+                OptimizeError::InvalidInput(msg) => {
+                    OptimizeError::InvalidInput(format!("Asset class {name}: {msg}"))
+                }
+                e => e,
+            })?;
+
+            debug!(
+                "Estimated {name} returns from {} prices: {returns:?}",
+                samples.len()
+            );
+
+            if let Some(class) = options.classes.get_mut(&name) {
+                class.returns = returns;
+            }
+        }
+
+        let solution = solve_blocking(move || optimize::solve_projection(&options)).await??;
+
+        Ok(solution)
+    }
+}
@@ -0,0 +1,94 @@
+use std::{str::FromStr, sync::Arc};
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    DateTime,
+    app::domain::entity::{RateObservation, RateType, ReferenceRate},
+    error::DcaError,
+    ports::outbound::repository::{
+        postgres::types::ReferenceRateRow, reference_rate::ReferenceRateRepository,
+    },
+};
+
+/// Errors raised while serving ingested reference rates.
+#[derive(Debug, thiserror::Error)]
+pub enum ReferenceRateServiceError {
+    #[error("no '{0}' observation was ingested yet")]
+    Unavailable(RateType),
+    #[error("latest '{0}' observation ({1}) is too old to be used")]
+    Stale(RateType, chrono::NaiveDate),
+    #[error("reference rate persistence failed")]
+    Persistence(#[from] DcaError),
+}
+
+impl From<ReferenceRateServiceError> for DcaError {
+    fn from(e: ReferenceRateServiceError) -> Self {
+        match e {
+            e @ (ReferenceRateServiceError::Unavailable(_)
+            | ReferenceRateServiceError::Stale(..)) => DcaError::NotFound(e.to_string()),
+            ReferenceRateServiceError::Persistence(e) => e,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, ReferenceRateServiceError>;
+
+/// Stores and serves the reference rates ingested from their publishers.
+pub struct ReferenceRateService {
+    repo: Arc<dyn ReferenceRateRepository>,
+}
+
+impl ReferenceRateService {
+    /// Observations older than this are no longer a usable latest rate. It
+    /// spans the longest publication gaps around bank holidays.
+    const MAX_OBSERVATION_AGE_DAYS: i64 = 7;
+
+    /// Creates a reference-rate service using the supplied persistence port.
+    pub fn new(repo: Arc<dyn ReferenceRateRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Records an observation retrieved from its publisher at `fetched_at`.
+    pub async fn store(&self, observation: &RateObservation, fetched_at: DateTime) -> Result<()> {
+        Ok(self.repo.store_observation(observation, fetched_at).await?)
+    }
+
+    /// Returns the latest observation of `rate_type`.
+    ///
+    /// A prior business day observation is normal, but guidance must not be
+    /// computed from a rate that stopped being refreshed: observations older
+    /// than a week are reported as stale rather than served.
+    pub async fn latest(&self, rate_type: RateType) -> Result<ReferenceRate> {
+        let row = self
+            .repo
+            .find_latest(rate_type)
+            .await?
+            .ok_or(ReferenceRateServiceError::Unavailable(rate_type))?;
+        let rate = try_into_reference_rate(row)?;
+
+        let observed_on = rate.observation.observed_on;
+        let today = Utc::now().date_naive();
+        if today - observed_on > Duration::days(Self::MAX_OBSERVATION_AGE_DAYS) {
+            return Err(ReferenceRateServiceError::Stale(rate_type, observed_on));
+        }
+
+        Ok(rate)
+    }
+}
+
+fn try_into_reference_rate(row: ReferenceRateRow) -> Result<ReferenceRate> {
+    let rate_type = RateType::from_str(&row.rate_type).map_err(|e| {
+        DcaError::Generic(format!("Unknown stored rate type '{}': {e}", row.rate_type))
+    })?;
+
+    Ok(ReferenceRate {
+        observation: RateObservation {
+            rate_type,
+            observed_on: row.observed_on,
+            percent: row.value,
+        },
+        published_at: row.published_at,
+        fetched_at: row.fetched_at,
+    })
+}
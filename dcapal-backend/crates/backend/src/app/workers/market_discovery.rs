@@ -6,41 +6,48 @@ use tracing::{debug, error, info};
 use crate::{
     AppContext, DateTime,
     app::{
-        domain::market_data_utils::fetch_market_price,
+        domain::{
+            entity::{Asset, AssetKind, FetchJobKind, Market},
+            market_data_utils::{discover_markets, fetch_market_price},
+        },
         infra::utils::{StopToken, should_stop},
-        services::market_data::MarketDataService,
+        services::{fetch_job::FetchJobService, market_data::MarketDataService},
     },
-    config::PriceProvider,
+    config::{self, PriceProvider},
     error::Result,
     ports::outbound::{
-        adapter::PriceProviders,
+        adapter::{MarketDataProvider, PriceProviders},
         repository::{MiscRepository, market_data::MarketDataRepository},
     },
 };
 
-/// Worker to periodically discover new crypto assets and markets. As of today,
-/// new markets are checked every 24 hours.
+/// Worker to periodically discover new assets and markets. As of today,
+/// new markets are checked every 24 hours. Each daily check runs as a durable
+/// `discover` fetch job, so a failed discovery is retried with backoff.
 pub struct MarketDiscoveryWorker {
     market_data_service: Arc<MarketDataService>,
+    fetch_jobs: Arc<FetchJobService>,
     misc_repo: Arc<MiscRepository>,
     market_data_repo: Arc<MarketDataRepository>,
-    price_provider: PriceProvider,
+    providers_config: config::Providers,
     providers: Arc<PriceProviders>,
 }
 
 impl MarketDiscoveryWorker {
     pub fn new(ctx: &AppContext) -> Self {
         let market_data_service = ctx.services.mkt_data.clone();
+        let fetch_jobs = ctx.services.fetch_job.clone();
         let misc_repo = ctx.repos.misc.clone();
         let market_data_repo = ctx.repos.mkt_data.clone();
-        let price_provider = ctx.config.app.providers.price_provider;
+        let providers_config = ctx.config.app.providers.clone();
         let providers = ctx.providers.clone();
 
         Self {
             market_data_service,
+            fetch_jobs,
             misc_repo,
             market_data_repo,
-            price_provider,
+            providers_config,
             providers,
         }
     }
@@ -57,43 +64,89 @@ impl MarketDiscoveryWorker {
             // Reset next check timeout
             sleep = tokio::time::sleep(Duration::from_secs(60));
 
-            let res = is_outdated(&self.misc_repo).await;
-            if let Err(e) = res {
-                error!("Failed to fetch last update time: {:?}", e);
-                continue;
+            match is_outdated(&self.misc_repo).await {
+                Ok((true, _)) => {
+                    // One discovery job per day: retries reuse it instead of queueing more
+                    let key = Utc::now().date_naive().to_string();
+                    if let Err(e) = self
+                        .fetch_jobs
+                        .schedule(FetchJobKind::Discover, &key, None)
+                        .await
+                    {
+                        error!("Failed to schedule market discovery: {:?}", e);
+                    }
+                }
+                Ok((false, last_fetched_ts)) => {
+                    debug!(
+                        "Markets already discovered today ({})",
+                        last_fetched_ts.map(|t| t.to_string()).unwrap_or_default()
+                    );
+                }
+                Err(e) => error!("Failed to fetch last update time: {:?}", e),
             }
 
-            let (is_outdated, last_fetched_ts) = res.unwrap();
-            if !is_outdated {
-                debug!(
-                    "Kraken assets already fetched today ({})",
-                    last_fetched_ts.map(|t| t.to_string()).unwrap_or_default()
-                );
-                continue;
+            if let Err(e) = self.run_next_job().await {
+                error!("Failed to run market discovery job: {:?}", e);
             }
 
-            if let Err(e) = self.discover_new_markets().await {
-                error!("Failed to update Kraken Assets and Markets data: {:?}", e);
+            if let Err(e) = self.fetch_jobs.refresh_metrics().await {
+                error!("Failed to refresh fetch job metrics: {:?}", e);
             }
+        }
+    }
 
-            let now = Utc::now();
-            if let Err(e) = self.misc_repo.set_cw_last_fetched(now).await {
-                error!("Failed to update last update time: {:?}", e);
-            }
+    async fn run_next_job(&self) -> Result<()> {
+        let Some(job) = self.fetch_jobs.claim(FetchJobKind::Discover).await? else {
+            return Ok(());
+        };
 
-            // Reset next check timeout
-            sleep = tokio::time::sleep(Duration::from_secs(60));
+        match self.discover_new_markets().await {
+            Ok(()) => {
+                self.fetch_jobs.succeed(&job).await?;
+
+                // Only a successful discovery moves the daily marker forward
+                self.misc_repo.set_cw_last_fetched(Utc::now()).await?;
+
+                let pruned = self.fetch_jobs.prune_succeeded().await?;
+                debug!("Pruned {pruned} succeeded fetch jobs");
+            }
+            Err(e) => {
+                error!("Failed to update Assets and Markets data: {:?}", e);
+                self.fetch_jobs
+                    .fail(FetchJobKind::Discover, &job, &e)
+                    .await?;
+            }
         }
+
+        Ok(())
     }
 
     async fn discover_new_markets(&self) -> Result<()> {
-        // Collect assets and markets from Kraken
-        let (assets, markets) = self
-            .providers
-            .kraken
-            .fetch_assets(&self.market_data_repo)
-            .await?;
+        // Collect assets and markets from the first provider able to list them
+        let (assets, markets) = discover_markets(
+            &self.market_data_repo,
+            &self.providers,
+            &self.providers_config,
+        )
+        .await?;
+        self.store_discovered(assets, markets).await;
+
+        // The ECB reference set lists fiat currencies the crypto venues do not quote
+        if self
+            .providers_config
+            .price_chain(AssetKind::Fiat)
+            .contains(&PriceProvider::Ecb)
+        {
+            let (assets, markets) = self.providers.ecb.discover(&self.market_data_repo).await?;
+            self.store_discovered(assets, markets).await;
+        }
+
+        self.market_data_service.invalidate_asset_cache();
 
+        Ok(())
+    }
+
+    async fn store_discovered(&self, assets: Vec<Asset>, markets: Vec<Market>) {
         // Store assets in repository
         for a in assets {
             info!("Storing asset '{}'", a.id());
@@ -110,15 +163,19 @@ impl MarketDiscoveryWorker {
                 })
         }
 
-        // Store markets in repository
+        // Store markets in repository. Known markets come back already priced
+        // when only their trading rules changed.
         for mut m in markets {
-            info!("Fetching price for market '{}'", m.id);
-            let Some(price) = fetch_market_price(&m, &self.providers, self.price_provider).await
-            else {
-                continue;
-            };
-
-            m.set_price(price);
+            if m.price().is_none() {
+                info!("Fetching price for market '{}'", m.id);
+                let Some(price) =
+                    fetch_market_price(&m, &self.providers, &self.providers_config).await
+                else {
+                    continue;
+                };
+
+                m.set_price(price);
+            }
 
             info!("Storing market '{}'", m.id);
             if let Err(e) = self.market_data_repo.store_market(&m).await {
@@ -129,11 +186,9 @@ impl MarketDiscoveryWorker {
                     serde_json::to_string(&m).unwrap()
                 );
             }
-        }
-
-        self.market_data_service.invalidate_asset_cache();
 
-        Ok(())
+            self.market_data_service.invalidate_market(&m.id);
+        }
     }
 }
 
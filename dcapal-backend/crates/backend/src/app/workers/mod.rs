@@ -1,2 +1,4 @@
 pub mod market_discovery;
+pub mod price_series;
 pub mod price_updater;
+pub mod reference_rate;
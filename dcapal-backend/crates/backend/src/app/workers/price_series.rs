@@ -0,0 +1,172 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use chrono::{NaiveTime, Utc};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    AppContext, DateTime,
+    app::{
+        domain::entity::{FetchJobKind, SeriesTimeframe},
+        infra::utils::{StopToken, should_stop},
+        services::fetch_job::FetchJobService,
+    },
+    config::PriceProvider,
+    error::{DcaError, Result},
+    ports::outbound::{
+        adapter::PriceProviders,
+        repository::{postgres::types::PriceSeriesRow, price_series::PriceSeriesRepository},
+    },
+};
+
+const TIMEFRAMES: [SeriesTimeframe; 2] = [SeriesTimeframe::Daily, SeriesTimeframe::Weekly];
+
+/// Worker ingesting registered price series. A series is backfilled on its
+/// first run and then extended with the buckets closed since its last run,
+/// at most once per UTC day. Each daily ingestion runs as a durable
+/// `backfill` fetch job, retried with backoff on failure.
+pub struct PriceSeriesWorker {
+    period: Duration,
+    price_series_repo: Arc<dyn PriceSeriesRepository>,
+    fetch_jobs: Arc<FetchJobService>,
+    providers: Arc<PriceProviders>,
+}
+
+impl PriceSeriesWorker {
+    pub fn new(ctx: &AppContext, period: Duration) -> Self {
+        let price_series_repo = ctx.repos.price_series.clone();
+        let fetch_jobs = ctx.services.fetch_job.clone();
+        let providers = ctx.providers.clone();
+
+        Self {
+            period,
+            price_series_repo,
+            fetch_jobs,
+            providers,
+        }
+    }
+
+    pub async fn run(&self, mut stop_token: StopToken) {
+        let mut sleep = tokio::time::sleep(Duration::from_millis(50));
+        loop {
+            tokio::select! {
+                _ = sleep => {}
+                _ = should_stop(&mut stop_token) => break,
+            }
+
+            if let Err(e) = self.schedule_due_series().await {
+                error!("Error occurred while scheduling price series ingestion: {e:?}");
+            }
+
+            if let Err(e) = self.run_pending_jobs().await {
+                error!("Error occurred while running price series jobs: {e:?}");
+            }
+
+            if let Err(e) = self.fetch_jobs.refresh_metrics().await {
+                error!("Failed to refresh fetch job metrics: {e:?}");
+            }
+
+            sleep = tokio::time::sleep(self.period);
+            let next = Utc::now() + chrono::Duration::from_std(self.period).unwrap();
+            debug!("Next PriceSeriesWorker execution: {next}");
+        }
+    }
+
+    async fn schedule_due_series(&self) -> Result<()> {
+        let today = Utc::now().with_time(NaiveTime::MIN).unwrap();
+
+        let due = self.price_series_repo.load_due(today).await?;
+        debug!("Found {} price series to ingest", due.len());
+
+        for series in due {
+            // One job per series and day: a job failed for good waits for tomorrow
+            let dedup_key = format!("{}:{}", series.id, today.date_naive());
+            if self
+                .fetch_jobs
+                .schedule(FetchJobKind::Backfill, &dedup_key, Some(series.id))
+                .await?
+            {
+                info!(
+                    "Scheduled ingestion of price series {}:{} ({})",
+                    series.provider, series.instrument, series.id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_pending_jobs(&self) -> Result<()> {
+        while let Some(job) = self.fetch_jobs.claim(FetchJobKind::Backfill).await? {
+            let res = match job.series_id {
+                Some(series_id) => match self.price_series_repo.find_by_id(series_id).await? {
+                    Some(series) => self.ingest_series(&series, Utc::now()).await,
+                    None => Err(DcaError::Generic(format!(
+                        "Price series {series_id} no longer exists"
+                    ))),
+                },
+                None => Err(DcaError::Generic(format!(
+                    "Backfill job {} has no price series",
+                    job.id
+                ))),
+            };
+
+            match res {
+                Ok(()) => self.fetch_jobs.succeed(&job).await?,
+                Err(e) => {
+                    self.fetch_jobs
+                        .fail(FetchJobKind::Backfill, &job, &e)
+                        .await?
+                }
+            }
+        }
+
+        self.fetch_jobs.prune_succeeded().await?;
+
+        Ok(())
+    }
+
+    async fn ingest_series(&self, series: &PriceSeriesRow, now: DateTime) -> Result<()> {
+        let provider = PriceProvider::from_str(&series.provider).map_err(|_| {
+            DcaError::Generic(format!("Unknown series provider '{}'", series.provider))
+        })?;
+
+        for timeframe in TIMEFRAMES {
+            let since = self
+                .price_series_repo
+                .last_observed_at(series.id, timeframe)
+                .await?
+                .unwrap_or(now - timeframe.backfill_depth());
+
+            let observations = self
+                .providers
+                .market_data(provider)
+                .fetch_ohlc(&series.instrument, timeframe, since, now)
+                .await?
+                .into_iter()
+                .filter(|o| timeframe.is_closed(o.observed_at, now))
+                .collect::<Vec<_>>();
+
+            let stored = self
+                .price_series_repo
+                .store_observations(series.id, timeframe, &observations, now)
+                .await?;
+
+            if stored == 0 {
+                warn!(
+                    "No new {timeframe} samples for price series {}:{}",
+                    series.provider, series.instrument
+                );
+            } else {
+                debug!(
+                    "Stored {stored} {timeframe} samples for price series {}:{}",
+                    series.provider, series.instrument
+                );
+            }
+
+            // Please the rate limiter
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        self.price_series_repo.mark_fetched(series.id, now).await
+    }
+}
@@ -1,42 +1,46 @@
 use std::{sync::Arc, time::Duration};
 
-use chrono::Utc;
+use chrono::{TimeZone, Utc};
 use tracing::{error, info, warn};
 
 use crate::{
-    AppContext,
+    AppContext, DateTime,
     app::{
-        domain::market_data_utils::fetch_market_price,
+        domain::{entity::FetchJobKind, market_data_utils::fetch_market_price},
         infra::utils::{StopToken, should_stop},
-        services::market_data::MarketDataService,
+        services::{fetch_job::FetchJobService, market_data::MarketDataService},
     },
-    config::PriceProvider,
+    config,
     error::Result,
     ports::outbound::{adapter::PriceProviders, repository::market_data::MarketDataRepository},
 };
 
 /// Worker periodically updating market prices. As of today, prices are
-/// refreshed every 5 minutes.
+/// refreshed every 5 minutes. Each sweep runs as a durable `reprice` fetch
+/// job, so a failed sweep is retried with backoff within its period.
 pub struct PriceUpdaterWorker {
     period: Duration,
     market_data_service: Arc<MarketDataService>,
+    fetch_jobs: Arc<FetchJobService>,
     market_data_repo: Arc<MarketDataRepository>,
-    price_provider: PriceProvider,
+    providers_config: config::Providers,
     providers: Arc<PriceProviders>,
 }
 
 impl PriceUpdaterWorker {
     pub fn new(ctx: &AppContext, period: Duration) -> Self {
         let market_data_service = ctx.services.mkt_data.clone();
+        let fetch_jobs = ctx.services.fetch_job.clone();
         let market_data_repo = ctx.repos.mkt_data.clone();
-        let price_provider = ctx.config.app.providers.price_provider;
+        let providers_config = ctx.config.app.providers.clone();
         let providers = ctx.providers.clone();
 
         Self {
             period,
             market_data_service,
+            fetch_jobs,
             market_data_repo,
-            price_provider,
+            providers_config,
             providers,
         }
     }
@@ -49,14 +53,53 @@ impl PriceUpdaterWorker {
                 _ = should_stop(&mut stop_token) => break,
             }
 
-            if let Err(e) = self.update_prices().await {
-                error!("Error occurred while updating prices: {e:?}");
+            // Poll often enough to pick up retries, but sweep once per period
+            sleep = tokio::time::sleep(Self::POLL_INTERVAL.min(self.period));
+
+            let slot = self.current_slot();
+            if let Err(e) = self
+                .fetch_jobs
+                .schedule(FetchJobKind::Reprice, &slot.to_rfc3339(), None)
+                .await
+            {
+                error!("Failed to schedule price update for {slot}: {e:?}");
+            }
+
+            if let Err(e) = self.run_next_job().await {
+                error!("Error occurred while running price update job: {e:?}");
             }
+        }
+    }
+
+    const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+    fn current_slot(&self) -> DateTime {
+        let period = self.period.as_secs().max(1) as i64;
+        let ts = Utc::now().timestamp();
+        Utc.timestamp_opt(ts - ts.rem_euclid(period), 0).unwrap()
+    }
+
+    async fn run_next_job(&self) -> Result<()> {
+        let Some(job) = self.fetch_jobs.claim(FetchJobKind::Reprice).await? else {
+            return Ok(());
+        };
 
-            sleep = tokio::time::sleep(self.period);
-            let next = Utc::now() + chrono::Duration::from_std(self.period).unwrap();
-            info!("Next PriceUpdaterWorker execution: {next}");
+        match self.update_prices().await {
+            Ok(()) => {
+                self.fetch_jobs.succeed(&job).await?;
+
+                let next = self.current_slot() + chrono::Duration::from_std(self.period).unwrap();
+                info!("Next PriceUpdaterWorker execution: {next}");
+            }
+            Err(e) => {
+                error!("Error occurred while updating prices: {e:?}");
+                self.fetch_jobs
+                    .fail(FetchJobKind::Reprice, &job, &e)
+                    .await?;
+            }
         }
+
+        Ok(())
     }
 
     async fn update_prices(&self) -> Result<()> {
@@ -64,7 +107,7 @@ impl PriceUpdaterWorker {
         let markets = self.market_data_repo.load_markets().await?;
 
         for mut m in markets {
-            let Some(price) = fetch_market_price(&m, &self.providers, self.price_provider).await
+            let Some(price) = fetch_market_price(&m, &self.providers, &self.providers_config).await
             else {
                 warn!("Failed to fetch price update for market {}", m.id);
                 continue;
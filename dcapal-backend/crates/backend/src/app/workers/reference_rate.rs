@@ -0,0 +1,130 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{TimeZone, Utc};
+use strum::IntoEnumIterator;
+use tracing::{error, info, warn};
+
+use crate::{
+    AppContext, DateTime,
+    app::{
+        domain::entity::{FetchJobKind, RateType},
+        infra::utils::{StopToken, should_stop},
+        services::{fetch_job::FetchJobService, reference_rate::ReferenceRateService},
+    },
+    error::{DcaError, Result},
+    ports::outbound::adapter::{PriceProviders, ReferenceRateProvider},
+};
+
+/// Worker periodically ingesting the latest observation of every reference
+/// rate. Publishers announce a new observation once per business day, so a
+/// refresh every hour bounds how late a publication is recorded. Each refresh
+/// runs as a durable `rates` fetch job.
+pub struct ReferenceRateWorker {
+    period: Duration,
+    fetch_jobs: Arc<FetchJobService>,
+    reference_rates: Arc<ReferenceRateService>,
+    providers: Arc<PriceProviders>,
+}
+
+impl ReferenceRateWorker {
+    pub fn new(ctx: &AppContext, period: Duration) -> Self {
+        let fetch_jobs = ctx.services.fetch_job.clone();
+        let reference_rates = ctx.services.reference_rate.clone();
+        let providers = ctx.providers.clone();
+
+        Self {
+            period,
+            fetch_jobs,
+            reference_rates,
+            providers,
+        }
+    }
+
+    pub async fn run(&self, mut stop_token: StopToken) {
+        let mut sleep = tokio::time::sleep(Duration::from_millis(50));
+        loop {
+            tokio::select! {
+                _ = sleep => {}
+                _ = should_stop(&mut stop_token) => break,
+            }
+
+            // Poll often enough to pick up retries, but refresh once per period
+            sleep = tokio::time::sleep(Self::POLL_INTERVAL.min(self.period));
+
+            let slot = self.current_slot();
+            if let Err(e) = self
+                .fetch_jobs
+                .schedule(FetchJobKind::Rates, &slot.to_rfc3339(), None)
+                .await
+            {
+                error!("Failed to schedule reference rate refresh for {slot}: {e:?}");
+            }
+
+            if let Err(e) = self.run_next_job().await {
+                error!("Error occurred while running reference rate job: {e:?}");
+            }
+        }
+    }
+
+    const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+    fn current_slot(&self) -> DateTime {
+        let period = self.period.as_secs().max(1) as i64;
+        let ts = Utc::now().timestamp();
+        Utc.timestamp_opt(ts - ts.rem_euclid(period), 0).unwrap()
+    }
+
+    async fn run_next_job(&self) -> Result<()> {
+        let Some(job) = self.fetch_jobs.claim(FetchJobKind::Rates).await? else {
+            return Ok(());
+        };
+
+        match self.refresh_rates().await {
+            Ok(()) => {
+                self.fetch_jobs.succeed(&job).await?;
+
+                let next = self.current_slot() + chrono::Duration::from_std(self.period).unwrap();
+                info!("Next ReferenceRateWorker execution: {next}");
+            }
+            Err(e) => {
+                error!("Error occurred while refreshing reference rates: {e:?}");
+                self.fetch_jobs.fail(FetchJobKind::Rates, &job, &e).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refreshes every rate, failing the job if any of them failed so that
+    /// the retry picks it up again.
+    async fn refresh_rates(&self) -> Result<()> {
+        let mut failed = Vec::new();
+        for rate_type in RateType::iter() {
+            if let Err(e) = self.refresh_rate(rate_type).await {
+                error!("Failed to refresh '{rate_type}' reference rate: {e:?}");
+                failed.push(rate_type.to_string());
+            }
+        }
+
+        if !failed.is_empty() {
+            return Err(DcaError::Generic(format!(
+                "Failed to refresh reference rates: {}",
+                failed.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn refresh_rate(&self, rate_type: RateType) -> Result<()> {
+        let Some(observation) = self.providers.rates.fetch_latest(rate_type).await? else {
+            warn!("No '{rate_type}' observation published");
+            return Ok(());
+        };
+
+        self.reference_rates
+            .store(&observation, Utc::now())
+            .await
+            .map_err(DcaError::from)
+    }
+}
@@ -1,14 +1,29 @@
 use jsonwebtoken::jwk::JwkSet;
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
-
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, strum_macros::Display)]
+use crate::{app::domain::entity::AssetKind, error::Result};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum PriceProvider {
     CryptoWatch,
     Kraken,
     Yahoo,
+    /// The ECB euro foreign exchange reference rates, for fiat markets only.
+    Ecb,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -32,12 +47,41 @@ fn default_enable_stdout() -> bool {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Providers {
+    /// Provider used for asset kinds without a configured `price_chain`.
     pub price_provider: PriceProvider,
+    #[serde(default)]
+    pub price_chain: Option<PriceChain>,
     pub cw_api_key: String,
     pub ip_api_key: String,
     pub cmc_api_key: Option<String>,
 }
 
+impl Providers {
+    /// Returns the providers to query, in order, for markets of `kind`.
+    pub fn price_chain(&self, kind: AssetKind) -> Vec<PriceProvider> {
+        let chain = self.price_chain.as_ref().map(|c| match kind {
+            AssetKind::Crypto => &c.crypto,
+            AssetKind::Fiat => &c.fiat,
+        });
+
+        match chain {
+            Some(chain) if !chain.is_empty() => chain.clone(),
+            _ => vec![self.price_provider],
+        }
+    }
+}
+
+/// Ordered market-data providers per asset kind. When a provider has no
+/// price for a market, or fails, the next one in the chain is tried.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceChain {
+    #[serde(default)]
+    pub crypto: Vec<PriceProvider>,
+    #[serde(default)]
+    pub fiat: Vec<PriceProvider>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Auth {
@@ -144,3 +188,40 @@ impl Config {
         Ok(s.try_deserialize()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers(price_chain: Option<PriceChain>) -> Providers {
+        Providers {
+            price_provider: PriceProvider::Kraken,
+            price_chain,
+            cw_api_key: String::new(),
+            ip_api_key: String::new(),
+            cmc_api_key: None,
+        }
+    }
+
+    #[test]
+    fn price_chain_defaults_to_price_provider() {
+        let config = providers(None);
+        assert_eq!(
+            config.price_chain(AssetKind::Fiat),
+            vec![PriceProvider::Kraken]
+        );
+
+        let config = providers(Some(PriceChain {
+            crypto: vec![PriceProvider::Kraken, PriceProvider::Yahoo],
+            fiat: vec![],
+        }));
+        assert_eq!(
+            config.price_chain(AssetKind::Crypto),
+            vec![PriceProvider::Kraken, PriceProvider::Yahoo]
+        );
+        assert_eq!(
+            config.price_chain(AssetKind::Fiat),
+            vec![PriceProvider::Kraken]
+        );
+    }
+}
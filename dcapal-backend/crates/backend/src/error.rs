@@ -16,6 +16,8 @@ pub enum DcaError {
     Generic(String),
     #[error("Bad Request: {0}")]
     BadRequest(String),
+    #[error("Not Found: {0}")]
+    NotFound(String),
     #[error("Price for market '{0}/{1}' not available")]
     PriceNotAvailable(AssetId, AssetId),
     #[error("Price for market '{0}' not available")]
@@ -93,6 +95,7 @@ impl IntoResponse for DcaError {
         error!("{:?}", &self);
         match self {
             DcaError::BadRequest(_) => (StatusCode::BAD_REQUEST, format!("{self}")).into_response(),
+            DcaError::NotFound(_) => (StatusCode::NOT_FOUND, format!("{self}")).into_response(),
             DcaError::PriceNotAvailable(_, _) => {
                 (StatusCode::NOT_FOUND, format!("{self}")).into_response()
             }
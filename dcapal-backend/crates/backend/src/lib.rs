@@ -14,7 +14,7 @@ use chrono::prelude::*;
 use deadpool_redis::{Pool, Runtime};
 use futures::future::BoxFuture;
 use hyper::header;
-use metrics::{Unit, counter, describe_counter, describe_histogram};
+use metrics::{Unit, counter, describe_counter, describe_gauge, describe_histogram};
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use tokio::{net::TcpListener, task::JoinHandle};
 use tower::ServiceBuilder;
@@ -25,22 +25,37 @@ use crate::{
     app::{
         infra,
         services::{
-            ip2location::Ip2LocationService, market_data::MarketDataService,
-            portfolio::PortfolioService,
+            allocation::AllocationService, backtest::BacktestService, fetch_job::FetchJobService,
+            framework::FrameworkService, ip2location::Ip2LocationService,
+            market_data::MarketDataService, portfolio::PortfolioService,
+            price_series::PriceSeriesService, projection::ProjectionService,
+            reference_rate::ReferenceRateService,
+        },
+        workers::{
+            market_discovery::MarketDiscoveryWorker, price_series::PriceSeriesWorker,
+            price_updater::PriceUpdaterWorker, reference_rate::ReferenceRateWorker,
         },
-        workers::{market_discovery::MarketDiscoveryWorker, price_updater::PriceUpdaterWorker},
     },
     config::{Config, Postgres},
     error::{DcaError, Result},
     ports::{
         inbound::rest,
         outbound::{
-            adapter::{CryptoWatchProvider, IpApi, KrakenProvider, PriceProviders, YahooProvider},
+            adapter::{
+                CentralBankProvider, CryptoWatchProvider, EcbProvider, IpApi, KrakenProvider,
+                PriceProviders, YahooProvider,
+            },
             repository::{
                 ImportedRepository, MiscRepository, StatsRepository,
+                fetch_job::FetchJobRepository,
                 market_data::MarketDataRepository,
                 portfolio::PortfolioRepository,
-                postgres::{SqlxPortfolioRepository, SqlxUserRepository},
+                postgres::{
+                    SqlxFetchJobRepository, SqlxPortfolioRepository, SqlxPriceSeriesRepository,
+                    SqlxReferenceRateRepository, SqlxUserRepository,
+                },
+                price_series::PriceSeriesRepository,
+                reference_rate::ReferenceRateRepository,
                 user::UserRepository,
             },
         },
@@ -84,6 +99,13 @@ struct Services {
     mkt_data: Arc<MarketDataService>,
     ip2location: Option<Arc<Ip2LocationService>>,
     portfolio: Arc<PortfolioService>,
+    price_series: Arc<PriceSeriesService>,
+    fetch_job: Arc<FetchJobService>,
+    allocation: Arc<AllocationService>,
+    backtest: Arc<BacktestService>,
+    projection: Arc<ProjectionService>,
+    reference_rate: Arc<ReferenceRateService>,
+    framework: Arc<FrameworkService>,
 }
 
 #[derive(Clone)]
@@ -94,6 +116,9 @@ struct Repository {
     pub imported: Arc<ImportedRepository>,
     pub portfolio: Arc<dyn PortfolioRepository>,
     pub user: Arc<dyn UserRepository>,
+    pub price_series: Arc<dyn PriceSeriesRepository>,
+    pub fetch_job: Arc<dyn FetchJobRepository>,
+    pub reference_rate: Arc<dyn ReferenceRateRepository>,
 }
 
 /// The HTTP server and background workers that make up the backend process.
@@ -139,6 +164,9 @@ impl DcaServer {
             imported: Arc::new(ImportedRepository::new(redis.clone())),
             portfolio: Arc::new(SqlxPortfolioRepository::new(postgres.clone())),
             user: Arc::new(SqlxUserRepository::new(postgres.clone())),
+            price_series: Arc::new(SqlxPriceSeriesRepository::new(postgres.clone())),
+            fetch_job: Arc::new(SqlxFetchJobRepository::new(postgres.clone())),
+            reference_rate: Arc::new(SqlxReferenceRateRepository::new(postgres.clone())),
         });
 
         let providers = Arc::new(PriceProviders {
@@ -148,7 +176,9 @@ impl DcaServer {
             )),
             kraken: Arc::new(KrakenProvider::new(http.clone(), &config.app.providers)),
             yahoo: Arc::new(YahooProvider::new(rquest.clone())),
+            ecb: Arc::new(EcbProvider::new(http.clone())),
             ipapi: Arc::new(IpApi::new(http.clone(), &config.app.providers)),
+            rates: Arc::new(CentralBankProvider::new(http.clone())),
         });
 
         let ip2location = {
@@ -163,10 +193,19 @@ impl DcaServer {
             }
         };
 
+        let price_series = Arc::new(PriceSeriesService::new(repos.price_series.clone()));
+        let reference_rate = Arc::new(ReferenceRateService::new(repos.reference_rate.clone()));
         let services = Services {
             mkt_data: Arc::new(MarketDataService::new(repos.mkt_data.clone())),
             ip2location,
             portfolio: Arc::new(PortfolioService::new(repos.portfolio.clone())),
+            price_series: price_series.clone(),
+            fetch_job: Arc::new(FetchJobService::new(repos.fetch_job.clone())),
+            allocation: Arc::new(AllocationService::new()),
+            backtest: Arc::new(BacktestService::new(price_series.clone())),
+            projection: Arc::new(ProjectionService::new(price_series)),
+            reference_rate: reference_rate.clone(),
+            framework: Arc::new(FrameworkService::new(reference_rate)),
         };
 
         let (api_routes, openapi) = rest::build_openapi_router();
@@ -249,6 +288,28 @@ impl DcaServer {
             self.worker_handlers.push(handle);
         }
 
+        info!("Starting PriceSeries worker");
+        {
+            let ctx = self.ctx.clone();
+            let stop_rx = self.stop_tx.subscribe();
+            let handle = tokio::spawn(async move {
+                let worker = PriceSeriesWorker::new(&ctx, Duration::from_secs(60));
+                worker.run(stop_rx).await;
+            });
+            self.worker_handlers.push(handle);
+        }
+
+        info!("Starting ReferenceRate worker");
+        {
+            let ctx = self.ctx.clone();
+            let stop_rx = self.stop_tx.subscribe();
+            let handle = tokio::spawn(async move {
+                let worker = ReferenceRateWorker::new(&ctx, Duration::from_secs(60 * 60));
+                worker.run(stop_rx).await;
+            });
+            self.worker_handlers.push(handle);
+        }
+
         info!("Starting DcaServer at {}", &self.addr);
         let listener = TcpListener::bind(&self.addr)
             .await
@@ -283,6 +344,11 @@ impl DcaServer {
             Unit::Count,
             "Number of portfolios imported"
         );
+        describe_gauge!(
+            infra::stats::FETCH_JOBS,
+            Unit::Count,
+            "Number of market-data fetch jobs by kind and state"
+        );
 
         // Refresh Prometheus stats
         if let Err(e) = refresh_total_visitors_stats(&self.ctx.repos.stats).await {
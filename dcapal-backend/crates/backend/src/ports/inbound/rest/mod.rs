@@ -18,13 +18,18 @@ use utoipa::{
     openapi::{Info, OpenApi, Paths},
 };
 use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
 
 use crate::{
-    AppContext,
+    AppContext, DateTime,
     app::{
-        domain::entity::AssetKind,
-        infra::utils::Expiring,
-        services::command::{ConversionRateQuery, ImportPortfolioCmd},
+        domain::entity::{AssetKind, RateType, SeriesTimeframe},
+        infra::{claim::Claims, utils::Expiring},
+        services::command::{
+            AllocateCmd, AllocationParams, BacktestCmd, BacktestSchedule, ConversionRateQuery,
+            ConversionRatesQuery, ImportPortfolioCmd, PriceSeriesQuery, ProjectionCmd,
+            ProjectionParams, TheBullCmd,
+        },
     },
     error::{DcaError, Result},
     infra::stats,
@@ -65,7 +70,17 @@ pub fn build_openapi_router() -> (Router<AppContext>, OpenApi) {
 }
 
 fn build_v1_openapi_router() -> OpenApiRouter<AppContext> {
-    OpenApiRouter::new().routes(routes!(request::sync_portfolios))
+    OpenApiRouter::new()
+        .routes(routes!(request::sync_portfolios))
+        .routes(routes!(get_prices, post_prices))
+        .routes(routes!(get_market))
+        .routes(routes!(get_price_series))
+        .routes(routes!(allocate))
+        .routes(routes!(allocate_portfolio))
+        .routes(routes!(backtest))
+        .routes(routes!(project))
+        .routes(routes!(get_reference_rate))
+        .routes(routes!(get_the_bull_v1))
 }
 
 fn base_openapi() -> OpenApi {
@@ -280,6 +295,419 @@ pub async fn get_price(
     Ok(response.into_response())
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+/// Assets and quote currency of a batch conversion-price request.
+pub struct GetPricesQuery {
+    /// Comma-separated base asset symbols.
+    assets: String,
+    /// Quote currency.
+    quote: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Assets and quote currency of a batch conversion-price request.
+pub struct PricesRequest {
+    /// Base asset symbols.
+    pub assets: Vec<String>,
+    /// Quote currency.
+    pub quote: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/prices",
+    params(GetPricesQuery),
+    responses(
+        (
+            status = 200,
+            description = "Conversion prices, and the reason of each asset that could not be priced",
+            body = response::PricesResponse
+        ),
+        (status = 400, description = "Unknown quote asset, or no or too many assets")
+    )
+)]
+/// Returns the current conversion price of several assets in one quote currency.
+pub async fn get_prices(
+    Query(query): Query<GetPricesQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let assets = query.assets.split(',').collect::<Vec<_>>();
+    prices(&assets, &query.quote, &ctx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/prices",
+    request_body = PricesRequest,
+    responses(
+        (
+            status = 200,
+            description = "Conversion prices, and the reason of each asset that could not be priced",
+            body = response::PricesResponse
+        ),
+        (status = 400, description = "Unknown quote asset, or no or too many assets")
+    )
+)]
+/// Returns the current conversion price of several assets in one quote
+/// currency, for asset lists too long for a query string.
+pub async fn post_prices(
+    State(ctx): State<AppContext>,
+    Json(request): Json<PricesRequest>,
+) -> Result<Response> {
+    prices(&request.assets, &request.quote, &ctx).await
+}
+
+async fn prices<S: AsRef<str>>(assets: &[S], quote: &str, ctx: &AppContext) -> Result<Response> {
+    let repo = &ctx.repos.mkt_data;
+    let service = &ctx.services.mkt_data;
+
+    let cmd = ConversionRatesQuery::try_new(assets, quote, repo).await?;
+    let quote = cmd.quote.id().clone();
+
+    let rates = service.get_conversion_rates(cmd).await?;
+
+    // The response is only fresh as long as its stalest price
+    let max_age = rates
+        .iter()
+        .filter_map(|(_, rate)| rate.as_ref().ok())
+        .map(|price| price.time_to_live())
+        .min()
+        .unwrap_or_default();
+    let cache_control = CacheControl::new().with_public().with_max_age(max_age);
+
+    let response = response::PricesResponse::new(quote, rates);
+    Ok((TypedHeader(cache_control), Json(response)).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+/// Path parameters identifying a market.
+pub struct GetMarketPath {
+    /// Market id, e.g. `btceur`.
+    id: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/markets/{id}",
+    params(GetMarketPath),
+    responses(
+        (status = 200, description = "Market and its trading rules", body = response::MarketResponse),
+        (status = 404, description = "Market not found")
+    )
+)]
+/// Returns a market, its latest price and the order constraints of its exchange.
+pub async fn get_market(
+    Path(path): Path<GetMarketPath>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let id = path.id.to_lowercase();
+    let market = ctx
+        .services
+        .mkt_data
+        .get_market(&id)
+        .await?
+        .ok_or_else(|| DcaError::NotFound(format!("Market '{id}' not found")))?;
+
+    let response = (
+        TypedHeader(cache_control(market.as_ref())),
+        Json(response::MarketResponse::from(market.as_ref())),
+    );
+    Ok(response.into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+/// Path parameters identifying a provider-bound price series.
+pub struct GetPriceSeriesPath {
+    /// Market data provider (`kraken` or `yahoo`).
+    provider: String,
+    /// Provider instrument symbol of a known market.
+    symbol: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+/// Sampling and range parameters for a price-series request.
+pub struct GetPriceSeriesQuery {
+    /// Provider exchange or market, when the provider exposes one.
+    market: Option<String>,
+    /// Sampling interval. Defaults to `daily`.
+    #[serde(default = "default_series_timeframe")]
+    #[param(inline)]
+    timeframe: SeriesTimeframe,
+    /// Range start (RFC 3339). Defaults to the backfill depth before `to`.
+    from: Option<DateTime>,
+    /// Range end (RFC 3339). Defaults to now.
+    to: Option<DateTime>,
+}
+
+fn default_series_timeframe() -> SeriesTimeframe {
+    SeriesTimeframe::Daily
+}
+
+#[utoipa::path(
+    get,
+    path = "/series/{provider}/{symbol}",
+    params(GetPriceSeriesPath, GetPriceSeriesQuery),
+    responses(
+        (
+            status = 200,
+            description = "Stored price series and its coverage",
+            body = response::PriceSeriesResponse
+        ),
+        (status = 400, description = "Unsupported provider, unknown market or invalid range")
+    )
+)]
+/// Returns the stored samples of a price series, registering it for ingestion
+/// when it is requested for the first time.
+pub async fn get_price_series(
+    Path(path): Path<GetPriceSeriesPath>,
+    Query(query): Query<GetPriceSeriesQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let repo = &ctx.repos.mkt_data;
+    let service = &ctx.services.price_series;
+
+    let cmd = PriceSeriesQuery::try_new(
+        &path.provider,
+        &path.symbol,
+        query.market,
+        query.timeframe,
+        query.from,
+        query.to,
+        repo,
+    )
+    .await?;
+
+    let series = service.get_series(cmd).await?;
+
+    Ok(Json(response::PriceSeriesResponse::from(series)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/allocate",
+    request_body = request::AllocateRequest,
+    responses(
+        (status = 200, description = "Allocation recommendation", body = response::AllocationResponse),
+        (status = 400, description = "Invalid budget, assets, weights or fees")
+    )
+)]
+/// Splits a budget across the assets of a portfolio supplied in the request.
+pub async fn allocate(
+    State(ctx): State<AppContext>,
+    Json(req): Json<request::AllocateRequest>,
+) -> Result<Response> {
+    let params = AllocationParams {
+        budget: req.budget,
+        is_buy_only: req.is_buy_only,
+        use_all_budget: req.use_all_budget,
+        capital_gains_tax: req.capital_gains_tax.map(Into::into),
+    };
+    let assets = req.assets.into_iter().map(Into::into).collect();
+
+    let cmd = AllocateCmd::try_new(params, &req.pfolio_ccy, assets, req.fees.map(Into::into))?;
+
+    let solution = ctx.services.allocation.allocate(cmd).await?;
+
+    Ok(Json(response::AllocationResponse::from(solution)).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+/// Path parameters identifying a saved portfolio.
+pub struct PortfolioPath {
+    /// Portfolio id.
+    id: Uuid,
+}
+
+#[utoipa::path(
+    post,
+    path = "/portfolios/{id}/allocate",
+    params(
+        ("Authorization" = String, Header, description = "Bearer JWT token"),
+        PortfolioPath
+    ),
+    request_body = request::AllocatePortfolioRequest,
+    responses(
+        (status = 200, description = "Allocation recommendation", body = response::AllocationResponse),
+        (status = 400, description = "Invalid budget, or saved portfolio not allocatable"),
+        (status = 404, description = "Portfolio not found")
+    )
+)]
+/// Splits a budget across the assets of a saved portfolio of the user.
+pub async fn allocate_portfolio(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+    Path(path): Path<PortfolioPath>,
+    Json(req): Json<request::AllocatePortfolioRequest>,
+) -> Result<Response> {
+    let params = AllocationParams {
+        budget: req.budget,
+        is_buy_only: req.is_buy_only,
+        use_all_budget: req.use_all_budget,
+        capital_gains_tax: req.capital_gains_tax.map(Into::into),
+    };
+
+    let cmd = AllocateCmd::try_new_for_portfolio(
+        claims.sub,
+        path.id,
+        params,
+        req.use_whole_shares,
+        ctx.repos.portfolio.as_ref(),
+    )
+    .await?;
+
+    let solution = ctx.services.allocation.allocate(cmd).await?;
+
+    Ok(Json(response::AllocationResponse::from(solution)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/backtest",
+    request_body = request::BacktestRequest,
+    responses(
+        (status = 200, description = "Backtest results", body = response::BacktestResponse),
+        (status = 400, description = "Invalid strategy, unknown series or missing prices")
+    )
+)]
+/// Replays periodic contributions over the stored daily prices of the assets.
+pub async fn backtest(
+    State(ctx): State<AppContext>,
+    Json(req): Json<request::BacktestRequest>,
+) -> Result<Response> {
+    let params = AllocationParams {
+        budget: req.contribution,
+        is_buy_only: req.is_buy_only,
+        use_all_budget: req.use_all_budget,
+        capital_gains_tax: None,
+    };
+    let schedule = BacktestSchedule {
+        interval_days: req.interval_days,
+        from: req.from,
+        to: req.to,
+    };
+    let assets = req.assets.into_iter().map(Into::into).collect();
+
+    let cmd = BacktestCmd::try_new(
+        params,
+        schedule,
+        &req.pfolio_ccy,
+        assets,
+        req.fees.map(Into::into),
+        &ctx.repos.mkt_data,
+    )
+    .await?;
+
+    let solution = ctx.services.backtest.backtest(cmd).await?;
+
+    Ok(Json(response::BacktestResponse::from(solution)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/projection",
+    request_body = request::ProjectionRequest,
+    responses(
+        (status = 200, description = "Projected value percentiles", body = response::ProjectionResponse),
+        (status = 400, description = "Invalid plan, unknown series or too few prices to estimate returns")
+    )
+)]
+/// Projects a contribution plan over the years with Monte Carlo simulations.
+pub async fn project(
+    State(ctx): State<AppContext>,
+    Json(req): Json<request::ProjectionRequest>,
+) -> Result<Response> {
+    let params = ProjectionParams {
+        initial_value: req.initial_value,
+        contribution: req.contribution,
+        periods_per_year: req.periods_per_year,
+        years: req.years,
+        paths: req.paths,
+        seed: req.seed,
+        goal: req.goal,
+    };
+    let classes = req
+        .classes
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<_>>>()?;
+
+    let cmd =
+        ProjectionCmd::try_new(params, classes, req.from, req.to, &ctx.repos.mkt_data).await?;
+
+    let solution = ctx.services.projection.project(cmd).await?;
+
+    Ok(Json(response::ProjectionResponse::from(solution)).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+/// Path parameters identifying a reference rate.
+pub struct GetReferenceRatePath {
+    /// Reference rate (`fed_effr` or `ecb_estr`).
+    #[param(inline)]
+    rate_type: RateType,
+}
+
+#[utoipa::path(
+    get,
+    path = "/rates/{rate_type}",
+    params(GetReferenceRatePath),
+    responses(
+        (status = 200, description = "Latest reference rate observation", body = response::ReferenceRateResponse),
+        (status = 404, description = "No recent observation ingested")
+    )
+)]
+/// Returns the latest ingested observation of a reference rate.
+pub async fn get_reference_rate(
+    Path(path): Path<GetReferenceRatePath>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let rate = ctx.services.reference_rate.latest(path.rate_type).await?;
+
+    Ok(Json(response::ReferenceRateResponse::from(rate)).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+/// Inputs of the `the-bull-v1` framework.
+pub struct GetTheBullQuery {
+    /// Investor age, in completed years.
+    age_years: u32,
+    /// Reference rate used as the risk-free rate (`fed_effr` or `ecb_estr`).
+    #[param(inline)]
+    rate_type: RateType,
+}
+
+#[utoipa::path(
+    get,
+    path = "/frameworks/the-bull-v1",
+    params(GetTheBullQuery),
+    responses(
+        (status = 200, description = "Suggested equity/defensive split", body = response::TheBullResponse),
+        (status = 400, description = "Invalid age or rate type"),
+        (status = 404, description = "No recent observation of the selected rate")
+    )
+)]
+/// Returns the equity/defensive split suggested by `the-bull-v1` for an age,
+/// computed with the latest observation of the selected rate.
+pub async fn get_the_bull_v1(
+    Query(query): Query<GetTheBullQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let cmd = TheBullCmd::try_new(query.age_years, query.rate_type)?;
+
+    let guidance = ctx.services.framework.the_bull_v1(cmd).await?;
+
+    Ok(Json(response::TheBullResponse::from(guidance)).into_response())
+}
+
 fn cache_control<T: Expiring>(t: &T) -> CacheControl {
     // Cache only until the domain object itself becomes stale.
     CacheControl::new()
@@ -398,6 +826,17 @@ pub enum FeeStructure {
         )]
         max_fee: Option<Decimal>,
     },
+
+    /// The fee of the first bracket whose upper bound covers the order amount.
+    #[serde(rename = "tiered")]
+    Tiered { tiers: Vec<FeeTier> },
+
+    /// The sum of several fee structures, e.g. a fixed part plus a rate.
+    #[serde(rename = "composite")]
+    Composite {
+        #[schema(no_recursion)]
+        components: Vec<FeeStructure>,
+    },
 }
 
 impl Display for FeeStructure {
@@ -406,6 +845,26 @@ impl Display for FeeStructure {
             FeeStructure::ZeroFee => write!(f, "ZeroFee"),
             FeeStructure::Fixed { .. } => write!(f, "Fixed"),
             FeeStructure::Variable { .. } => write!(f, "Variable"),
+            FeeStructure::Tiered { .. } => write!(f, "Tiered"),
+            FeeStructure::Composite { .. } => write!(f, "Composite"),
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+/// An amount bracket of a tiered fee policy.
+pub struct FeeTier {
+    #[serde(
+        rename = "upTo",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::str_option"
+    )]
+    /// Inclusive upper bound of the order amounts in the bracket. Only the
+    /// last bracket may be unbounded.
+    pub up_to: Option<Decimal>,
+    #[serde(rename = "feeStructure")]
+    #[schema(no_recursion)]
+    /// The fee charged in the bracket.
+    pub fee_structure: FeeStructure,
+}
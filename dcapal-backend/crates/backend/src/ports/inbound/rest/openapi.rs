@@ -149,6 +149,15 @@ mod tests {
             "/import/portfolio",
             "/import/portfolio/{id}",
             "/v1/sync/portfolios",
+            "/v1/prices",
+            "/v1/markets/{id}",
+            "/v1/allocate",
+            "/v1/portfolios/{id}/allocate",
+            "/v1/series/{provider}/{symbol}",
+            "/v1/backtest",
+            "/v1/projection",
+            "/v1/rates/{rate_type}",
+            "/v1/frameworks/the-bull-v1",
         ] {
             assert!(paths.contains_key(expected), "missing path {expected}");
         }
@@ -10,7 +10,20 @@ use tracing::{error, info};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{AppContext, DateTime, app::infra::claim::Claims, ports::inbound::rest::FeeStructure};
+use dcapal_optimizer_wasm::optimize::{
+    self, FeeStructureComposite, FeeStructureFixed, FeeStructureTiered, FeeStructureVariable,
+    FeeTier, advanced, projection,
+};
+
+use crate::{
+    AppContext, DateTime,
+    app::{
+        infra::claim::Claims,
+        services::command::{BacktestAsset, ProjectionClass, ProjectionReturns},
+    },
+    error::DcaError,
+    ports::inbound::rest::FeeStructure,
+};
 
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -57,6 +70,328 @@ pub struct TransactionFeesRequest {
     pub fee_structure: FeeStructure,
 }
 
+impl From<TransactionFeesRequest> for optimize::TransactionFees {
+    fn from(fees: TransactionFeesRequest) -> Self {
+        Self {
+            max_fee_impact: fees
+                .max_fee_impact
+                .unwrap_or_else(optimize::TransactionFees::default_max_fee_impact),
+            fee_structure: fees.fee_structure.into(),
+            sell_fee_structure: None,
+        }
+    }
+}
+
+impl From<FeeStructure> for optimize::FeeStructure {
+    fn from(fee: FeeStructure) -> Self {
+        match fee {
+            FeeStructure::ZeroFee => Self::default(),
+            FeeStructure::Fixed { fee_amount } => Self::Fixed(FeeStructureFixed { fee_amount }),
+            FeeStructure::Variable {
+                fee_rate,
+                min_fee,
+                max_fee,
+            } => Self::Variable(FeeStructureVariable {
+                min_fee: Some(min_fee),
+                max_fee,
+                fee_rate,
+            }),
+            FeeStructure::Tiered { tiers } => Self::Tiered(FeeStructureTiered {
+                tiers: tiers
+                    .into_iter()
+                    .map(|t| FeeTier {
+                        up_to: t.up_to,
+                        fee_structure: t.fee_structure.into(),
+                    })
+                    .collect(),
+            }),
+            FeeStructure::Composite { components } => Self::Composite(FeeStructureComposite {
+                components: components.into_iter().map(Into::into).collect(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A complete allocation problem: the portfolio, its fee policy and the budget to invest.
+pub struct AllocateRequest {
+    /// The amount to invest, or to withdraw if negative, in the portfolio quote currency.
+    pub budget: Decimal,
+    /// The portfolio quote currency.
+    pub pfolio_ccy: String,
+    /// The portfolio assets.
+    pub assets: Vec<AllocateAssetRequest>,
+    /// The portfolio-level transaction fee settings.
+    pub fees: Option<TransactionFeesRequest>,
+    /// Whether over-allocated assets must be kept rather than sold.
+    pub is_buy_only: bool,
+    /// Whether the budget left by the main allocation should be spent too.
+    #[serde(default)]
+    pub use_all_budget: bool,
+    /// The tax due on the gains realized by selling over-allocated assets.
+    pub capital_gains_tax: Option<CapitalGainsTaxRequest>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+/// How realized capital gains are taxed.
+pub struct CapitalGainsTaxRequest {
+    /// The tax rate applied to realized gains, in [0, 1].
+    pub rate: Decimal,
+    /// Whether losses realized by a sale reduce the taxable gains of the others.
+    #[serde(default)]
+    pub offset_losses: bool,
+    /// The largest tax the rebalancing sales may owe altogether. Overweight
+    /// assets with the lowest gains are sold first. Unbounded when missing.
+    pub max_tax: Option<Decimal>,
+}
+
+impl From<CapitalGainsTaxRequest> for advanced::CapitalGainsTax {
+    fn from(tax: CapitalGainsTaxRequest) -> Self {
+        Self {
+            rate: tax.rate,
+            offset_losses: tax.offset_losses,
+            max_tax: tax.max_tax,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// An asset of an allocation problem.
+pub struct AllocateAssetRequest {
+    /// The asset symbol, unique within the request.
+    pub symbol: String,
+    /// The quantity held.
+    pub shares: Decimal,
+    /// The price, in the portfolio quote currency.
+    pub price: Decimal,
+    /// The target portfolio weight, in [0, 1].
+    pub target_weight: Decimal,
+    /// Whether the asset can only be bought in whole shares.
+    #[serde(default)]
+    pub is_whole_shares: bool,
+    /// Asset-level transaction fee settings overriding the portfolio ones.
+    pub fees: Option<TransactionFeesRequest>,
+    /// The smallest tradable quantity increment, e.g. a market lot size.
+    pub quantity_step: Option<Decimal>,
+    /// The smallest quantity a single order may trade.
+    pub min_quantity: Option<Decimal>,
+    /// The smallest value a single order may trade, in the portfolio quote currency.
+    pub min_order_amount: Option<Decimal>,
+    /// The average price paid for the quantity held, used as cost basis of sales.
+    pub average_buy_price: Option<Decimal>,
+}
+
+impl From<AllocateAssetRequest> for advanced::ProblemAsset {
+    fn from(asset: AllocateAssetRequest) -> Self {
+        Self {
+            symbol: asset.symbol,
+            shares: asset.shares,
+            price: asset.price,
+            target_weight: asset.target_weight,
+            is_whole_shares: asset.is_whole_shares,
+            fees: asset.fees.map(Into::into),
+            quantity_step: asset.quantity_step,
+            min_quantity: asset.min_quantity,
+            min_order_amount: asset.min_order_amount,
+            average_buy_price: asset.average_buy_price,
+            tolerance_band: None,
+            price_ccy: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// The budget to invest in a saved portfolio, and how to invest it.
+pub struct AllocatePortfolioRequest {
+    /// The amount to invest, or to withdraw if negative, in the portfolio quote currency.
+    pub budget: Decimal,
+    /// Whether over-allocated assets must be kept rather than sold.
+    pub is_buy_only: bool,
+    /// Whether the budget left by the main allocation should be spent too.
+    #[serde(default)]
+    pub use_all_budget: bool,
+    /// Whether equities are bought in whole shares.
+    #[serde(default = "default_use_whole_shares")]
+    pub use_whole_shares: bool,
+    /// The tax due on the gains realized by selling over-allocated assets.
+    pub capital_gains_tax: Option<CapitalGainsTaxRequest>,
+}
+
+fn default_use_whole_shares() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A DCA strategy replayed over the stored daily price series of its assets.
+pub struct BacktestRequest {
+    /// The amount contributed at each date, in the portfolio quote currency.
+    pub contribution: Decimal,
+    /// The number of days between two contributions. The range may span at
+    /// most 1000 contributions.
+    pub interval_days: u32,
+    /// The portfolio quote currency. Every series must be quoted in it, as
+    /// prices are not converted.
+    pub pfolio_ccy: String,
+    /// The portfolio assets.
+    pub assets: Vec<BacktestAssetRequest>,
+    /// The portfolio-level transaction fee settings.
+    pub fees: Option<TransactionFeesRequest>,
+    /// Whether over-allocated assets must be kept rather than sold.
+    pub is_buy_only: bool,
+    /// Whether the contribution left by the main allocation should be spent too.
+    #[serde(default)]
+    pub use_all_budget: bool,
+    /// The backtest start (RFC 3339). Defaults to the backfill depth before `to`.
+    pub from: Option<DateTime>,
+    /// The backtest end (RFC 3339). Defaults to now.
+    pub to: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// An asset of a backtest and the price series it is replayed over.
+pub struct BacktestAssetRequest {
+    /// The provider instrument symbol, unique within the request.
+    pub symbol: String,
+    /// The market data provider of the series (`kraken` or `yahoo`).
+    pub provider: String,
+    /// The provider exchange or market, when the provider exposes one.
+    pub market: Option<String>,
+    /// The quantity held when the backtest starts.
+    #[serde(default)]
+    pub shares: Decimal,
+    /// The target portfolio weight, in [0, 1].
+    pub target_weight: Decimal,
+    /// Whether the asset can only be bought in whole shares.
+    #[serde(default)]
+    pub is_whole_shares: bool,
+    /// Asset-level transaction fee settings overriding the portfolio ones.
+    pub fees: Option<TransactionFeesRequest>,
+}
+
+impl From<BacktestAssetRequest> for BacktestAsset {
+    fn from(asset: BacktestAssetRequest) -> Self {
+        Self {
+            asset: advanced::ProblemAsset {
+                symbol: asset.symbol,
+                shares: asset.shares,
+                // Replaced by the series price at each date
+                price: Decimal::ONE,
+                target_weight: asset.target_weight,
+                is_whole_shares: asset.is_whole_shares,
+                fees: asset.fees.map(Into::into),
+                quantity_step: None,
+                min_quantity: None,
+                min_order_amount: None,
+                average_buy_price: None,
+                tolerance_band: None,
+                price_ccy: None,
+            },
+            provider: asset.provider,
+            market: asset.market,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A contribution plan projected over the years with Monte Carlo simulations.
+pub struct ProjectionRequest {
+    /// The portfolio value when the projection starts.
+    #[serde(default)]
+    pub initial_value: Decimal,
+    /// The amount contributed at the end of every period.
+    pub contribution: Decimal,
+    /// The number of contributions per year, e.g. 12 for monthly ones, at most 365.
+    pub periods_per_year: u32,
+    /// The projection horizon, in years.
+    pub years: u32,
+    /// The number of simulated paths, at most 10000. Paths times years times
+    /// periods per year may not exceed 10 million.
+    #[serde(default = "default_projection_paths")]
+    pub paths: u32,
+    /// The seed of the simulated paths. Equal requests with equal seeds get equal results.
+    #[serde(default)]
+    pub seed: u64,
+    /// The portfolio value to reach by the end of the projection.
+    pub goal: Option<Decimal>,
+    /// The portfolio asset classes.
+    pub classes: Vec<ProjectionClassRequest>,
+    /// The start of the prices returns are estimated from (RFC 3339). Defaults to the backfill depth before `to`.
+    pub from: Option<DateTime>,
+    /// The end of the prices returns are estimated from (RFC 3339). Defaults to now.
+    pub to: Option<DateTime>,
+}
+
+fn default_projection_paths() -> u32 {
+    1000
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// An asset class of a projection, with either configured returns or a series to estimate them from.
+pub struct ProjectionClassRequest {
+    /// The asset class name, unique within the request.
+    pub name: String,
+    /// The target portfolio weight, in [0, 1].
+    pub target_weight: Decimal,
+    /// The mean yearly return, as a rate. Set together with `volatility`.
+    pub expected_return: Option<Decimal>,
+    /// The standard deviation of the yearly log return, in [0, 5]. Set together with `expectedReturn`.
+    pub volatility: Option<Decimal>,
+    /// The stored daily series returns are estimated from, when not configured.
+    pub series: Option<ProjectionSeriesRequest>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A stored price series representative of an asset class.
+pub struct ProjectionSeriesRequest {
+    /// The market data provider of the series (`kraken` or `yahoo`).
+    pub provider: String,
+    /// The provider instrument symbol.
+    pub symbol: String,
+    /// The provider exchange or market, when the provider exposes one.
+    pub market: Option<String>,
+}
+
+impl TryFrom<ProjectionClassRequest> for ProjectionClass {
+    type Error = DcaError;
+
+    fn try_from(class: ProjectionClassRequest) -> Result<Self, Self::Error> {
+        let returns = match (class.expected_return, class.volatility, class.series) {
+            (Some(expected_return), Some(volatility), None) => {
+                ProjectionReturns::Configured(projection::ReturnModel {
+                    expected_return,
+                    volatility,
+                })
+            }
+            (None, None, Some(series)) => ProjectionReturns::Estimated {
+                provider: series.provider,
+                symbol: series.symbol,
+                market: series.market,
+            },
+            _ => {
+                return Err(DcaError::BadRequest(format!(
+                    "Asset class {} must set either expectedReturn and volatility, or series",
+                    class.name
+                )));
+            }
+        };
+
+        Ok(Self {
+            name: class.name,
+            target_weight: class.target_weight,
+            returns,
+        })
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/sync/portfolios",
@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+
+use dcapal_optimizer_wasm::optimize::{
+    advanced::{Solution, SolutionState, TheoreticalAllocation, Withdrawal},
+    backtest, projection,
+};
 use rust_decimal::Decimal;
 use serde::Serialize;
 use utoipa::ToSchema;
@@ -5,6 +11,16 @@ use uuid::Uuid;
 
 use crate::{
     DateTime,
+    app::{
+        domain::{
+            entity::{
+                AssetId, CoverageStatus, Market, MarketId, Price, PriceUnavailableReason, RateType,
+                ReferenceRate, SeriesTimeframe, TradingRules,
+            },
+            framework::THE_BULL_V1,
+        },
+        services::{framework::TheBullGuidance, price_series::PriceSeries},
+    },
     error::DcaError,
     ports::{
         inbound::rest::FeeStructure,
@@ -81,46 +97,13 @@ impl TryFrom<(PortfolioRow, Vec<PortfolioAssetRow>)> for PortfolioResponse {
         let portfolio_assets: Vec<PortfolioAssetResponse> = assets
             .iter()
             .map(|asset| {
-                let fees = if let Some(fee_type) = asset.fee_type.clone() {
-                    Some(TransactionFeesResponse {
-                        max_fee_impact: asset.max_fee_impact,
-                        fee_structure: match fee_type {
-                            val if val == *"ZeroFee" => FeeStructure::ZeroFee,
-                            val if val == *"Fixed" => {
-                                if let Some(fee_amount) = asset.fee_amount {
-                                    FeeStructure::Fixed { fee_amount }
-                                } else {
-                                    return Err(DcaError::Generic(
-                                        "Fixed fee requires fee_amount to be Some.".to_string(),
-                                    ));
-                                }
-                            }
-                            val if val == *"Variable" => {
-                                if let (Some(fee_rate), Some(min_fee)) =
-                                    (asset.fee_rate, asset.min_fee)
-                                {
-                                    FeeStructure::Variable {
-                                        fee_rate,
-                                        min_fee,
-                                        max_fee: asset.max_fee,
-                                    }
-                                } else {
-                                    return Err(DcaError::Generic(
-                                        "Variable fee requires fee_rate and min_fee to be Some."
-                                            .to_string(),
-                                    ));
-                                }
-                            }
-                            _ => {
-                                return Err(DcaError::Generic(
-                                    "Fee type is not specified.".to_string(),
-                                ));
-                            }
-                        },
-                    })
-                } else {
-                    None
-                };
+                let fees =
+                    asset
+                        .stored_fee_structure()?
+                        .map(|fee_structure| TransactionFeesResponse {
+                            max_fee_impact: asset.max_fee_impact,
+                            fee_structure,
+                        });
 
                 Ok(PortfolioAssetResponse {
                     symbol: asset.symbol.clone(),
@@ -135,52 +118,18 @@ impl TryFrom<(PortfolioRow, Vec<PortfolioAssetRow>)> for PortfolioResponse {
                     fees,
                 })
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect::<Result<Vec<_>, DcaError>>()?;
 
         Ok(Self {
             id: portfolio.id,
             name: portfolio.name.clone(),
             quote_ccy: portfolio.currency.clone(),
-            fees: if let Some(fee_type) = portfolio.fee_type {
-                Some(TransactionFeesResponse {
+            fees: portfolio
+                .stored_fee_structure()?
+                .map(|fee_structure| TransactionFeesResponse {
                     max_fee_impact: portfolio.max_fee_impact,
-                    fee_structure: match fee_type {
-                        val if val == *"ZeroFee" => FeeStructure::ZeroFee,
-                        val if val == *"Fixed" => {
-                            if let Some(fee_amount) = portfolio.fee_amount {
-                                FeeStructure::Fixed { fee_amount }
-                            } else {
-                                return Err(DcaError::Generic(
-                                    "Fixed fee requires fee_amount to be Some.".to_string(),
-                                ));
-                            }
-                        }
-                        val if val == *"Variable" => {
-                            if let (Some(fee_rate), Some(min_fee)) =
-                                (portfolio.fee_rate, portfolio.min_fee)
-                            {
-                                FeeStructure::Variable {
-                                    fee_rate,
-                                    min_fee,
-                                    max_fee: portfolio.max_fee,
-                                }
-                            } else {
-                                return Err(DcaError::Generic(
-                                    "Variable fee requires fee_rate and min_fee to be Some."
-                                        .to_string(),
-                                ));
-                            }
-                        }
-                        _ => {
-                            return Err(DcaError::Generic(
-                                "Fee type is not specified.".to_string(),
-                            ));
-                        }
-                    },
-                })
-            } else {
-                None
-            },
+                    fee_structure,
+                }),
             assets: portfolio_assets,
             last_updated_at: portfolio.last_updated_at,
         })
@@ -202,6 +151,571 @@ pub struct TransactionFeesResponse {
     pub fee_structure: FeeStructure,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A market and the order constraints of its exchange.
+pub struct MarketResponse {
+    /// The market id.
+    pub id: MarketId,
+    /// The display pair, e.g. `BTC/EUR`.
+    pub pair: String,
+    /// The base asset id.
+    pub base: AssetId,
+    /// The quote asset id.
+    pub quote: AssetId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The latest cached price, when available.
+    pub price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The order constraints, when the listing provider publishes them.
+    pub trading_rules: Option<TradingRulesResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// Order constraints of a market.
+///
+/// Decimal values are serialized as JSON strings to preserve precision.
+pub struct TradingRulesResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Decimals allowed in an order quantity.
+    pub lot_decimals: Option<u32>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::str_option"
+    )]
+    /// The smallest orderable quantity increment, in base units.
+    pub quantity_step: Option<Decimal>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::str_option"
+    )]
+    /// The smallest order quantity, in base units.
+    pub min_quantity: Option<Decimal>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::str_option"
+    )]
+    /// The smallest order cost, in quote units.
+    pub min_cost: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Decimals allowed in an order price.
+    pub price_decimals: Option<u32>,
+}
+
+impl From<&Market> for MarketResponse {
+    fn from(market: &Market) -> Self {
+        Self {
+            id: market.id.clone(),
+            pair: market.pair.clone(),
+            base: market.base.id().clone(),
+            quote: market.quote.id().clone(),
+            price: *market.price(),
+            trading_rules: market.trading_rules().map(Into::into),
+        }
+    }
+}
+
+impl From<&TradingRules> for TradingRulesResponse {
+    fn from(rules: &TradingRules) -> Self {
+        Self {
+            lot_decimals: rules.lot_decimals,
+            quantity_step: rules.quantity_step(),
+            min_quantity: rules.min_quantity,
+            min_cost: rules.min_cost,
+            price_decimals: rules.price_decimals,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// A stored price series over the requested range.
+///
+/// Samples are actual provider observations in ascending order; missing
+/// buckets are not filled in.
+pub struct PriceSeriesResponse {
+    /// The market data provider serving the series.
+    pub provider: String,
+    /// The provider's instrument symbol.
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The provider exchange or market, when the series has one.
+    pub market: Option<String>,
+    /// The sampling interval.
+    pub timeframe: SeriesTimeframe,
+    /// The requested range start.
+    pub from: DateTime,
+    /// The requested range end.
+    pub to: DateTime,
+    /// How much of the requested range the stored samples cover.
+    pub status: CoverageStatus,
+    /// The stored samples in the requested range.
+    pub samples: Vec<PriceSampleResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// A closed price-series bucket.
+pub struct PriceSampleResponse {
+    /// The provider bucket open time.
+    pub observed_at: DateTime,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The bucket close value.
+    pub value: Decimal,
+}
+
+impl From<PriceSeries> for PriceSeriesResponse {
+    fn from(series: PriceSeries) -> Self {
+        Self {
+            provider: series.key.provider.to_string(),
+            symbol: series.key.instrument,
+            market: (!series.key.market.is_empty()).then_some(series.key.market),
+            timeframe: series.timeframe,
+            from: series.from,
+            to: series.to,
+            status: series.status,
+            samples: series
+                .samples
+                .into_iter()
+                .map(|s| PriceSampleResponse {
+                    observed_at: s.observed_at,
+                    value: s.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Conversion prices of several assets in one quote asset.
+pub struct PricesResponse {
+    /// The quote asset of every price.
+    pub quote: AssetId,
+    /// The price of each asset that could be priced.
+    pub prices: BTreeMap<AssetId, Price>,
+    /// The assets that could not be priced, with the reason.
+    pub unavailable: BTreeMap<AssetId, UnavailablePriceResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// Why an asset of a batch price request could not be priced.
+pub struct UnavailablePriceResponse {
+    /// A stable, machine-readable reason.
+    pub reason: PriceUnavailableReason,
+    /// A human-readable description.
+    pub message: String,
+}
+
+impl PricesResponse {
+    /// Splits per-asset conversion results into prices and failures.
+    pub fn new(
+        quote: AssetId,
+        rates: Vec<(AssetId, std::result::Result<Price, PriceUnavailableReason>)>,
+    ) -> Self {
+        let mut prices = BTreeMap::new();
+        let mut unavailable = BTreeMap::new();
+        for (base, rate) in rates {
+            match rate {
+                Ok(price) => {
+                    prices.insert(base, price);
+                }
+                Err(reason) => {
+                    let message = match reason {
+                        PriceUnavailableReason::UnknownAsset => {
+                            format!("Unknown base asset: {base}")
+                        }
+                        PriceUnavailableReason::PriceNotAvailable => {
+                            format!("Price not available for {base}/{quote}")
+                        }
+                    };
+                    unavailable.insert(base, UnavailablePriceResponse { reason, message });
+                }
+            }
+        }
+
+        Self {
+            quote,
+            prices,
+            unavailable,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// An allocation recommendation.
+///
+/// Decimal values are serialized as JSON strings to preserve precision.
+pub struct AllocationResponse {
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The part of the budget that could not be allocated.
+    pub budget_left: Decimal,
+    /// The recommended holding of each asset, by symbol.
+    pub assets: BTreeMap<String, AssetAllocationResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The cash raised by the sells, when the budget is a withdrawal.
+    pub withdrawal: Option<WithdrawalResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The cash raised to fund a withdrawal.
+pub struct WithdrawalResponse {
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The amount requested.
+    pub requested: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The cash raised, net of sell fees and capital-gains tax.
+    pub raised: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The part of the requested amount the sells could not raise.
+    pub shortfall: Decimal,
+}
+
+impl From<Withdrawal> for WithdrawalResponse {
+    fn from(withdrawal: Withdrawal) -> Self {
+        Self {
+            requested: withdrawal.requested,
+            raised: withdrawal.raised,
+            shortfall: withdrawal.shortfall,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The recommended holding of an asset once the budget is invested.
+pub struct AssetAllocationResponse {
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The amount held, in the portfolio quote currency.
+    pub amount: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The quantity held.
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The fees charged on the quantity sold to rebalance the asset.
+    pub sell_fees: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The gain, or loss if negative, realized by the quantity sold.
+    pub realized_gain: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The capital-gains tax due on the realized gain.
+    pub tax: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The order the optimizer wanted but rejected, e.g. because of fees.
+    pub theo_alloc: Option<TheoreticalAllocationResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Why the asset could not be traded up to its target, if a rule blocked it.
+    pub blocked_by: Option<AllocationBlockReason>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// A rule that prevented an asset from being bought or sold.
+pub enum AllocationBlockReason {
+    /// The budget left cannot buy a single lot.
+    PriceTooHigh,
+    /// The fees would exceed the maximum fee impact.
+    FeesTooHigh,
+    /// The order would be smaller than the asset minimum quantity.
+    MinQuantityNotMet,
+    /// The order would be worth less than the asset minimum order amount.
+    MinOrderAmountNotMet,
+    /// The capital-gains tax of the sale would exceed the tax budget left.
+    TaxTooHigh,
+}
+
+impl AllocationBlockReason {
+    fn from_state(state: &SolutionState) -> Option<Self> {
+        match state {
+            SolutionState::PriceTooHigh => Some(Self::PriceTooHigh),
+            SolutionState::FeesTooHigh => Some(Self::FeesTooHigh),
+            SolutionState::MinQuantityNotMet => Some(Self::MinQuantityNotMet),
+            SolutionState::MinOrderAmountNotMet => Some(Self::MinOrderAmountNotMet),
+            SolutionState::TaxTooHigh => Some(Self::TaxTooHigh),
+            SolutionState::Open
+            | SolutionState::DoNotAllocate
+            | SolutionState::FullyAllocated
+            | SolutionState::TieBreaker => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// A purchase that was computed but not recommended.
+pub struct TheoreticalAllocationResponse {
+    #[serde(with = "rust_decimal::serde::str")]
+    pub shares: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub fees: Decimal,
+}
+
+impl From<TheoreticalAllocation> for TheoreticalAllocationResponse {
+    fn from(alloc: TheoreticalAllocation) -> Self {
+        Self {
+            shares: alloc.shares,
+            amount: alloc.amount,
+            fees: alloc.fees,
+        }
+    }
+}
+
+impl From<Solution> for AllocationResponse {
+    fn from(solution: Solution) -> Self {
+        Self {
+            budget_left: solution.budget_left,
+            assets: solution
+                .assets
+                .into_iter()
+                .map(|(symbol, asset)| {
+                    let alloc = AssetAllocationResponse {
+                        amount: asset.amount,
+                        shares: asset.shares,
+                        sell_fees: asset.sell_fees,
+                        realized_gain: asset.realized_gain,
+                        tax: asset.tax,
+                        theo_alloc: asset.theo_alloc.map(Into::into),
+                        blocked_by: AllocationBlockReason::from_state(&asset.state),
+                    };
+                    (symbol, alloc)
+                })
+                .collect(),
+            withdrawal: solution.withdrawal.map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// How a DCA strategy would have performed over the requested range.
+pub struct BacktestResponse {
+    /// The portfolio state at each date on which every asset has a price.
+    pub points: Vec<BacktestPointResponse>,
+    /// The number of contributions replayed.
+    pub contributions: u32,
+    /// The quantity held of each asset at the end of the backtest, by symbol.
+    pub holdings: BTreeMap<String, BacktestHoldingResponse>,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The cash left unallocated at the end, negative if owed for fees.
+    pub cash: Decimal,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The portfolio state on a date of a backtest.
+pub struct BacktestPointResponse {
+    /// The date of the prices.
+    pub observed_at: DateTime,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The value of the holdings plus the cash left, in the portfolio quote currency.
+    pub value: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The contributions made so far.
+    pub invested: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The fees paid so far.
+    pub fees: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The largest distance of an asset weight from its target.
+    pub max_drift: Decimal,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// An asset holding at the end of a backtest.
+pub struct BacktestHoldingResponse {
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The quantity held.
+    pub shares: Decimal,
+}
+
+impl From<backtest::Solution> for BacktestResponse {
+    fn from(solution: backtest::Solution) -> Self {
+        Self {
+            points: solution
+                .points
+                .into_iter()
+                .map(|p| BacktestPointResponse {
+                    observed_at: DateTime::from_timestamp(p.timestamp, 0).unwrap(),
+                    value: p.value,
+                    invested: p.invested,
+                    fees: p.fees,
+                    max_drift: p.max_drift,
+                })
+                .collect(),
+            contributions: solution.contributions,
+            holdings: solution
+                .holdings
+                .into_iter()
+                .map(|(symbol, shares)| (symbol, BacktestHoldingResponse { shares }))
+                .collect(),
+            cash: solution.cash,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The simulated outcomes of a contribution plan.
+pub struct ProjectionResponse {
+    /// The portfolio value percentiles at the start and at the end of every year.
+    pub bands: Vec<ProjectionBandResponse>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "rust_decimal::serde::str_option"
+    )]
+    /// The share of the simulated paths reaching the goal, when one is set.
+    pub goal_probability: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The percentiles of the portfolio value simulated at a year end.
+pub struct ProjectionBandResponse {
+    /// The years elapsed since the projection start.
+    pub year: u32,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The initial value plus the contributions made so far.
+    pub invested: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The 5th percentile of the value.
+    pub p5: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The 25th percentile of the value.
+    pub p25: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The median value.
+    pub p50: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The 75th percentile of the value.
+    pub p75: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The 95th percentile of the value.
+    pub p95: Decimal,
+}
+
+impl From<projection::Solution> for ProjectionResponse {
+    fn from(solution: projection::Solution) -> Self {
+        Self {
+            bands: solution
+                .bands
+                .into_iter()
+                .map(|b| ProjectionBandResponse {
+                    year: b.year,
+                    invested: b.invested,
+                    p5: b.p5,
+                    p25: b.p25,
+                    p50: b.p50,
+                    p75: b.p75,
+                    p95: b.p95,
+                })
+                .collect(),
+            goal_probability: solution.goal_probability,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The latest ingested observation of a reference rate.
+pub struct ReferenceRateResponse {
+    /// The reference rate.
+    pub rate_type: RateType,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The rate in percent, e.g. `3.63` for 3.63%.
+    pub percent: Decimal,
+    /// The business day of the transactions the rate is computed from.
+    pub observed_on: chrono::NaiveDate,
+    /// When ingestion first found the observation published.
+    pub published_at: DateTime,
+    /// When the observation was last retrieved from its publisher.
+    pub fetched_at: DateTime,
+}
+
+impl From<ReferenceRate> for ReferenceRateResponse {
+    fn from(rate: ReferenceRate) -> Self {
+        Self {
+            rate_type: rate.observation.rate_type,
+            percent: rate.observation.percent,
+            observed_on: rate.observation.observed_on,
+            published_at: rate.published_at,
+            fetched_at: rate.fetched_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The equity/defensive split suggested by `the-bull-v1`.
+///
+/// Percentages use the source formula; the raw equity share is clamped to
+/// `[0, 100]` by a DcaPal guardrail, reported by `isClamped`.
+pub struct TheBullResponse {
+    /// The framework version the split was computed with.
+    pub framework_version: String,
+    /// The investor age, in completed years.
+    pub age_years: u32,
+    /// The rate observation the split was computed with.
+    pub rate: ReferenceRateResponse,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The equity share given by the source formula, in percent.
+    pub raw_equity_pct: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The equity share, in percent.
+    pub equity_pct: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The defensive share, in percent.
+    pub defensive_pct: Decimal,
+    /// Whether the guardrail changed the source formula result.
+    pub is_clamped: bool,
+    /// The split as group target weights, ready to seed a portfolio.
+    pub target_groups: Vec<TargetGroupResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// A group of assets and its suggested target weight.
+pub struct TargetGroupResponse {
+    /// The group name: `equity` or `defensive`.
+    pub name: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    /// The group target weight, between 0 and 1.
+    pub target_weight: Decimal,
+}
+
+impl From<TheBullGuidance> for TheBullResponse {
+    fn from(guidance: TheBullGuidance) -> Self {
+        let split = guidance.split;
+        let target_groups = [
+            ("equity", split.equity_pct),
+            ("defensive", split.defensive_pct),
+        ]
+        .into_iter()
+        .map(|(name, pct)| TargetGroupResponse {
+            name: name.to_string(),
+            target_weight: pct / Decimal::ONE_HUNDRED,
+        })
+        .collect();
+
+        Self {
+            framework_version: THE_BULL_V1.to_string(),
+            age_years: guidance.age_years,
+            rate: guidance.rate.into(),
+            raw_equity_pct: split.raw_equity_pct,
+            equity_pct: split.equity_pct,
+            defensive_pct: split.defensive_pct,
+            is_clamped: split.is_clamped(),
+            target_groups,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -209,6 +723,7 @@ mod test {
     use rust_decimal::dec;
 
     use super::*;
+    use crate::app::domain::{entity::RateObservation, framework::TheBullSplit};
 
     #[test]
     fn map_model_to_response() {
@@ -227,6 +742,7 @@ mod test {
             fee_rate: None,
             min_fee: None,
             max_fee: None,
+            fee_structure: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -248,6 +764,7 @@ mod test {
             fee_rate: None,
             min_fee: None,
             max_fee: None,
+            fee_structure: None,
             average_buy_price: Some(dec!(90.0)),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -287,4 +804,59 @@ mod test {
         assert_eq!(serialized["assets"][0]["averageBuyPrice"], "90.0");
         assert_eq!(serialized["fees"]["feeStructure"]["feeAmount"], "2.95");
     }
+
+    #[test]
+    fn splits_batch_prices_from_failures() {
+        let now = Utc::now();
+        let rates = vec![
+            ("btc".to_string(), Ok(Price::new(50_000., now, now))),
+            ("foo".to_string(), Err(PriceUnavailableReason::UnknownAsset)),
+            (
+                "eth".to_string(),
+                Err(PriceUnavailableReason::PriceNotAvailable),
+            ),
+        ];
+
+        let response = PricesResponse::new("eur".to_string(), rates);
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(serialized["quote"], "eur");
+        assert_eq!(serialized["prices"]["btc"]["price"], 50_000.);
+        assert_eq!(serialized["unavailable"]["foo"]["reason"], "unknown_asset");
+        assert_eq!(
+            serialized["unavailable"]["eth"]["reason"],
+            "price_not_available"
+        );
+        assert_eq!(
+            serialized["unavailable"]["eth"]["message"],
+            "Price not available for eth/eur"
+        );
+    }
+
+    #[test]
+    fn maps_the_bull_guidance_to_group_targets() {
+        let now = Utc::now();
+        let guidance = TheBullGuidance {
+            age_years: 40,
+            rate: ReferenceRate {
+                observation: RateObservation {
+                    rate_type: RateType::FedEffr,
+                    observed_on: chrono::NaiveDate::from_ymd_opt(2026, 7, 31).unwrap(),
+                    percent: dec!(3.5),
+                },
+                published_at: now,
+                fetched_at: now,
+            },
+            split: TheBullSplit::new(40, dec!(3.5)),
+        };
+
+        let serialized = serde_json::to_value(TheBullResponse::from(guidance)).unwrap();
+        assert_eq!(serialized["frameworkVersion"], "the-bull-v1");
+        assert_eq!(serialized["rate"]["rateType"], "fed_effr");
+        assert_eq!(serialized["rate"]["observedOn"], "2026-07-31");
+        assert_eq!(serialized["equityPct"], "67.5");
+        assert_eq!(serialized["isClamped"], false);
+        assert_eq!(serialized["targetGroups"][0]["name"], "equity");
+        assert_eq!(serialized["targetGroups"][0]["targetWeight"], "0.675");
+        assert_eq!(serialized["targetGroups"][1]["targetWeight"], "0.325");
+    }
 }
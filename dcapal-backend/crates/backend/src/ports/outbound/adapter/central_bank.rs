@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use tracing::{debug, warn};
+
+use super::{ReferenceRateProvider, ecb::read_csv};
+use crate::{
+    app::domain::entity::{RateObservation, RateType},
+    error::{DcaError, Result},
+};
+
+const NY_FED_EFFR_URL: &str = "https://markets.newyorkfed.org/api/rates/unsecured/effr/last/1.json";
+const ECB_ESTR_URL: &str = "https://data-api.ecb.europa.eu/service/data/EST/B.EU000A2X2A25.WT?format=csvdata&lastNObservations=1";
+
+/// Reads the reference rates published by the New York Fed and the ECB.
+#[derive(Clone)]
+pub struct CentralBankProvider {
+    http: reqwest::Client,
+}
+
+impl CentralBankProvider {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+
+    async fn fetch_effr(&self) -> Result<Option<RateObservation>> {
+        let res = self.http.get(NY_FED_EFFR_URL).send().await?;
+        if !res.status().is_success() {
+            warn!(
+                url = NY_FED_EFFR_URL,
+                "Unsuccessful request. Status: {}",
+                res.status()
+            );
+            return Err(DcaError::Generic(format!(
+                "New York Fed API returned {}",
+                res.status()
+            )));
+        }
+
+        let res = res.json::<res::RefRatesResponse>().await?;
+        Ok(parse_effr(res))
+    }
+
+    async fn fetch_estr(&self) -> Result<Option<RateObservation>> {
+        let res = self.http.get(ECB_ESTR_URL).send().await?;
+        if !res.status().is_success() {
+            warn!(
+                url = ECB_ESTR_URL,
+                "Unsuccessful request. Status: {}",
+                res.status()
+            );
+            return Err(DcaError::Generic(format!(
+                "ECB Data API returned {}",
+                res.status()
+            )));
+        }
+
+        let csv = res.text().await?;
+        parse_estr(&csv)
+    }
+}
+
+#[async_trait]
+impl ReferenceRateProvider for CentralBankProvider {
+    async fn fetch_latest(&self, rate_type: RateType) -> Result<Option<RateObservation>> {
+        let observation = match rate_type {
+            RateType::FedEffr => self.fetch_effr().await?,
+            RateType::EcbEstr => self.fetch_estr().await?,
+        };
+
+        debug!("Fetched latest '{rate_type}' observation: {observation:?}");
+        Ok(observation)
+    }
+}
+
+/// Picks the latest EFFR record. The live API reports the rate as
+/// `percentRate`, while its published schema calls it `percent`.
+fn parse_effr(res: res::RefRatesResponse) -> Option<RateObservation> {
+    res.ref_rates
+        .into_iter()
+        .filter(|r| r.rate_type == "EFFR")
+        .max_by_key(|r| r.effective_date)
+        .map(|r| RateObservation {
+            rate_type: RateType::FedEffr,
+            observed_on: r.effective_date,
+            percent: r.percent_rate,
+        })
+}
+
+/// Reads the latest €STR observation from an ECB Data API CSV payload.
+fn parse_estr(csv: &str) -> Result<Option<RateObservation>> {
+    let mut latest: Option<RateObservation> = None;
+    for record in read_csv(csv, &["TIME_PERIOD", "OBS_VALUE"])? {
+        let [date, value] = &record[..] else {
+            unreachable!("records hold the requested columns");
+        };
+
+        let observed_on = NaiveDate::from_str(date)
+            .map_err(|e| DcaError::Generic(format!("Invalid ECB TIME_PERIOD '{date}': {e}")))?;
+        let percent = Decimal::from_str(value)
+            .map_err(|e| DcaError::Generic(format!("Invalid ECB OBS_VALUE '{value}': {e}")))?;
+
+        if latest.is_none_or(|l| l.observed_on < observed_on) {
+            latest = Some(RateObservation {
+                rate_type: RateType::EcbEstr,
+                observed_on,
+                percent,
+            });
+        }
+    }
+
+    Ok(latest)
+}
+
+mod res {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RefRatesResponse {
+        pub ref_rates: Vec<RefRate>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RefRate {
+        pub effective_date: NaiveDate,
+        #[serde(rename = "type")]
+        pub rate_type: String,
+        pub percent_rate: Decimal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::dec;
+
+    use super::*;
+
+    #[test]
+    fn reads_latest_effr_record() {
+        let res: res::RefRatesResponse = serde_json::from_value(serde_json::json!({
+            "refRates": [
+                {
+                    "effectiveDate": "2026-07-31",
+                    "type": "EFFR",
+                    "percentRate": 3.63,
+                    "percentPercentile1": 3.6,
+                    "targetRateFrom": 3.5,
+                    "targetRateTo": 3.75,
+                    "volumeInBillions": 96,
+                    "revisionIndicator": ""
+                }
+            ]
+        }))
+        .unwrap();
+
+        let observation = parse_effr(res).unwrap();
+        assert_eq!(observation.rate_type, RateType::FedEffr);
+        assert_eq!(
+            observation.observed_on,
+            NaiveDate::from_ymd_opt(2026, 7, 31).unwrap()
+        );
+        assert_eq!(observation.percent, dec!(3.63));
+    }
+
+    #[test]
+    fn reads_latest_estr_record() {
+        let csv = "KEY,FREQ,BENCHMARK_ITEM,DATA_TYPE_EST,TIME_PERIOD,OBS_VALUE,OBS_STATUS,CONF_STATUS,TITLE\r\n\
+                   EST.B.EU000A2X2A25.WT,B,EU000A2X2A25,WT,2026-07-31,1.922,A,F,\"Euro short-term rate - Volume-weighted trimmed mean rate, daily\"\r\n";
+
+        let observation = parse_estr(csv).unwrap().unwrap();
+        assert_eq!(observation.rate_type, RateType::EcbEstr);
+        assert_eq!(
+            observation.observed_on,
+            NaiveDate::from_ymd_opt(2026, 7, 31).unwrap()
+        );
+        assert_eq!(observation.percent, dec!(1.922));
+    }
+
+    #[test]
+    fn rejects_estr_payload_without_value_column() {
+        let csv = "KEY,TIME_PERIOD\nEST.B.EU000A2X2A25.WT,2026-07-31\n";
+
+        assert!(parse_estr(csv).is_err());
+        assert!(parse_estr("").unwrap().is_none());
+    }
+}
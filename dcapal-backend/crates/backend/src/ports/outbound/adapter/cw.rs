@@ -3,15 +3,18 @@ use std::{
     fmt::Debug,
 };
 
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
 use futures::{StreamExt, future};
 use reqwest::StatusCode;
 use serde::{Deserialize, de::DeserializeOwned};
 use tracing::{debug, error, info};
 
+use super::MarketDataProvider;
 use crate::{
     DateTime,
-    app::domain::entity::{Asset, Crypto, Fiat, Market, MarketId, OHLCFrequency},
-    config,
+    app::domain::entity::{Asset, Crypto, Fiat, Market, MarketId, OHLCFrequency, Price},
+    config::{self, PriceProvider},
     error::{DcaError, Result},
     ports::outbound::repository::market_data::MarketDataRepository,
 };
@@ -100,7 +103,7 @@ impl CryptoWatchProvider {
         Ok((symbol.to_string(), res.result))
     }
 
-    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<Price>> {
         let (px_mins5, px_day) = tokio::join!(
             self.fetch_price(&mkt.id, OHLCFrequency::Minutes5, ts),
             self.fetch_price(&mkt.id, OHLCFrequency::Daily, ts)
@@ -120,7 +123,7 @@ impl CryptoWatchProvider {
         id: &MarketId,
         freq: OHLCFrequency,
         ts: DateTime,
-    ) -> Result<Option<f64>> {
+    ) -> Result<Option<Price>> {
         let periods = get_cw_api_periods(freq);
         let (r_lo, r_hi) = freq.ohlc_range(ts);
         let (after_ts, before_ts) = (r_lo.timestamp(), r_hi.timestamp());
@@ -151,7 +154,14 @@ impl CryptoWatchProvider {
             )));
         }
 
-        Ok(Some(cstick[4]))
+        // CW candlesticks start with their close time
+        let Some(close_ts) = Utc.timestamp_opt(cstick[0] as i64, 0).single() else {
+            return Err(DcaError::Generic(format!(
+                "Malformed response. Unexpected candlestick close time: {cstick:?}"
+            )));
+        };
+
+        Ok(Some(Price::new(cstick[4], close_ts.min(ts), ts)))
     }
 
     async fn fetch_cw_api<T: DeserializeOwned + Debug>(&self, url: &str) -> Result<T> {
@@ -181,6 +191,21 @@ impl CryptoWatchProvider {
     }
 }
 
+#[async_trait]
+impl MarketDataProvider for CryptoWatchProvider {
+    fn id(&self) -> PriceProvider {
+        PriceProvider::CryptoWatch
+    }
+
+    async fn fetch_latest(&self, market: &Market, ts: DateTime) -> Result<Option<Price>> {
+        self.fetch_market_price(market, ts).await
+    }
+
+    async fn discover(&self, repo: &MarketDataRepository) -> Result<(Vec<Asset>, Vec<Market>)> {
+        self.fetch_assets(repo).await
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct CWMarket {
     #[serde(rename = "pair")]
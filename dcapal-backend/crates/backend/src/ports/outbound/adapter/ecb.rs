@@ -0,0 +1,339 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use tracing::{debug, error, warn};
+
+use super::MarketDataProvider;
+use crate::{
+    DateTime,
+    app::{
+        domain::entity::{Asset, AssetId, Fiat, Market, Price},
+        infra::utils::ExpiringOnceCell,
+    },
+    config::PriceProvider,
+    error::{DcaError, Result},
+    ports::outbound::repository::market_data::MarketDataRepository,
+};
+
+const EXR_URL: &str = "https://data-api.ecb.europa.eu/service/data/EXR/D..EUR.SP00.A?format=csvdata&lastNObservations=1";
+
+/// The ECB publishes its reference rates once per TARGET2 business day, so
+/// one download serves every market for a while.
+const REFRESH_INTERVAL_MINS: i64 = 60;
+
+const EUR: &str = "eur";
+
+/// Reads the ECB euro foreign exchange reference rates.
+///
+/// Every fiat market is priced from a single daily set of rates against the
+/// euro, crossed through it when neither side is the euro.
+pub struct EcbProvider {
+    http: reqwest::Client,
+    rates: ExpiringOnceCell<Arc<ReferenceRates>>,
+}
+
+impl EcbProvider {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            rates: ExpiringOnceCell::new(|r: &Arc<ReferenceRates>| {
+                Utc::now() - r.fetched_at > Duration::minutes(REFRESH_INTERVAL_MINS)
+            }),
+        }
+    }
+
+    async fn reference_rates(&self) -> Result<Arc<ReferenceRates>> {
+        self.rates
+            .get_or_try_init(|| self.fetch_reference_rates())
+            .await
+    }
+
+    async fn fetch_reference_rates(&self) -> Result<Arc<ReferenceRates>> {
+        debug!(url = EXR_URL, "Fetching ECB reference rates");
+
+        let res = self.http.get(EXR_URL).send().await?;
+        if !res.status().is_success() {
+            warn!(
+                url = EXR_URL,
+                "Unsuccessful request. Status: {}",
+                res.status()
+            );
+            return Err(DcaError::Generic(format!(
+                "ECB Data API returned {}",
+                res.status()
+            )));
+        }
+
+        let csv = res.text().await?;
+        let rates = ReferenceRates::parse(&csv, Utc::now())?;
+        debug!(
+            "Fetched {} ECB reference rates of {}",
+            rates.rates.len(),
+            rates.date
+        );
+
+        Ok(Arc::new(rates))
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for EcbProvider {
+    fn id(&self) -> PriceProvider {
+        PriceProvider::Ecb
+    }
+
+    async fn fetch_latest(&self, market: &Market, _ts: DateTime) -> Result<Option<Price>> {
+        if !market.is_fiat() {
+            return Ok(None);
+        }
+
+        let rates = self.reference_rates().await?;
+        Ok(rates.price(market.base.id(), market.quote.id()))
+    }
+
+    /// Lists the currencies of the latest reference set as fiat assets, and
+    /// one market from the euro to each of them, already priced.
+    async fn discover(&self, repo: &MarketDataRepository) -> Result<(Vec<Asset>, Vec<Market>)> {
+        let rates = self.reference_rates().await?;
+
+        let mut assets = Vec::new();
+        let mut known = HashMap::new();
+        let ids = std::iter::once(EUR.to_string()).chain(rates.rates.keys().cloned());
+        for id in ids {
+            let asset = match repo.find_asset(&id).await {
+                Ok(Some(a)) => a,
+                Ok(None) => {
+                    let asset = Asset::Fiat(Fiat::new(id.clone(), rates.name(&id)));
+                    assets.push(asset.clone());
+                    asset
+                }
+                Err(e) => {
+                    error!("Failed to find asset '{id}': {e}");
+                    continue;
+                }
+            };
+            known.insert(id, asset);
+        }
+
+        let Some(eur) = known.get(EUR) else {
+            return Err(DcaError::Generic("Cannot resolve the euro asset".into()));
+        };
+
+        let mut markets = Vec::new();
+        for (id, quote) in &known {
+            if id == EUR {
+                continue;
+            }
+
+            let market_id = format!("{EUR}{id}");
+            match repo.find_market(&market_id).await {
+                Ok(Some(_)) => continue,
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Error occurred in searching market '{market_id}': {e}");
+                    continue;
+                }
+            }
+
+            let price = rates.price(EUR, id);
+            markets.push(Market::new(market_id, eur.clone(), quote.clone(), price));
+        }
+
+        Ok((assets, markets))
+    }
+}
+
+/// Units of a currency worth one euro on a reference day.
+#[derive(Debug, Clone)]
+struct ExchangeRate {
+    name: String,
+    per_euro: Decimal,
+}
+
+/// The latest ECB reference set, by asset id.
+#[derive(Debug, Clone)]
+struct ReferenceRates {
+    date: NaiveDate,
+    rates: HashMap<AssetId, ExchangeRate>,
+    fetched_at: DateTime,
+}
+
+impl ReferenceRates {
+    /// Reads an EXR payload holding the last observation of every currency.
+    ///
+    /// Currencies the ECB stopped quoting still report their last, older,
+    /// observation: only the ones dated on the latest reference day are kept.
+    fn parse(csv: &str, fetched_at: DateTime) -> Result<Self> {
+        let records = read_csv(csv, &["CURRENCY", "TIME_PERIOD", "OBS_VALUE", "TITLE"])?;
+
+        let mut observations = Vec::with_capacity(records.len());
+        for record in records {
+            let [currency, date, value, title] = &record[..] else {
+                unreachable!("records hold the requested columns");
+            };
+
+            let date = NaiveDate::from_str(date)
+                .map_err(|e| DcaError::Generic(format!("Invalid ECB TIME_PERIOD '{date}': {e}")))?;
+            let per_euro = Decimal::from_str(value)
+                .map_err(|e| DcaError::Generic(format!("Invalid ECB OBS_VALUE '{value}': {e}")))?;
+            if per_euro <= Decimal::ZERO {
+                warn!("Skipping non-positive ECB reference rate for {currency}: {per_euro}");
+                continue;
+            }
+
+            // Titles read like "Swedish krona/Euro"
+            let name = title.split('/').next().unwrap_or_default().trim();
+            let rate = ExchangeRate {
+                name: name.to_string(),
+                per_euro,
+            };
+            observations.push((currency.to_lowercase(), date, rate));
+        }
+
+        let Some(date) = observations.iter().map(|(_, d, _)| *d).max() else {
+            return Err(DcaError::Generic("ECB returned no reference rate".into()));
+        };
+
+        let rates = observations
+            .into_iter()
+            .filter(|(_, d, _)| *d == date)
+            .map(|(id, _, rate)| (id, rate))
+            .collect();
+
+        Ok(Self {
+            date,
+            rates,
+            fetched_at,
+        })
+    }
+
+    /// Display name of a currency of the set.
+    fn name(&self, id: &str) -> String {
+        if id == EUR {
+            return "Euro".to_string();
+        }
+
+        self.rates
+            .get(id)
+            .map(|r| r.name.clone())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| id.to_uppercase())
+    }
+
+    /// Units of `quote` worth one unit of `base`.
+    ///
+    /// Reference rates are dated, not timed: their prices are observed at the
+    /// start of the reference day, UTC.
+    fn price(&self, base: &str, quote: &str) -> Option<Price> {
+        let per_euro = |id: &str| match id {
+            EUR => Some(Decimal::ONE),
+            id => self.rates.get(id).map(|r| r.per_euro),
+        };
+
+        let price = (per_euro(quote)? / per_euro(base)?).to_f64()?;
+        let observed_at = self.date.and_hms_opt(0, 0, 0)?.and_utc();
+
+        Some(Price::new(price, observed_at, self.fetched_at).with_provider(PriceProvider::Ecb))
+    }
+}
+
+/// Reads the `columns` of every record of an ECB Data API CSV payload,
+/// locating them by header.
+pub(super) fn read_csv(csv: &str, columns: &[&str]) -> Result<Vec<Vec<String>>> {
+    let mut lines = csv.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
+
+    let header = split_csv_line(header);
+    let indexes = columns
+        .iter()
+        .map(|name| {
+            header
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| DcaError::Generic(format!("ECB CSV payload misses column {name}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    lines
+        .map(|line| {
+            let mut fields = split_csv_line(line);
+            indexes
+                .iter()
+                .map(|i| {
+                    fields.get_mut(*i).map(std::mem::take).ok_or_else(|| {
+                        DcaError::Generic(format!("Malformed ECB CSV record: {line}"))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Splits a CSV record on commas, honoring double-quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::dec;
+
+    use super::*;
+
+    const EXR_CSV: &str = "KEY,FREQ,CURRENCY,CURRENCY_DENOM,EXR_TYPE,EXR_SUFFIX,TIME_PERIOD,OBS_VALUE,OBS_STATUS,TITLE\r\n\
+        EXR.D.SEK.EUR.SP00.A,D,SEK,EUR,SP00,A,2026-10-16,11.0425,A,Swedish krona/Euro\r\n\
+        EXR.D.USD.EUR.SP00.A,D,USD,EUR,SP00,A,2026-10-16,1.1,A,US dollar/Euro\r\n\
+        EXR.D.RUB.EUR.SP00.A,D,RUB,EUR,SP00,A,2022-03-01,117.2010,A,\"Russian rouble/Euro, discontinued\"\r\n";
+
+    #[test]
+    fn keeps_the_latest_reference_set() {
+        let rates = ReferenceRates::parse(EXR_CSV, Utc::now()).unwrap();
+
+        assert_eq!(rates.date, NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+        assert_eq!(rates.rates.len(), 2);
+        assert_eq!(rates.rates["sek"].per_euro, dec!(11.0425));
+        assert_eq!(rates.name("sek"), "Swedish krona");
+        assert_eq!(rates.name(EUR), "Euro");
+        assert!(!rates.rates.contains_key("rub"));
+    }
+
+    #[test]
+    fn crosses_rates_through_the_euro() {
+        let rates = ReferenceRates::parse(EXR_CSV, Utc::now()).unwrap();
+
+        let price = rates.price(EUR, "sek").unwrap();
+        assert_eq!(price.price, 11.0425);
+        assert_eq!(price.provider, Some(PriceProvider::Ecb));
+        assert_eq!(
+            price.observed_at.date_naive(),
+            NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()
+        );
+
+        let price = rates.price("usd", "sek").unwrap();
+        assert!((price.price - 11.0425 / 1.1).abs() < 1e-9);
+        assert!((rates.price("sek", EUR).unwrap().price - 1. / 11.0425).abs() < 1e-9);
+
+        assert!(rates.price("rub", EUR).is_none());
+    }
+}
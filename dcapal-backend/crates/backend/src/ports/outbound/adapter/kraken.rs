@@ -3,19 +3,25 @@ use std::{
     fmt::Debug,
 };
 
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
 use failsafe::futures::CircuitBreaker;
 use futures::StreamExt;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use reqwest::StatusCode;
+use rust_decimal::Decimal;
 use serde::{Deserialize, de::DeserializeOwned};
 use tracing::{debug, error, warn};
 
-use super::DefaultCircuitBreaker;
+use super::{DefaultCircuitBreaker, MarketDataProvider};
 use crate::{
     DateTime,
-    app::domain::entity::{Asset, AssetId, Crypto, Fiat, Market, MarketId, OHLCFrequency},
-    config,
+    app::domain::entity::{
+        Asset, AssetId, Crypto, Fiat, Market, MarketId, OHLCFrequency, Price, SeriesObservation,
+        SeriesTimeframe, TradingRules,
+    },
+    config::{self, PriceProvider},
     error::{DcaError, Result},
     ports::outbound::repository::market_data::MarketDataRepository,
 };
@@ -60,14 +66,24 @@ impl KrakenProvider {
 
         // Filter online market symbols and rename Kraken specific pairs to standard
         // names
-        let market_symbols = res
+        let online_pairs = res
             .result
             .values()
             .filter(|&p| p.status == "online")
-            .map(|p| normalize_symbol(&p.wsname))
+            .map(|p| (normalize_symbol(&p.wsname), p.trading_rules()))
+            .collect::<Vec<_>>();
+
+        let market_symbols = online_pairs
+            .iter()
+            .map(|(symbol, _)| symbol.clone())
             .collect::<Vec<String>>();
 
-        let (markets, assets) = if self.cmc_api_key.is_some() {
+        let rules = online_pairs
+            .into_iter()
+            .map(|(symbol, rules)| (symbol.replace('/', ""), rules))
+            .collect::<HashMap<MarketId, TradingRules>>();
+
+        let (mut markets, assets) = if self.cmc_api_key.is_some() {
             // If CoinMarketCap API key is available, enrich assets data with human-friendly
             // info
             self.resolve_assets_data(&market_symbols, repo).await
@@ -77,13 +93,20 @@ impl KrakenProvider {
             resolve_assets_data_kraken_only(&market_symbols, repo).await
         };
 
+        for m in &mut markets {
+            m.set_trading_rules(rules.get(&m.id).cloned());
+        }
+
+        // Known markets are returned again only when their trading rules changed
+        markets.extend(get_markets_with_new_rules(&rules, repo).await);
+
         debug!("New assets: {}", serde_json::to_string(&assets).unwrap());
         debug!("New markets: {}", serde_json::to_string(&markets).unwrap());
 
         Ok((assets, markets))
     }
 
-    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<Price>> {
         if let Some(px) = self
             .fetch_price(&mkt.id, OHLCFrequency::Minutes5, ts)
             .await?
@@ -103,7 +126,7 @@ impl KrakenProvider {
         id: &MarketId,
         freq: OHLCFrequency,
         ts: DateTime,
-    ) -> Result<Option<f64>> {
+    ) -> Result<Option<Price>> {
         let (r_lo, r_hi) = freq.ohlc_range(ts);
         let (after_ts, before_ts) = (r_lo.timestamp(), r_hi.timestamp());
         let periods = get_kraken_api_periods(freq);
@@ -165,7 +188,86 @@ impl KrakenProvider {
             )));
         };
 
-        Ok(Some(close_px))
+        // The close is observed when the candle ends, or now for the running one
+        let Some(open_ts) = cstick[0]
+            .as_i64()
+            .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        else {
+            return Err(DcaError::Generic(format!(
+                "Cannot parse '{:?}' into a timestamp",
+                cstick[0]
+            )));
+        };
+        let observed_at = (open_ts + freq.period()).min(ts);
+
+        Ok(Some(Price::new(close_px, observed_at, ts)))
+    }
+
+    /// Fetches the closed `timeframe` candles opened after `since`.
+    ///
+    /// Kraken returns at most the 720 most recent candles regardless of
+    /// `since`, and its last candle is the running one.
+    pub async fn fetch_series(
+        &self,
+        instrument: &str,
+        timeframe: SeriesTimeframe,
+        since: DateTime,
+    ) -> Result<Vec<SeriesObservation>> {
+        let interval = get_kraken_series_interval(timeframe);
+        let since_ts = since.timestamp();
+        let url = format!(
+            "https://api.kraken.com/0/public/OHLC?pair={instrument}&since={since_ts}&interval={interval}"
+        );
+
+        debug!(
+            url = url,
+            "Fetching {timeframe} series for instrument '{instrument}' since {since}"
+        );
+
+        let Some(mut res) = self.fetch_kraken_api::<OHLCResult>(&url).await? else {
+            return Ok(vec![]);
+        };
+
+        if !res.error.is_empty() {
+            error!(
+                "Error occurred while fetching '{instrument}': {:?}",
+                res.error
+            );
+            return Err(DcaError::Generic(format!("{:?}", res.error)));
+        }
+
+        let Some(ref mut result) = res.result else {
+            return Err(DcaError::Generic(
+                "Unexpected empty 'result' field".to_string(),
+            ));
+        };
+
+        result.remove("last");
+        let Some(Payload::CandleSticks(csticks)) = result.values().next() else {
+            return Err(DcaError::Generic(format!(
+                "Malformed response. Cannot find candlesticks: {res:?}"
+            )));
+        };
+
+        csticks
+            .0
+            .iter()
+            .map(|c| {
+                let observed_at = c[0]
+                    .as_i64()
+                    .and_then(|ts| Utc.timestamp_opt(ts, 0).single());
+                let value = c[4].as_str().and_then(|px| px.parse::<Decimal>().ok());
+
+                match (observed_at, value) {
+                    (Some(observed_at), Some(value)) => {
+                        Ok(SeriesObservation { observed_at, value })
+                    }
+                    _ => Err(DcaError::Generic(format!(
+                        "Malformed candlestick for '{instrument}': {c:?}"
+                    ))),
+                }
+            })
+            .collect()
     }
 
     async fn resolve_assets_data(
@@ -376,10 +478,50 @@ impl KrakenProvider {
     }
 }
 
+#[async_trait]
+impl MarketDataProvider for KrakenProvider {
+    fn id(&self) -> PriceProvider {
+        PriceProvider::Kraken
+    }
+
+    async fn fetch_latest(&self, market: &Market, ts: DateTime) -> Result<Option<Price>> {
+        self.fetch_market_price(market, ts).await
+    }
+
+    async fn fetch_ohlc(
+        &self,
+        instrument: &str,
+        timeframe: SeriesTimeframe,
+        from: DateTime,
+        _to: DateTime,
+    ) -> Result<Vec<SeriesObservation>> {
+        self.fetch_series(instrument, timeframe, from).await
+    }
+
+    async fn discover(&self, repo: &MarketDataRepository) -> Result<(Vec<Asset>, Vec<Market>)> {
+        self.fetch_assets(repo).await
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct Pair {
     wsname: String,
     status: String,
+    lot_decimals: Option<u32>,
+    ordermin: Option<Decimal>,
+    costmin: Option<Decimal>,
+    pair_decimals: Option<u32>,
+}
+
+impl Pair {
+    fn trading_rules(&self) -> TradingRules {
+        TradingRules {
+            lot_decimals: self.lot_decimals,
+            min_quantity: self.ordermin,
+            min_cost: self.costmin,
+            price_decimals: self.pair_decimals,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -455,6 +597,33 @@ async fn get_new_markets(
         .await
 }
 
+/// Returns the known markets whose stored trading rules differ from the
+/// published ones, updated with the latter.
+async fn get_markets_with_new_rules(
+    rules: &HashMap<MarketId, TradingRules>,
+    repo: &MarketDataRepository,
+) -> Vec<Market> {
+    let ids = rules.keys().collect::<Vec<_>>();
+    let known = repo.find_markets(&ids).await.unwrap_or_else(|e| {
+        error!("Error occurred in searching known markets: {}", e);
+        Vec::new()
+    });
+
+    known
+        .into_iter()
+        .flatten()
+        .filter_map(|mut m| {
+            let published = rules.get(&m.id)?;
+            if m.trading_rules() == Some(published) {
+                return None;
+            }
+
+            m.set_trading_rules(Some(published.clone()));
+            Some(m)
+        })
+        .collect()
+}
+
 fn get_unique_ccys(market_pairs: &[MarketPair]) -> HashSet<AssetId> {
     market_pairs
         .iter()
@@ -535,6 +704,13 @@ fn get_kraken_api_periods(freq: OHLCFrequency) -> &'static str {
     }
 }
 
+fn get_kraken_series_interval(timeframe: SeriesTimeframe) -> &'static str {
+    match timeframe {
+        SeriesTimeframe::Daily => "1440",
+        SeriesTimeframe::Weekly => "10080",
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct OHLCResult {
     error: Vec<String>,
@@ -602,3 +778,45 @@ struct CMCInfoData {
     symbol: String,
     name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::dec;
+
+    use super::*;
+
+    #[test]
+    fn reads_pair_trading_rules() {
+        let res: AssetPairsResponse = serde_json::from_value(serde_json::json!({
+            "error": [],
+            "result": {
+                "XXBTZEUR": {
+                    "altname": "XBTEUR",
+                    "wsname": "XBT/EUR",
+                    "status": "online",
+                    "lot_decimals": 8,
+                    "pair_decimals": 1,
+                    "ordermin": "0.00005",
+                    "costmin": "0.5"
+                },
+                "LEGACY": {
+                    "wsname": "ABC/EUR",
+                    "status": "online"
+                }
+            }
+        }))
+        .unwrap();
+
+        let rules = res.result["XXBTZEUR"].trading_rules();
+        assert_eq!(normalize_symbol(&res.result["XXBTZEUR"].wsname), "btc/eur");
+        assert_eq!(rules.lot_decimals, Some(8));
+        assert_eq!(rules.quantity_step(), Some(dec!(0.00000001)));
+        assert_eq!(rules.min_quantity, Some(dec!(0.00005)));
+        assert_eq!(rules.min_cost, Some(dec!(0.5)));
+        assert_eq!(rules.price_decimals, Some(1));
+
+        let rules = res.result["LEGACY"].trading_rules();
+        assert_eq!(rules.quantity_step(), None);
+        assert_eq!(rules.min_quantity, None);
+    }
+}
@@ -1,13 +1,18 @@
 //! The [`adapter`](self) module contains adapters to third-party services
 
+mod central_bank;
 mod cw;
+mod ecb;
 mod ipapi;
 mod kraken;
 mod yahoo;
 
 use std::sync::Arc;
 
+use async_trait::async_trait;
+pub use central_bank::*;
 pub use cw::*;
+pub use ecb::*;
 use failsafe::{
     StateMachine,
     backoff::EqualJittered,
@@ -17,15 +22,85 @@ pub use ipapi::*;
 pub use kraken::*;
 pub use yahoo::*;
 
+use crate::{
+    DateTime,
+    app::domain::entity::{
+        Asset, Market, Price, RateObservation, RateType, SeriesObservation, SeriesTimeframe,
+    },
+    config::PriceProvider,
+    error::{DcaError, Result},
+    ports::outbound::repository::market_data::MarketDataRepository,
+};
+
 type DefaultCircuitBreaker = StateMachine<
     OrElse<SuccessRateOverTimeWindow<EqualJittered>, ConsecutiveFailures<EqualJittered>>,
     (),
 >;
 
+/// A source of market data: latest prices, historical candles and, for
+/// exchanges, the catalog of tradable markets.
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// Returns the provider recorded on the prices it serves.
+    fn id(&self) -> PriceProvider;
+
+    /// Fetches the most recent price of `market` as of `ts`, stamped with the
+    /// time the provider observed it.
+    async fn fetch_latest(&self, market: &Market, ts: DateTime) -> Result<Option<Price>>;
+
+    /// Fetches the `timeframe` samples of `instrument` opened in `[from, to]`.
+    async fn fetch_ohlc(
+        &self,
+        _instrument: &str,
+        _timeframe: SeriesTimeframe,
+        _from: DateTime,
+        _to: DateTime,
+    ) -> Result<Vec<SeriesObservation>> {
+        Err(DcaError::Generic(format!(
+            "Price series are not supported by provider '{}'",
+            self.id()
+        )))
+    }
+
+    /// Discovers the assets and markets missing from the catalog in `repo`.
+    async fn discover(&self, _repo: &MarketDataRepository) -> Result<(Vec<Asset>, Vec<Market>)> {
+        Err(DcaError::Generic(format!(
+            "Market discovery is not supported by provider '{}'",
+            self.id()
+        )))
+    }
+}
+
+/// A publisher of official reference rates.
+#[async_trait]
+pub trait ReferenceRateProvider: Send + Sync {
+    /// Fetches the latest published observation of `rate_type`.
+    async fn fetch_latest(&self, rate_type: RateType) -> Result<Option<RateObservation>>;
+}
+
 #[derive(Clone)]
 pub struct PriceProviders {
     pub cw: Arc<CryptoWatchProvider>,
     pub kraken: Arc<KrakenProvider>,
     pub yahoo: Arc<YahooProvider>,
+    pub ecb: Arc<EcbProvider>,
     pub ipapi: Arc<IpApi>,
+    pub rates: Arc<CentralBankProvider>,
+}
+
+impl PriceProviders {
+    /// Returns the market data adapter of `provider`.
+    pub fn market_data(&self, provider: PriceProvider) -> Arc<dyn MarketDataProvider> {
+        match provider {
+            PriceProvider::CryptoWatch => self.cw.clone(),
+            PriceProvider::Kraken => self.kraken.clone(),
+            PriceProvider::Yahoo => self.yahoo.clone(),
+            PriceProvider::Ecb => self.ecb.clone(),
+        }
+    }
+
+    /// Returns the market data adapters of `chain`, preserving its order.
+    pub fn chain(&self, chain: &[PriceProvider]) -> Vec<Arc<dyn MarketDataProvider>> {
+        chain.iter().map(|p| self.market_data(*p)).collect()
+    }
 }
@@ -1,14 +1,21 @@
 use std::collections::HashMap;
 
+use async_trait::async_trait;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use chrono::{NaiveTime, TimeZone, Utc};
+use rust_decimal::Decimal;
 use tracing::{debug, error, warn};
 
+use super::MarketDataProvider;
 use crate::{
     DateTime,
-    app::domain::entity::{Market, OHLCFrequency},
+    app::domain::entity::{
+        Market, MarketId, OHLCFrequency, Price, SeriesObservation, SeriesTimeframe,
+    },
+    config::PriceProvider,
     error::{DcaError, Result},
 };
 
@@ -22,7 +29,7 @@ impl YahooProvider {
         Self { http }
     }
 
-    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<Price>> {
         if let Some(px) = self.fetch_price(mkt, OHLCFrequency::Minutes5, ts).await? {
             Ok(Some(px))
         } else if let Some(px) = self.fetch_price(mkt, OHLCFrequency::Daily, ts).await? {
@@ -43,7 +50,7 @@ impl YahooProvider {
         mkt: &Market,
         freq: OHLCFrequency,
         ts: DateTime,
-    ) -> Result<Option<f64>> {
+    ) -> Result<Option<Price>> {
         let symbol = mkt.as_yahoo();
         let interval = get_api_interval(freq);
         let (r_lo, r_hi) = freq.ohlc_range(ts);
@@ -103,18 +110,103 @@ impl YahooProvider {
         }
 
         let quote = &result.indicators.quote[0];
-        match quote {
-            chart::QuotesKind::Empty {} => Ok(None),
-            chart::QuotesKind::Quotes(q) => {
-                let price = if q.close.is_empty() {
-                    None
-                } else {
-                    q.close.iter().rev().find_map(|p| *p)
-                };
-
-                Ok(price)
-            }
+        let chart::QuotesKind::Quotes(q) = quote else {
+            return Ok(None);
+        };
+
+        // Yahoo stamps candles with their open time: the close is observed
+        // when the candle ends, or now for the running one
+        let last_close = q
+            .close
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, p)| Some((i, (*p)?)));
+        let Some((i, px)) = last_close else {
+            return Ok(None);
+        };
+
+        let open_ts = result
+            .timestamp
+            .as_ref()
+            .and_then(|t| t.get(i))
+            .and_then(|t| Utc.timestamp_opt(*t, 0).single());
+        let Some(open_ts) = open_ts else {
+            return Err(DcaError::Generic(
+                "Malformed response. Missing chart.result.timestamp".to_owned(),
+            ));
+        };
+        let observed_at = (open_ts + freq.period()).min(ts);
+
+        Ok(Some(Price::new(px, observed_at, ts)))
+    }
+
+    /// Fetches the `timeframe` samples opened in `[from, to]`.
+    ///
+    /// Yahoo stamps a sample with the exchange session open, so samples are
+    /// normalized to the start of their UTC day to match the other providers.
+    pub async fn fetch_series(
+        &self,
+        symbol: &str,
+        timeframe: SeriesTimeframe,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Vec<SeriesObservation>> {
+        let interval = get_series_interval(timeframe);
+        let (period_1, period_2) = (from.timestamp(), to.timestamp());
+        let url = format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{symbol}?period1={period_1}&period2={period_2}&interval={interval}"
+        );
+
+        debug!(
+            url = url,
+            "Fetching {timeframe} series for instrument '{symbol}' in range [{from}, {to}]"
+        );
+
+        let res = self.http.get(&url).send().await?;
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            warn!(url = url, "Unknown instrument '{symbol}'");
+            return Ok(vec![]);
+        } else if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
         }
+
+        let res = res.json::<chart::ChartResponse>().await?;
+        if let Some(e) = res.chart.error {
+            return Err(DcaError::Generic(format!(
+                "Unsuccessful request. Code: {}. Description: {}",
+                e.code, e.description
+            )));
+        }
+
+        let Some(result) = res.chart.result.and_then(|r| r.into_iter().next()) else {
+            return Err(DcaError::Generic(
+                "Malformed response. Unexpected empty chart.result".to_owned(),
+            ));
+        };
+
+        let (Some(timestamps), Some(chart::QuotesKind::Quotes(quotes))) =
+            (result.timestamp, result.indicators.quote.first())
+        else {
+            return Ok(vec![]);
+        };
+
+        let observations = timestamps
+            .iter()
+            .zip(quotes.close.iter())
+            .filter_map(|(ts, px)| {
+                let observed_at = Utc
+                    .timestamp_opt(*ts, 0)
+                    .single()?
+                    .with_time(NaiveTime::MIN)
+                    .single()?;
+                let value = Decimal::try_from((*px)?).ok()?;
+
+                Some(SeriesObservation { observed_at, value })
+            })
+            .collect();
+
+        Ok(observations)
     }
 
     pub async fn search(&self, request_param: String) -> Response {
@@ -150,6 +242,27 @@ impl YahooProvider {
     }
 }
 
+#[async_trait]
+impl MarketDataProvider for YahooProvider {
+    fn id(&self) -> PriceProvider {
+        PriceProvider::Yahoo
+    }
+
+    async fn fetch_latest(&self, market: &Market, ts: DateTime) -> Result<Option<Price>> {
+        self.fetch_market_price(market, ts).await
+    }
+
+    async fn fetch_ohlc(
+        &self,
+        instrument: &str,
+        timeframe: SeriesTimeframe,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Vec<SeriesObservation>> {
+        self.fetch_series(instrument, timeframe, from, to).await
+    }
+}
+
 fn get_api_interval(freq: OHLCFrequency) -> &'static str {
     match freq {
         OHLCFrequency::Minutes5 => "5m",
@@ -157,18 +270,26 @@ fn get_api_interval(freq: OHLCFrequency) -> &'static str {
     }
 }
 
+fn get_series_interval(timeframe: SeriesTimeframe) -> &'static str {
+    match timeframe {
+        SeriesTimeframe::Daily => "1d",
+        SeriesTimeframe::Weekly => "1wk",
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Assets Yahoo lists under another id.
+    static ref MAPPINGS: HashMap<&'static str, &'static str> = {
+        [("luna", ("luna1"))].into_iter().collect()
+    };
+}
+
 pub trait AsYahooMarket {
     fn as_yahoo(&self) -> String;
 }
 
 impl AsYahooMarket for Market {
     fn as_yahoo(&self) -> String {
-        lazy_static::lazy_static! {
-            static ref MAPPINGS: HashMap<&'static str, &'static str> = {
-                [("luna", ("luna1"))].into_iter().collect()
-            };
-        }
-
         if self.is_fiat() {
             if self.base.id() == "usd" {
                 return format!("{}=x", self.quote.id());
@@ -191,6 +312,29 @@ impl AsYahooMarket for Market {
     }
 }
 
+/// Returns the id of the market whose [`AsYahooMarket::as_yahoo`] symbol
+/// `symbol` would be, if it has the shape of one.
+pub fn yahoo_market_id(symbol: &str) -> Option<MarketId> {
+    let symbol = symbol.to_lowercase();
+    if let Some(pair) = symbol.strip_suffix("=x") {
+        return match pair.len() {
+            3 => Some(format!("usd{pair}")),
+            6 => Some(pair.to_string()),
+            _ => None,
+        };
+    }
+
+    let unmapped = |id: &str| {
+        MAPPINGS
+            .iter()
+            .find(|(_, yahoo)| **yahoo == id)
+            .map_or_else(|| id.to_string(), |(asset, _)| asset.to_string())
+    };
+
+    let (base, quote) = symbol.split_once('-')?;
+    Some(format!("{}{}", unmapped(base), unmapped(quote)))
+}
+
 mod chart {
     use serde::Deserialize;
 
@@ -213,6 +357,7 @@ mod chart {
 
     #[derive(Debug, Clone, Deserialize)]
     pub struct Candlestick {
+        pub timestamp: Option<Vec<i64>>,
         pub indicators: Indicators,
     }
 
@@ -233,3 +378,18 @@ mod chart {
         pub close: Vec<Option<f64>>,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_the_market_of_yahoo_symbols() {
+        assert_eq!(yahoo_market_id("EUR=X").as_deref(), Some("usdeur"));
+        assert_eq!(yahoo_market_id("eurchf=x").as_deref(), Some("eurchf"));
+        assert_eq!(yahoo_market_id("BTC-USD").as_deref(), Some("btcusd"));
+        assert_eq!(yahoo_market_id("luna1-usd").as_deref(), Some("lunausd"));
+        assert_eq!(yahoo_market_id("VWCE.DE"), None);
+        assert_eq!(yahoo_market_id("eu=x"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::app::domain::entity::{AssetId, Market, MarketId, Price};
+use crate::app::domain::entity::{AssetId, Market, MarketId, Price, TradingRules};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MarketDto {
@@ -10,6 +10,8 @@ pub struct MarketDto {
     pub quote: AssetId,
     #[serde(flatten)]
     pub price: Option<Price>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trading_rules: Option<TradingRules>,
 }
 
 impl MarketDto {}
@@ -18,12 +20,14 @@ impl From<Market> for MarketDto {
     fn from(m: Market) -> Self {
         let (base, quote) = (m.base.id().clone(), m.quote.id().clone());
         let price = *m.price();
+        let trading_rules = m.trading_rules().cloned();
         Self {
             id: m.id,
             pair: m.pair,
             base,
             quote,
             price,
+            trading_rules,
         }
     }
 }
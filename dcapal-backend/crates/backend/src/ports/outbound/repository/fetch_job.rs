@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    DateTime, app::domain::entity::FetchJobKind, error::Result,
+    ports::outbound::repository::postgres::types::FetchJobRow,
+};
+
+/// Persistence operations for the durable market-data fetch-job queue.
+#[async_trait]
+pub trait FetchJobRepository: Send + Sync {
+    /// Queues a job unless one with the same kind and `dedup_key` already exists.
+    ///
+    /// Returns the new job, or `None` when the request was deduplicated.
+    async fn enqueue(
+        &self,
+        kind: FetchJobKind,
+        dedup_key: &str,
+        series_id: Option<Uuid>,
+        max_attempts: i32,
+    ) -> Result<Option<FetchJobRow>>;
+
+    /// Claims the next runnable job of `kind`, skipping rows locked by other workers.
+    ///
+    /// A job is runnable when it is queued or waiting for a retry whose time
+    /// has come, or when it is running but was started before `stale_before`
+    /// and has attempts left. Stale jobs out of attempts are marked failed.
+    async fn claim(
+        &self,
+        kind: FetchJobKind,
+        now: DateTime,
+        stale_before: DateTime,
+    ) -> Result<Option<FetchJobRow>>;
+
+    /// Marks a claimed job as succeeded.
+    async fn succeed(&self, id: Uuid, now: DateTime) -> Result<()>;
+
+    /// Records a failed attempt, either scheduling a retry at `retry_after`
+    /// or, when `None`, marking the job as failed.
+    async fn fail(
+        &self,
+        id: Uuid,
+        error: &str,
+        retry_after: Option<DateTime>,
+        now: DateTime,
+    ) -> Result<()>;
+
+    /// Deletes succeeded jobs that finished before `before`.
+    async fn prune_succeeded(&self, before: DateTime) -> Result<u64>;
+
+    /// Returns the number of jobs per kind and state.
+    async fn count_by_state(&self) -> Result<Vec<(String, String, i64)>>;
+}
@@ -163,6 +163,10 @@ async fn resolve_market(market: MarketDto, repo: &MarketDataRepository) -> Resul
             error!(mkt = market.id, "Quote asset not found: {}", &market.quote);
             Ok(None)
         }
-        (Some(b), Some(q)) => Ok(Some(Market::new(market.id, b, q, market.price))),
+        (Some(b), Some(q)) => {
+            let mut mkt = Market::new(market.id, b, q, market.price);
+            mkt.set_trading_rules(market.trading_rules);
+            Ok(Some(mkt))
+        }
     }
 }
@@ -9,10 +9,13 @@ use uuid::Uuid;
 
 use crate::{DateTime, app::services::ip2location::GeoData, error::Result};
 pub mod dto;
+pub mod fetch_job;
 pub mod market_data;
 pub mod portfolio;
 /// PostgreSQL-backed repository implementations.
 pub mod postgres;
+pub mod price_series;
+pub mod reference_rate;
 pub mod user;
 
 const REDIS_BASE: &str = "dcapal:be";
@@ -18,6 +18,13 @@ pub trait PortfolioRepository: Send + Sync {
         user_id: Uuid,
     ) -> Result<Vec<(PortfolioRow, Vec<PortfolioAssetRow>)>>;
 
+    /// Returns an owned, non-deleted portfolio together with its assets.
+    async fn find_user_portfolio_with_assets(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Option<(PortfolioRow, Vec<PortfolioAssetRow>)>>;
+
     /// Marks an owned portfolio as deleted.
     async fn soft_delete(&self, user_id: Uuid, portfolio_id: Uuid) -> Result<()>;
 
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, query, query_as};
+use uuid::Uuid;
+
+use crate::{
+    app::domain::entity::{FetchJobKind, FetchJobState},
+    error::Result,
+    ports::outbound::repository::{fetch_job::FetchJobRepository, postgres::types::FetchJobRow},
+};
+
+/// Error recorded on a job whose last attempt never finished.
+const STALE_EXHAUSTED_ERROR: &str = "worker lease expired on the last attempt";
+
+/// PostgreSQL persistence for the market-data fetch-job queue.
+#[derive(Clone)]
+pub struct SqlxFetchJobRepository {
+    pool: PgPool,
+}
+
+impl SqlxFetchJobRepository {
+    /// Creates a fetch-job repository backed by the provided PostgreSQL pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl FetchJobRepository for SqlxFetchJobRepository {
+    async fn enqueue(
+        &self,
+        kind: FetchJobKind,
+        dedup_key: &str,
+        series_id: Option<Uuid>,
+        max_attempts: i32,
+    ) -> Result<Option<FetchJobRow>> {
+        Ok(query_as::<_, FetchJobRow>(
+            "INSERT INTO fetch_job (kind, dedup_key, series_id, state, max_attempts)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (kind, dedup_key) DO NOTHING
+             RETURNING id, kind, dedup_key, series_id, state, attempts, max_attempts,
+                       retry_after, started_at, finished_at, last_error, created_at, updated_at",
+        )
+        .bind(kind.to_string())
+        .bind(dedup_key)
+        .bind(series_id)
+        .bind(FetchJobState::Queued.to_string())
+        .bind(max_attempts)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn claim(
+        &self,
+        kind: FetchJobKind,
+        now: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+    ) -> Result<Option<FetchJobRow>> {
+        // A stale job that used its last attempt keeps crashing its worker:
+        // fail it for good rather than handing it out again.
+        query(
+            "UPDATE fetch_job
+             SET state = $4, finished_at = $2, updated_at = $2,
+                 last_error = COALESCE(last_error, $5)
+             WHERE kind = $1
+               AND state = $6
+               AND started_at < $3
+               AND attempts >= max_attempts",
+        )
+        .bind(kind.to_string())
+        .bind(now)
+        .bind(stale_before)
+        .bind(FetchJobState::Failed.to_string())
+        .bind(STALE_EXHAUSTED_ERROR)
+        .bind(FetchJobState::Running.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        // Concurrent workers skip each other's locked candidates instead of
+        // waiting on them, so each job is handed to exactly one worker.
+        Ok(query_as::<_, FetchJobRow>(
+            "UPDATE fetch_job
+             SET state = $4, attempts = attempts + 1, started_at = $2, updated_at = $2
+             WHERE id = (
+                 SELECT id
+                 FROM fetch_job
+                 WHERE kind = $1
+                   AND ((state IN ($5, $6) AND retry_after <= $2)
+                        OR (state = $4 AND started_at < $3 AND attempts < max_attempts))
+                 ORDER BY retry_after, created_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING id, kind, dedup_key, series_id, state, attempts, max_attempts,
+                       retry_after, started_at, finished_at, last_error, created_at, updated_at",
+        )
+        .bind(kind.to_string())
+        .bind(now)
+        .bind(stale_before)
+        .bind(FetchJobState::Running.to_string())
+        .bind(FetchJobState::Queued.to_string())
+        .bind(FetchJobState::RetryWait.to_string())
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn succeed(&self, id: Uuid, now: DateTime<Utc>) -> Result<()> {
+        query(
+            "UPDATE fetch_job
+             SET state = $2, finished_at = $3, updated_at = $3
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(FetchJobState::Succeeded.to_string())
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fail(
+        &self,
+        id: Uuid,
+        error: &str,
+        retry_after: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let (state, finished_at) = match retry_after {
+            Some(_) => (FetchJobState::RetryWait, None),
+            None => (FetchJobState::Failed, Some(now)),
+        };
+
+        query(
+            "UPDATE fetch_job
+             SET state = $2, last_error = $3, retry_after = COALESCE($4, retry_after),
+                 finished_at = $5, updated_at = $6
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(state.to_string())
+        .bind(error)
+        .bind(retry_after)
+        .bind(finished_at)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn prune_succeeded(&self, before: DateTime<Utc>) -> Result<u64> {
+        let res = query("DELETE FROM fetch_job WHERE state = $1 AND finished_at < $2")
+            .bind(FetchJobState::Succeeded.to_string())
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn count_by_state(&self) -> Result<Vec<(String, String, i64)>> {
+        Ok(query_as::<_, (String, String, i64)>(
+            "SELECT kind, state, COUNT(*)
+             FROM fetch_job
+             GROUP BY kind, state
+             ORDER BY kind, state",
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
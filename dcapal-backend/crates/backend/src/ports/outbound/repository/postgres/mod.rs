@@ -1,11 +1,20 @@
 //! PostgreSQL repository implementations and their persistence row types.
 
+/// Fetch-job queue backed by PostgreSQL.
+pub mod fetch_job;
 /// Portfolio persistence backed by PostgreSQL.
 pub mod portfolio;
+/// Price-series persistence backed by PostgreSQL.
+pub mod price_series;
+/// Reference-rate persistence backed by PostgreSQL.
+pub mod reference_rate;
 /// PostgreSQL row representations used by the repository interfaces.
 pub mod types;
 /// User persistence backed by PostgreSQL.
 pub mod user;
 
+pub use fetch_job::SqlxFetchJobRepository;
 pub use portfolio::SqlxPortfolioRepository;
+pub use price_series::SqlxPriceSeriesRepository;
+pub use reference_rate::SqlxReferenceRateRepository;
 pub use user::SqlxUserRepository;
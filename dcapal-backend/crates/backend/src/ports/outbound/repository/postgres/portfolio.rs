@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
-use sqlx::{Postgres, Transaction, query, query_as};
+use sqlx::{Postgres, Transaction, query, query_as, types::Json};
 use uuid::Uuid;
 
 use crate::{
@@ -33,6 +33,7 @@ struct FeeFields {
     fee_rate: Option<Decimal>,
     min_fee: Option<Decimal>,
     max_fee: Option<Decimal>,
+    fee_structure: Option<Json<FeeStructure>>,
 }
 
 impl SqlxPortfolioRepository {
@@ -50,7 +51,8 @@ impl SqlxPortfolioRepository {
         let existing_assets = query_as::<_, PortfolioAssetRow>(
             "SELECT id, symbol, portfolio_id, name, asset_class, currency, provider,
                     quantity, target_weight, price, max_fee_impact, fee_type, fee_amount,
-                    fee_rate, min_fee, max_fee, average_buy_price, created_at, updated_at
+                    fee_rate, min_fee, max_fee, fee_structure, average_buy_price, created_at,
+                    updated_at
              FROM portfolio_asset
              WHERE portfolio_id = $1
              ORDER BY id
@@ -74,12 +76,13 @@ impl SqlxPortfolioRepository {
                      SET symbol = $2, name = $3, asset_class = $4, currency = $5,
                          provider = $6, quantity = $7, target_weight = $8, price = $9,
                          average_buy_price = $10, max_fee_impact = $11, fee_type = $12,
-                         fee_amount = $13, fee_rate = $14, min_fee = $15, max_fee = $16
+                         fee_amount = $13, fee_rate = $14, min_fee = $15, max_fee = $16,
+                         fee_structure = $17
                      WHERE id = $1
                      RETURNING id, symbol, portfolio_id, name, asset_class, currency, provider,
                                quantity, target_weight, price, max_fee_impact, fee_type,
-                               fee_amount, fee_rate, min_fee, max_fee, average_buy_price,
-                               created_at, updated_at",
+                               fee_amount, fee_rate, min_fee, max_fee, fee_structure,
+                               average_buy_price, created_at, updated_at",
                 )
                 .bind(existing_asset.id)
                 .bind(&asset.symbol)
@@ -97,6 +100,7 @@ impl SqlxPortfolioRepository {
                 .bind(fee_fields.fee_rate)
                 .bind(fee_fields.min_fee)
                 .bind(fee_fields.max_fee)
+                .bind(fee_fields.fee_structure)
                 .fetch_one(&mut **tx)
                 .await?
             } else {
@@ -104,13 +108,13 @@ impl SqlxPortfolioRepository {
                     "INSERT INTO portfolio_asset
                          (id, symbol, portfolio_id, name, asset_class, currency, provider,
                           quantity, target_weight, price, average_buy_price, max_fee_impact,
-                          fee_type, fee_amount, fee_rate, min_fee, max_fee)
+                          fee_type, fee_amount, fee_rate, min_fee, max_fee, fee_structure)
                      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
-                             $15, $16, $17)
+                             $15, $16, $17, $18)
                      RETURNING id, symbol, portfolio_id, name, asset_class, currency, provider,
                                quantity, target_weight, price, max_fee_impact, fee_type,
-                               fee_amount, fee_rate, min_fee, max_fee, average_buy_price,
-                               created_at, updated_at",
+                               fee_amount, fee_rate, min_fee, max_fee, fee_structure,
+                               average_buy_price, created_at, updated_at",
                 )
                 .bind(Uuid::new_v4())
                 .bind(&asset.symbol)
@@ -129,6 +133,7 @@ impl SqlxPortfolioRepository {
                 .bind(fee_fields.fee_rate)
                 .bind(fee_fields.min_fee)
                 .bind(fee_fields.max_fee)
+                .bind(fee_fields.fee_structure)
                 .fetch_one(&mut **tx)
                 .await?
             };
@@ -175,6 +180,13 @@ impl SqlxPortfolioRepository {
                     max_fee,
                     ..Default::default()
                 },
+                // Nested structures do not fit the flat columns and are stored whole
+                FeeStructure::Tiered { .. } | FeeStructure::Composite { .. } => FeeFields {
+                    max_fee_impact: fees.max_fee_impact,
+                    fee_type: Some(fees.fee_structure.to_string()),
+                    fee_structure: Some(Json(fees.fee_structure)),
+                    ..Default::default()
+                },
             },
             None => FeeFields {
                 fee_type: Some(FeeStructure::ZeroFee.to_string()),
@@ -193,7 +205,7 @@ impl PortfolioRepository for SqlxPortfolioRepository {
         let portfolios = query_as::<_, PortfolioRow>(
             "SELECT id, user_id, name, currency, deleted, last_updated_at,
                     max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
-                    created_at, updated_at
+                    fee_structure, created_at, updated_at
              FROM portfolios
              WHERE user_id = $1
              ORDER BY id",
@@ -210,7 +222,8 @@ impl PortfolioRepository for SqlxPortfolioRepository {
         let assets = query_as::<_, PortfolioAssetRow>(
             "SELECT id, symbol, portfolio_id, name, asset_class, currency, provider,
                     quantity, target_weight, price, max_fee_impact, fee_type, fee_amount,
-                    fee_rate, min_fee, max_fee, average_buy_price, created_at, updated_at
+                    fee_rate, min_fee, max_fee, fee_structure, average_buy_price, created_at,
+                    updated_at
              FROM portfolio_asset
              WHERE portfolio_id = ANY($1)
              ORDER BY portfolio_id, id",
@@ -238,6 +251,42 @@ impl PortfolioRepository for SqlxPortfolioRepository {
             .collect())
     }
 
+    async fn find_user_portfolio_with_assets(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Option<(PortfolioRow, Vec<PortfolioAssetRow>)>> {
+        let Some(portfolio) = query_as::<_, PortfolioRow>(
+            "SELECT id, user_id, name, currency, deleted, last_updated_at,
+                    max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
+                    fee_structure, created_at, updated_at
+             FROM portfolios
+             WHERE id = $1 AND user_id = $2 AND NOT deleted",
+        )
+        .bind(portfolio_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let assets = query_as::<_, PortfolioAssetRow>(
+            "SELECT id, symbol, portfolio_id, name, asset_class, currency, provider,
+                    quantity, target_weight, price, max_fee_impact, fee_type, fee_amount,
+                    fee_rate, min_fee, max_fee, fee_structure, average_buy_price, created_at,
+                    updated_at
+             FROM portfolio_asset
+             WHERE portfolio_id = $1
+             ORDER BY id",
+        )
+        .bind(portfolio_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some((portfolio, assets)))
+    }
+
     async fn soft_delete(&self, user_id: Uuid, portfolio_id: Uuid) -> Result<()> {
         // Keep the ownership check in the write itself so this invariant survives other callers.
         query("UPDATE portfolios SET deleted = TRUE WHERE id = $1 AND user_id = $2")
@@ -258,7 +307,7 @@ impl PortfolioRepository for SqlxPortfolioRepository {
         let existing = query_as::<_, PortfolioRow>(
             "SELECT id, user_id, name, currency, deleted, last_updated_at,
                     max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
-                    created_at, updated_at
+                    fee_structure, created_at, updated_at
              FROM portfolios
              WHERE id = $1
              FOR UPDATE",
@@ -281,11 +330,11 @@ impl PortfolioRepository for SqlxPortfolioRepository {
                 "UPDATE portfolios
                  SET name = $2, currency = $3, last_updated_at = $4,
                      max_fee_impact = $5, fee_type = $6, fee_amount = $7,
-                     fee_rate = $8, min_fee = $9, max_fee = $10
+                     fee_rate = $8, min_fee = $9, max_fee = $10, fee_structure = $11
                  WHERE id = $1
                  RETURNING id, user_id, name, currency, deleted, last_updated_at,
                            max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
-                           created_at, updated_at",
+                           fee_structure, created_at, updated_at",
             )
             .bind(portfolio_req.id)
             .bind(&portfolio_req.name)
@@ -297,17 +346,19 @@ impl PortfolioRepository for SqlxPortfolioRepository {
             .bind(fee_fields.fee_rate)
             .bind(fee_fields.min_fee)
             .bind(fee_fields.max_fee)
+            .bind(fee_fields.fee_structure)
             .fetch_one(&mut *tx)
             .await?
         } else {
             query_as::<_, PortfolioRow>(
                 "INSERT INTO portfolios
                      (id, user_id, name, currency, deleted, last_updated_at,
-                      max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee)
-                 VALUES ($1, $2, $3, $4, FALSE, $5, $6, $7, $8, $9, $10, $11)
+                      max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
+                      fee_structure)
+                 VALUES ($1, $2, $3, $4, FALSE, $5, $6, $7, $8, $9, $10, $11, $12)
                  RETURNING id, user_id, name, currency, deleted, last_updated_at,
                            max_fee_impact, fee_type, fee_amount, fee_rate, min_fee, max_fee,
-                           created_at, updated_at",
+                           fee_structure, created_at, updated_at",
             )
             .bind(portfolio_req.id)
             .bind(user_id)
@@ -320,6 +371,7 @@ impl PortfolioRepository for SqlxPortfolioRepository {
             .bind(fee_fields.fee_rate)
             .bind(fee_fields.min_fee)
             .bind(fee_fields.max_fee)
+            .bind(fee_fields.fee_structure)
             .fetch_one(&mut *tx)
             .await?
         };
@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, query, query_as, query_scalar};
+use uuid::Uuid;
+
+use crate::{
+    app::domain::entity::{SeriesKey, SeriesObservation, SeriesTimeframe},
+    error::Result,
+    ports::outbound::repository::{
+        postgres::types::{PriceObservationRow, PriceSeriesRow},
+        price_series::PriceSeriesRepository,
+    },
+};
+
+/// PostgreSQL persistence for price series and their observations.
+#[derive(Clone)]
+pub struct SqlxPriceSeriesRepository {
+    pool: PgPool,
+}
+
+impl SqlxPriceSeriesRepository {
+    /// Creates a price-series repository backed by the provided PostgreSQL pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PriceSeriesRepository for SqlxPriceSeriesRepository {
+    async fn find_or_register(&self, key: &SeriesKey) -> Result<PriceSeriesRow> {
+        // The no-op update makes RETURNING yield the existing row on conflict.
+        Ok(query_as::<_, PriceSeriesRow>(
+            "INSERT INTO price_series (provider, instrument, market)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (provider, instrument, market)
+             DO UPDATE SET provider = EXCLUDED.provider
+             RETURNING id, provider, instrument, market, last_fetched_at,
+                       created_at, updated_at",
+        )
+        .bind(key.provider.to_string())
+        .bind(&key.instrument)
+        .bind(&key.market)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_by_id(&self, series_id: Uuid) -> Result<Option<PriceSeriesRow>> {
+        Ok(query_as::<_, PriceSeriesRow>(
+            "SELECT id, provider, instrument, market, last_fetched_at, created_at, updated_at
+             FROM price_series
+             WHERE id = $1",
+        )
+        .bind(series_id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn load_due(&self, before: DateTime<Utc>) -> Result<Vec<PriceSeriesRow>> {
+        Ok(query_as::<_, PriceSeriesRow>(
+            "SELECT id, provider, instrument, market, last_fetched_at, created_at, updated_at
+             FROM price_series
+             WHERE last_fetched_at IS NULL OR last_fetched_at < $1
+             ORDER BY last_fetched_at NULLS FIRST, id",
+        )
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn last_observed_at(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+    ) -> Result<Option<DateTime<Utc>>> {
+        Ok(query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT MAX(observed_at)
+             FROM price_series_observation
+             WHERE series_id = $1 AND timeframe = $2",
+        )
+        .bind(series_id)
+        .bind(timeframe.to_string())
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn store_observations(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+        observations: &[SeriesObservation],
+        fetched_at: DateTime<Utc>,
+    ) -> Result<u64> {
+        if observations.is_empty() {
+            return Ok(0);
+        }
+
+        let observed_at: Vec<DateTime<Utc>> = observations.iter().map(|o| o.observed_at).collect();
+        let values: Vec<Decimal> = observations.iter().map(|o| o.value).collect();
+
+        // Re-ingesting a bucket is idempotent: the latest provider value wins.
+        let res = query(
+            "INSERT INTO price_series_observation
+                 (series_id, timeframe, observed_at, value, fetched_at)
+             SELECT $1, $2, o.observed_at, o.value, $5
+             FROM UNNEST($3::TIMESTAMPTZ[], $4::NUMERIC[]) AS o (observed_at, value)
+             ON CONFLICT (series_id, timeframe, observed_at)
+             DO UPDATE SET value = EXCLUDED.value, fetched_at = EXCLUDED.fetched_at",
+        )
+        .bind(series_id)
+        .bind(timeframe.to_string())
+        .bind(&observed_at)
+        .bind(&values)
+        .bind(fetched_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn mark_fetched(&self, series_id: Uuid, fetched_at: DateTime<Utc>) -> Result<()> {
+        query("UPDATE price_series SET last_fetched_at = $2, updated_at = NOW() WHERE id = $1")
+            .bind(series_id)
+            .bind(fetched_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn load_observations(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<PriceObservationRow>> {
+        Ok(query_as::<_, PriceObservationRow>(
+            "SELECT series_id, timeframe, observed_at, value, fetched_at
+             FROM price_series_observation
+             WHERE series_id = $1 AND timeframe = $2
+               AND observed_at >= $3 AND observed_at <= $4
+             ORDER BY observed_at",
+        )
+        .bind(series_id)
+        .bind(timeframe.to_string())
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
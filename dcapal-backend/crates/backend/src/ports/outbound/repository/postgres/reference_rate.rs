@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, query, query_as};
+
+use crate::{
+    app::domain::entity::{RateObservation, RateType},
+    error::Result,
+    ports::outbound::repository::{
+        postgres::types::ReferenceRateRow, reference_rate::ReferenceRateRepository,
+    },
+};
+
+/// PostgreSQL persistence for reference rate observations.
+#[derive(Clone)]
+pub struct SqlxReferenceRateRepository {
+    pool: PgPool,
+}
+
+impl SqlxReferenceRateRepository {
+    /// Creates a reference-rate repository backed by the provided PostgreSQL pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ReferenceRateRepository for SqlxReferenceRateRepository {
+    async fn store_observation(
+        &self,
+        observation: &RateObservation,
+        fetched_at: DateTime<Utc>,
+    ) -> Result<()> {
+        // Publishers may revise an observation: the latest value wins, but it
+        // keeps the time it was first found published.
+        query(
+            "INSERT INTO reference_rate_observation
+                 (rate_type, observed_on, value, published_at, fetched_at)
+             VALUES ($1, $2, $3, $4, $4)
+             ON CONFLICT (rate_type, observed_on)
+             DO UPDATE SET value = EXCLUDED.value, fetched_at = EXCLUDED.fetched_at",
+        )
+        .bind(observation.rate_type.to_string())
+        .bind(observation.observed_on)
+        .bind(observation.percent)
+        .bind(fetched_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_latest(&self, rate_type: RateType) -> Result<Option<ReferenceRateRow>> {
+        Ok(query_as::<_, ReferenceRateRow>(
+            "SELECT rate_type, observed_on, value, published_at, fetched_at
+             FROM reference_rate_observation
+             WHERE rate_type = $1
+             ORDER BY observed_on DESC
+             LIMIT 1",
+        )
+        .bind(rate_type.to_string())
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A row from the `fetch_job` table.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct FetchJobRow {
+    /// The job identifier.
+    pub id: Uuid,
+    /// The kind of market-data work.
+    pub kind: String,
+    /// The key deduplicating repeated requests for the same work.
+    pub dedup_key: String,
+    /// The price series to ingest, for backfill jobs.
+    pub series_id: Option<Uuid>,
+    /// The job lifecycle state.
+    pub state: String,
+    /// The number of attempts started so far.
+    pub attempts: i32,
+    /// The number of attempts allowed before the job fails.
+    pub max_attempts: i32,
+    /// The earliest time the job can be claimed.
+    pub retry_after: DateTime<Utc>,
+    /// When the latest attempt started.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job reached a terminal state.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The error reported by the latest failed attempt.
+    pub last_error: Option<String>,
+    /// When the database row was created.
+    pub created_at: DateTime<Utc>,
+    /// When the database row was last changed.
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,9 +1,71 @@
 //! PostgreSQL row types returned by SQLx queries.
 
+use rust_decimal::Decimal;
+use sqlx::types::Json;
+
+use crate::{
+    error::{DcaError, Result},
+    ports::inbound::rest::FeeStructure,
+};
+
+mod fetch_job;
 mod portfolio;
 mod portfolio_asset;
+mod price_series;
+mod reference_rate;
 mod user;
 
+pub use fetch_job::FetchJobRow;
 pub use portfolio::PortfolioRow;
 pub use portfolio_asset::PortfolioAssetRow;
+pub use price_series::{PriceObservationRow, PriceSeriesRow};
+pub use reference_rate::ReferenceRateRow;
 pub use user::UserRow;
+
+/// The fee columns shared by the `portfolios` and `portfolio_asset` tables.
+struct FeeColumns<'a> {
+    fee_type: Option<&'a str>,
+    fee_amount: Option<Decimal>,
+    fee_rate: Option<Decimal>,
+    min_fee: Option<Decimal>,
+    max_fee: Option<Decimal>,
+    fee_structure: Option<&'a Json<FeeStructure>>,
+}
+
+impl FeeColumns<'_> {
+    /// Rebuilds the fee structure the columns store, if any.
+    fn decode(self) -> Result<Option<FeeStructure>> {
+        let fee_structure = match self.fee_type {
+            None => return Ok(None),
+            Some("ZeroFee") => FeeStructure::ZeroFee,
+            Some("Fixed") => FeeStructure::Fixed {
+                fee_amount: self.fee_amount.ok_or_else(|| {
+                    DcaError::Generic("Fixed fee requires fee_amount to be Some.".to_string())
+                })?,
+            },
+            Some("Variable") => match (self.fee_rate, self.min_fee) {
+                (Some(fee_rate), Some(min_fee)) => FeeStructure::Variable {
+                    fee_rate,
+                    min_fee,
+                    max_fee: self.max_fee,
+                },
+                _ => {
+                    return Err(DcaError::Generic(
+                        "Variable fee requires fee_rate and min_fee to be Some.".to_string(),
+                    ));
+                }
+            },
+            Some(fee_type @ ("Tiered" | "Composite")) => self
+                .fee_structure
+                .map(|Json(fee_structure)| fee_structure.clone())
+                .ok_or_else(|| {
+                    DcaError::Generic(format!("{fee_type} fee requires fee_structure to be Some."))
+                })?,
+            Some(other) => {
+                return Err(DcaError::Generic(format!("Unknown fee type: {other}")));
+            }
+        };
+
+        Ok(Some(fee_structure))
+    }
+}
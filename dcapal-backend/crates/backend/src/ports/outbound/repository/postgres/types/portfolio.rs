@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use sqlx::types::Json;
 use uuid::Uuid;
 
+use super::FeeColumns;
+use crate::{error::Result, ports::inbound::rest::FeeStructure};
+
 /// A row from the `portfolios` table.
 #[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
 pub struct PortfolioRow {
@@ -29,8 +33,25 @@ pub struct PortfolioRow {
     pub min_fee: Option<Decimal>,
     /// The maximum variable fee, when configured.
     pub max_fee: Option<Decimal>,
+    /// The whole fee structure, when it is tiered or composite.
+    pub fee_structure: Option<Json<FeeStructure>>,
     /// When the database row was created.
     pub created_at: DateTime<Utc>,
     /// When the database row was last changed.
     pub updated_at: DateTime<Utc>,
 }
+
+impl PortfolioRow {
+    /// Rebuilds the portfolio fee structure from the columns it is stored in.
+    pub fn stored_fee_structure(&self) -> Result<Option<FeeStructure>> {
+        FeeColumns {
+            fee_type: self.fee_type.as_deref(),
+            fee_amount: self.fee_amount,
+            fee_rate: self.fee_rate,
+            min_fee: self.min_fee,
+            max_fee: self.max_fee,
+            fee_structure: self.fee_structure.as_ref(),
+        }
+        .decode()
+    }
+}
@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use sqlx::types::Json;
 use uuid::Uuid;
 
+use super::FeeColumns;
+use crate::{error::Result, ports::inbound::rest::FeeStructure};
+
 /// A row from the `portfolio_asset` table.
 #[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
 pub struct PortfolioAssetRow {
@@ -37,6 +41,8 @@ pub struct PortfolioAssetRow {
     pub min_fee: Option<Decimal>,
     /// The maximum variable fee, when configured.
     pub max_fee: Option<Decimal>,
+    /// The whole fee structure, when it is tiered or composite.
+    pub fee_structure: Option<Json<FeeStructure>>,
     /// The average price paid for the current holding.
     pub average_buy_price: Option<Decimal>,
     /// When the database row was created.
@@ -44,3 +50,18 @@ pub struct PortfolioAssetRow {
     /// When the database row was last changed.
     pub updated_at: DateTime<Utc>,
 }
+
+impl PortfolioAssetRow {
+    /// Rebuilds the asset fee structure from the columns it is stored in.
+    pub fn stored_fee_structure(&self) -> Result<Option<FeeStructure>> {
+        FeeColumns {
+            fee_type: self.fee_type.as_deref(),
+            fee_amount: self.fee_amount,
+            fee_rate: self.fee_rate,
+            min_fee: self.min_fee,
+            max_fee: self.max_fee,
+            fee_structure: self.fee_structure.as_ref(),
+        }
+        .decode()
+    }
+}
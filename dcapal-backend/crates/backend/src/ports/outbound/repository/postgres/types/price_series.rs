@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// A row from the `price_series` table.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct PriceSeriesRow {
+    /// The series identifier.
+    pub id: Uuid,
+    /// The market data provider serving the series.
+    pub provider: String,
+    /// The provider's instrument symbol.
+    pub instrument: String,
+    /// The provider exchange or market, empty when the provider exposes none.
+    pub market: String,
+    /// When the series was last ingested successfully, if ever.
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    /// When the database row was created.
+    pub created_at: DateTime<Utc>,
+    /// When the database row was last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A row from the `price_series_observation` table.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct PriceObservationRow {
+    /// The series the observation belongs to.
+    pub series_id: Uuid,
+    /// The sampling interval of the observation.
+    pub timeframe: String,
+    /// The provider bucket open time.
+    pub observed_at: DateTime<Utc>,
+    /// The bucket close value.
+    pub value: Decimal,
+    /// When the observation was retrieved from the provider.
+    pub fetched_at: DateTime<Utc>,
+}
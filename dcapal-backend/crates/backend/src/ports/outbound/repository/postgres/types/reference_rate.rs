@@ -0,0 +1,17 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+/// A row from the `reference_rate_observation` table.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ReferenceRateRow {
+    /// The reference rate the observation belongs to.
+    pub rate_type: String,
+    /// The business day the rate describes.
+    pub observed_on: NaiveDate,
+    /// The rate in percent.
+    pub value: Decimal,
+    /// When ingestion first found the observation published.
+    pub published_at: DateTime<Utc>,
+    /// When the observation was last retrieved from its publisher.
+    pub fetched_at: DateTime<Utc>,
+}
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    DateTime,
+    app::domain::entity::{SeriesKey, SeriesObservation, SeriesTimeframe},
+    error::Result,
+    ports::outbound::repository::postgres::types::{PriceObservationRow, PriceSeriesRow},
+};
+
+/// Persistence operations for provider-bound price series.
+#[async_trait]
+pub trait PriceSeriesRepository: Send + Sync {
+    /// Returns the series identified by `key`, registering it when unknown.
+    async fn find_or_register(&self, key: &SeriesKey) -> Result<PriceSeriesRow>;
+
+    /// Returns the series with the given id, if any.
+    async fn find_by_id(&self, series_id: Uuid) -> Result<Option<PriceSeriesRow>>;
+
+    /// Returns the series that were never ingested or were last ingested before `before`.
+    async fn load_due(&self, before: DateTime) -> Result<Vec<PriceSeriesRow>>;
+
+    /// Returns the most recent stored observation time for a series timeframe.
+    async fn last_observed_at(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+    ) -> Result<Option<DateTime>>;
+
+    /// Inserts observations, replacing the value of buckets already stored.
+    async fn store_observations(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+        observations: &[SeriesObservation],
+        fetched_at: DateTime,
+    ) -> Result<u64>;
+
+    /// Records a successful ingestion of every timeframe of a series.
+    async fn mark_fetched(&self, series_id: Uuid, fetched_at: DateTime) -> Result<()>;
+
+    /// Returns the observations in `[from, to]`, ordered by observation time.
+    async fn load_observations(
+        &self,
+        series_id: Uuid,
+        timeframe: SeriesTimeframe,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Vec<PriceObservationRow>>;
+}
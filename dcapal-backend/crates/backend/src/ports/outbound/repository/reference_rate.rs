@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+use crate::{
+    DateTime,
+    app::domain::entity::{RateObservation, RateType},
+    error::Result,
+    ports::outbound::repository::postgres::types::ReferenceRateRow,
+};
+
+/// Persistence operations for ingested reference rate observations.
+#[async_trait]
+pub trait ReferenceRateRepository: Send + Sync {
+    /// Inserts an observation, replacing the value of one already stored.
+    /// The first `fetched_at` of an observation is kept as its publication time.
+    async fn store_observation(
+        &self,
+        observation: &RateObservation,
+        fetched_at: DateTime,
+    ) -> Result<()>;
+
+    /// Returns the most recent observation of `rate_type`, if any.
+    async fn find_latest(&self, rate_type: RateType) -> Result<Option<ReferenceRateRow>>;
+}
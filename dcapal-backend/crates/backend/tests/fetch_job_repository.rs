@@ -0,0 +1,156 @@
+use chrono::{Duration, Utc};
+use dcapal_backend::{
+    app::domain::entity::FetchJobKind,
+    ports::outbound::repository::{
+        fetch_job::FetchJobRepository, postgres::SqlxFetchJobRepository,
+    },
+};
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn enqueues_a_job_once_per_dedup_key(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxFetchJobRepository::new(pool.clone());
+
+    let first = repository
+        .enqueue(FetchJobKind::Discover, "2026-09-02", None, 5)
+        .await?;
+    let second = repository
+        .enqueue(FetchJobKind::Discover, "2026-09-02", None, 5)
+        .await?;
+    let other_kind = repository
+        .enqueue(FetchJobKind::Reprice, "2026-09-02", None, 3)
+        .await?;
+
+    let first = first.expect("first job should be queued");
+    assert_eq!(first.state, "queued");
+    assert_eq!(first.attempts, 0);
+    assert_eq!(second, None);
+    assert!(other_kind.is_some());
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn hands_each_job_to_a_single_worker(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxFetchJobRepository::new(pool.clone());
+    repository
+        .enqueue(FetchJobKind::Reprice, "slot-1", None, 3)
+        .await?;
+
+    let now = Utc::now();
+    let stale_before = now - Duration::minutes(30);
+    let (a, b) = tokio::join!(
+        repository.claim(FetchJobKind::Reprice, now, stale_before),
+        repository.claim(FetchJobKind::Reprice, now, stale_before),
+    );
+    let claimed: Vec<_> = [a?, b?].into_iter().flatten().collect();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].state, "running");
+    assert_eq!(claimed[0].attempts, 1);
+
+    // Other kinds are never handed out
+    let discover = repository
+        .claim(FetchJobKind::Discover, now, stale_before)
+        .await?;
+    assert_eq!(discover, None);
+
+    // A running job is reclaimed once its lease expires
+    let reclaimed = repository
+        .claim(FetchJobKind::Reprice, now, now + Duration::seconds(1))
+        .await?
+        .expect("stale job should be reclaimed");
+    assert_eq!(reclaimed.id, claimed[0].id);
+    assert_eq!(reclaimed.attempts, 2);
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn retries_failed_jobs_until_they_fail_for_good(
+    pool: PgPool,
+) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxFetchJobRepository::new(pool.clone());
+    repository
+        .enqueue(FetchJobKind::Discover, "2026-09-02", None, 2)
+        .await?;
+
+    let now = Utc::now();
+    let stale_before = now - Duration::minutes(30);
+    let job = repository
+        .claim(FetchJobKind::Discover, now, stale_before)
+        .await?
+        .unwrap();
+
+    let retry_after = now + Duration::minutes(5);
+    repository
+        .fail(job.id, "provider timeout", Some(retry_after), now)
+        .await?;
+
+    // Not runnable before its retry time
+    let early = repository
+        .claim(FetchJobKind::Discover, now, stale_before)
+        .await?;
+    assert_eq!(early, None);
+
+    let retry = repository
+        .claim(FetchJobKind::Discover, retry_after, stale_before)
+        .await?
+        .expect("job should be retried");
+    assert_eq!(retry.attempts, 2);
+    assert_eq!(retry.last_error.as_deref(), Some("provider timeout"));
+
+    repository
+        .fail(retry.id, "provider down", None, retry_after)
+        .await?;
+    let after = repository
+        .claim(
+            FetchJobKind::Discover,
+            retry_after + Duration::days(1),
+            stale_before,
+        )
+        .await?;
+    assert_eq!(after, None);
+
+    let counts = repository.count_by_state().await?;
+    assert_eq!(
+        counts,
+        vec![("discover".to_string(), "failed".to_string(), 1)]
+    );
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn fails_stale_jobs_out_of_attempts(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxFetchJobRepository::new(pool.clone());
+    repository
+        .enqueue(FetchJobKind::Reprice, "slot-1", None, 2)
+        .await?;
+
+    // Every attempt crashes its worker, leaving the job running
+    let now = Utc::now();
+    let expired = now + Duration::seconds(1);
+    let first = repository
+        .claim(FetchJobKind::Reprice, now, now - Duration::minutes(30))
+        .await?
+        .unwrap();
+    let second = repository
+        .claim(FetchJobKind::Reprice, now, expired)
+        .await?
+        .expect("stale job should be reclaimed");
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.attempts, 2);
+
+    let third = repository
+        .claim(FetchJobKind::Reprice, now, expired)
+        .await?;
+    assert_eq!(third, None);
+
+    let counts = repository.count_by_state().await?;
+    assert_eq!(
+        counts,
+        vec![("reprice".to_string(), "failed".to_string(), 1)]
+    );
+
+    Ok(())
+}
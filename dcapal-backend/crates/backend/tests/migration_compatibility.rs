@@ -9,7 +9,7 @@ async fn sqlx_migrations_adopt_the_existing_seaorm_schema(pool: PgPool) -> sqlx:
     let migration_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations")
         .fetch_one(&pool)
         .await?;
-    assert_eq!(migration_count, 4);
+    assert_eq!(migration_count, 8);
 
     let seaorm_table: Option<String> =
         sqlx::query_scalar("SELECT to_regclass('seaql_migrations')::text")
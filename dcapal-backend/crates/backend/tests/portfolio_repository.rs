@@ -3,7 +3,7 @@ use dcapal_backend::{
     error::DcaError,
     ports::{
         inbound::rest::{
-            FeeStructure,
+            FeeStructure, FeeTier,
             request::{PortfolioAssetRequest, PortfolioRequest},
         },
         outbound::repository::{portfolio::PortfolioRepository, postgres::SqlxPortfolioRepository},
@@ -68,6 +68,31 @@ async fn reads_portfolios_with_their_assets(pool: PgPool) -> dcapal_backend::err
     Ok(())
 }
 
+#[sqlx::test(migrations = "../../migrations", fixtures("users", "portfolio"))]
+async fn finds_only_owned_live_portfolios(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxPortfolioRepository::new(pool);
+
+    let (portfolio, assets) = repository
+        .find_user_portfolio_with_assets(USER_ID, PORTFOLIO_ID)
+        .await?
+        .expect("owned portfolio should be found");
+    assert_eq!(portfolio.id, PORTFOLIO_ID);
+    assert_eq!(assets.len(), 2);
+
+    let other = repository
+        .find_user_portfolio_with_assets(OTHER_USER_ID, PORTFOLIO_ID)
+        .await?;
+    assert!(other.is_none());
+
+    repository.soft_delete(USER_ID, PORTFOLIO_ID).await?;
+    let deleted = repository
+        .find_user_portfolio_with_assets(USER_ID, PORTFOLIO_ID)
+        .await?;
+    assert!(deleted.is_none());
+
+    Ok(())
+}
+
 #[sqlx::test(migrations = "../../migrations", fixtures("users", "portfolio"))]
 async fn upsert_updates_assets_and_removes_missing_assets(
     pool: PgPool,
@@ -93,6 +118,54 @@ async fn upsert_updates_assets_and_removes_missing_assets(
     Ok(())
 }
 
+#[sqlx::test(migrations = "../../migrations", fixtures("users", "portfolio"))]
+async fn stores_nested_fee_structures(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxPortfolioRepository::new(pool);
+
+    let tiered = FeeStructure::Tiered {
+        tiers: vec![
+            FeeTier {
+                up_to: Some(dec!(1000)),
+                fee_structure: FeeStructure::Fixed {
+                    fee_amount: dec!(2),
+                },
+            },
+            FeeTier {
+                up_to: None,
+                fee_structure: FeeStructure::Variable {
+                    fee_rate: dec!(0.001),
+                    min_fee: dec!(0),
+                    max_fee: None,
+                },
+            },
+        ],
+    };
+    let composite = FeeStructure::Composite {
+        components: vec![
+            FeeStructure::Fixed {
+                fee_amount: dec!(1),
+            },
+            tiered.clone(),
+        ],
+    };
+
+    let mut request = portfolio_request(vec![asset("VWCE")]);
+    request.fees.as_mut().unwrap().fee_structure = composite.clone();
+    request.assets[0].fees.as_mut().unwrap().fee_structure = tiered.clone();
+    repository.upsert(USER_ID, request).await?;
+
+    let (portfolio, assets) = repository
+        .find_user_portfolio_with_assets(USER_ID, PORTFOLIO_ID)
+        .await?
+        .unwrap();
+    assert_eq!(portfolio.fee_type.as_deref(), Some("Composite"));
+    assert_eq!(portfolio.stored_fee_structure()?, Some(composite));
+    assert_eq!(assets[0].fee_type.as_deref(), Some("Tiered"));
+    assert_eq!(assets[0].stored_fee_structure()?, Some(tiered));
+
+    Ok(())
+}
+
 #[sqlx::test(migrations = "../../migrations", fixtures("users", "portfolio"))]
 async fn ownership_is_required_for_upsert_and_delete(
     pool: PgPool,
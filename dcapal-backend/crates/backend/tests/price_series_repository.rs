@@ -0,0 +1,90 @@
+use chrono::{Duration, TimeZone, Utc};
+use dcapal_backend::{
+    app::domain::entity::{SeriesKey, SeriesObservation, SeriesTimeframe},
+    config::PriceProvider,
+    ports::outbound::repository::{
+        postgres::SqlxPriceSeriesRepository, price_series::PriceSeriesRepository,
+    },
+};
+use rust_decimal::dec;
+use sqlx::PgPool;
+
+fn key(instrument: &str) -> SeriesKey {
+    SeriesKey {
+        provider: PriceProvider::Kraken,
+        instrument: instrument.to_string(),
+        market: String::new(),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn registers_a_series_once(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxPriceSeriesRepository::new(pool.clone());
+
+    let first = repository.find_or_register(&key("btceur")).await?;
+    let second = repository.find_or_register(&key("btceur")).await?;
+    assert_eq!(first.id, second.id);
+    assert_eq!(first.provider, "kraken");
+    assert_eq!(first.last_fetched_at, None);
+
+    let due = repository.load_due(Utc::now()).await?;
+    assert_eq!(due.len(), 1);
+
+    repository.mark_fetched(first.id, Utc::now()).await?;
+    let due = repository.load_due(Utc::now() - Duration::days(1)).await?;
+    assert!(due.is_empty());
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn stores_observations_idempotently(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxPriceSeriesRepository::new(pool.clone());
+    let series = repository.find_or_register(&key("etheur")).await?;
+    let day = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+
+    let observations = [
+        SeriesObservation {
+            observed_at: day,
+            value: dec!(1800.5),
+        },
+        SeriesObservation {
+            observed_at: day + Duration::days(1),
+            value: dec!(1810.25),
+        },
+    ];
+    let stored = repository
+        .store_observations(series.id, SeriesTimeframe::Daily, &observations, Utc::now())
+        .await?;
+    assert_eq!(stored, 2);
+
+    let revised = [SeriesObservation {
+        observed_at: day + Duration::days(1),
+        value: dec!(1811),
+    }];
+    repository
+        .store_observations(series.id, SeriesTimeframe::Daily, &revised, Utc::now())
+        .await?;
+
+    let rows = repository
+        .load_observations(
+            series.id,
+            SeriesTimeframe::Daily,
+            day,
+            day + Duration::days(7),
+        )
+        .await?;
+    let values: Vec<_> = rows.iter().map(|r| r.value).collect();
+    assert_eq!(values, vec![dec!(1800.5), dec!(1811)]);
+
+    let weekly = repository
+        .last_observed_at(series.id, SeriesTimeframe::Weekly)
+        .await?;
+    assert_eq!(weekly, None);
+    let daily = repository
+        .last_observed_at(series.id, SeriesTimeframe::Daily)
+        .await?;
+    assert_eq!(daily, Some(day + Duration::days(1)));
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use dcapal_backend::{
+    app::domain::entity::{RateObservation, RateType},
+    ports::outbound::repository::{
+        postgres::SqlxReferenceRateRepository, reference_rate::ReferenceRateRepository,
+    },
+};
+use rust_decimal::dec;
+use sqlx::PgPool;
+
+fn observation(day: u32, percent: rust_decimal::Decimal) -> RateObservation {
+    RateObservation {
+        rate_type: RateType::EcbEstr,
+        observed_on: NaiveDate::from_ymd_opt(2026, 7, day).unwrap(),
+        percent,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn finds_the_latest_observation(pool: PgPool) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxReferenceRateRepository::new(pool.clone());
+    let fetched_at = Utc.with_ymd_and_hms(2026, 8, 3, 8, 0, 0).unwrap();
+
+    assert_eq!(repository.find_latest(RateType::EcbEstr).await?, None);
+
+    repository
+        .store_observation(&observation(31, dec!(1.922)), fetched_at)
+        .await?;
+    repository
+        .store_observation(&observation(30, dec!(1.921)), fetched_at)
+        .await?;
+
+    let latest = repository.find_latest(RateType::EcbEstr).await?.unwrap();
+    assert_eq!(latest.rate_type, "ecb_estr");
+    assert_eq!(
+        latest.observed_on,
+        NaiveDate::from_ymd_opt(2026, 7, 31).unwrap()
+    );
+    assert_eq!(latest.value, dec!(1.922));
+    assert_eq!(repository.find_latest(RateType::FedEffr).await?, None);
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn keeps_the_publication_time_of_revised_observations(
+    pool: PgPool,
+) -> dcapal_backend::error::Result<()> {
+    let repository = SqlxReferenceRateRepository::new(pool.clone());
+    let published_at = Utc.with_ymd_and_hms(2026, 8, 3, 8, 0, 0).unwrap();
+    let revised_at = published_at + Duration::hours(1);
+
+    repository
+        .store_observation(&observation(31, dec!(1.922)), published_at)
+        .await?;
+    repository
+        .store_observation(&observation(31, dec!(1.95)), revised_at)
+        .await?;
+
+    let latest = repository.find_latest(RateType::EcbEstr).await?.unwrap();
+    assert_eq!(latest.value, dec!(1.95));
+    assert_eq!(latest.published_at, published_at);
+    assert_eq!(latest.fetched_at, revised_at);
+
+    Ok(())
+}
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS price_series_observation;
+DROP TABLE IF EXISTS price_series;
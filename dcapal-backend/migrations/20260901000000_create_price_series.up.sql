@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS price_series (
+    id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider TEXT NOT NULL,
+    instrument TEXT NOT NULL,
+    market TEXT NOT NULL DEFAULT '',
+    last_fetched_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT uq_price_series_key UNIQUE (provider, instrument, market)
+);
+
+CREATE TABLE IF NOT EXISTS price_series_observation (
+    series_id UUID NOT NULL,
+    timeframe TEXT NOT NULL,
+    observed_at TIMESTAMPTZ NOT NULL,
+    value NUMERIC NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL,
+    CONSTRAINT pk_price_series_observation PRIMARY KEY (series_id, timeframe, observed_at),
+    CONSTRAINT fk_price_series_observation_series_id
+        FOREIGN KEY (series_id) REFERENCES price_series (id) ON DELETE CASCADE,
+    CONSTRAINT ck_price_series_observation_timeframe CHECK (timeframe IN ('daily', 'weekly'))
+);
@@ -0,0 +1,2 @@
+DROP VIEW IF EXISTS fetch_job_backlog;
+DROP TABLE IF EXISTS fetch_job;
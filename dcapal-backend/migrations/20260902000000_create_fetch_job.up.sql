@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS fetch_job (
+    id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind TEXT NOT NULL,
+    dedup_key TEXT NOT NULL,
+    series_id UUID,
+    state TEXT NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    retry_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT uq_fetch_job_dedup_key UNIQUE (kind, dedup_key),
+    CONSTRAINT fk_fetch_job_series_id
+        FOREIGN KEY (series_id) REFERENCES price_series (id) ON DELETE CASCADE,
+    CONSTRAINT ck_fetch_job_kind CHECK (kind IN ('discover', 'reprice', 'backfill')),
+    CONSTRAINT ck_fetch_job_state
+        CHECK (state IN ('queued', 'running', 'retry_wait', 'succeeded', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS ix_fetch_job_claim
+    ON fetch_job (kind, retry_after)
+    WHERE state IN ('queued', 'running', 'retry_wait');
+
+CREATE OR REPLACE VIEW fetch_job_backlog AS
+SELECT id, kind, dedup_key, series_id, state, attempts, max_attempts, retry_after,
+       started_at, finished_at, last_error, created_at, updated_at
+FROM fetch_job
+WHERE state <> 'succeeded'
+ORDER BY state = 'failed' DESC, retry_after;
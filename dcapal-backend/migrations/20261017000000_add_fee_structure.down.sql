@@ -0,0 +1,5 @@
+ALTER TABLE portfolio_asset
+DROP COLUMN IF EXISTS fee_structure;
+
+ALTER TABLE portfolios
+DROP COLUMN IF EXISTS fee_structure;
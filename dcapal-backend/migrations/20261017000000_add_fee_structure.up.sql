@@ -0,0 +1,5 @@
+ALTER TABLE portfolios
+ADD COLUMN IF NOT EXISTS fee_structure JSONB;
+
+ALTER TABLE portfolio_asset
+ADD COLUMN IF NOT EXISTS fee_structure JSONB;
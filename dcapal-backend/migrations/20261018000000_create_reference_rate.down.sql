@@ -0,0 +1,7 @@
+DELETE FROM fetch_job WHERE kind = 'rates';
+
+ALTER TABLE fetch_job DROP CONSTRAINT IF EXISTS ck_fetch_job_kind;
+ALTER TABLE fetch_job ADD CONSTRAINT ck_fetch_job_kind
+    CHECK (kind IN ('discover', 'reprice', 'backfill'));
+
+DROP TABLE IF EXISTS reference_rate_observation;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS reference_rate_observation (
+    rate_type TEXT NOT NULL,
+    observed_on DATE NOT NULL,
+    value NUMERIC NOT NULL,
+    published_at TIMESTAMPTZ NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL,
+    CONSTRAINT pk_reference_rate_observation PRIMARY KEY (rate_type, observed_on),
+    CONSTRAINT ck_reference_rate_observation_rate_type
+        CHECK (rate_type IN ('fed_effr', 'ecb_estr'))
+);
+
+ALTER TABLE fetch_job DROP CONSTRAINT IF EXISTS ck_fetch_job_kind;
+ALTER TABLE fetch_job ADD CONSTRAINT ck_fetch_job_kind
+    CHECK (kind IN ('discover', 'reprice', 'backfill', 'rates'));
@@ -69,6 +69,30 @@ def jwks_environment() -> dict[str, list[dict[str, Any]]]:
     return value
 
 
+def price_chain_environment() -> dict[str, list[str]] | None:
+    """Return the configured per-asset-kind price provider chain, if any.
+
+    ECB reference exchange rates are only used when "ecb" is part of the fiat
+    chain, e.g. {"crypto": ["kraken"], "fiat": ["ecb", "kraken"]}.
+    """
+    raw_value = os.environ.get("DCAPAL_PRICE_CHAIN")
+    if not raw_value:
+        return None
+    try:
+        value = json.loads(raw_value)
+    except json.JSONDecodeError as error:
+        raise SystemExit("DCAPAL_PRICE_CHAIN must contain valid JSON") from error
+
+    if not isinstance(value, dict) or not all(
+        isinstance(chain, list) and all(isinstance(p, str) for p in chain)
+        for chain in value.values()
+    ):
+        raise SystemExit(
+            'DCAPAL_PRICE_CHAIN must be a JSON object such as {"crypto": ["kraken", "yahoo"]}'
+        )
+    return value
+
+
 def render(template: str) -> str:
     """Replace runtime configuration placeholders and reject unknown ones."""
     ip2location_path = os.environ.get("DCAPAL_IP2LOCATION_PATH")
@@ -80,6 +104,7 @@ def render(template: str) -> str:
         "@@DCAPAL_PRICE_PROVIDER@@": json_value(
             os.environ.get("DCAPAL_PRICE_PROVIDER", "kraken")
         ),
+        "@@DCAPAL_PRICE_CHAIN@@": json_value(price_chain_environment()),
         "@@DCAPAL_CW_API_KEY@@": json_value(
             os.environ.get("DCAPAL_CW_API_KEY", "CW_API_KEY")
         ),
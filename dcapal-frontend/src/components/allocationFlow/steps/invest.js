@@ -51,6 +51,7 @@ export const InvestStep = ({
   const totalAmount = pfolio?.totalAmount || 0;
   const assets = pfolio?.assets || {};
   const [solution, setSolution] = useState(null);
+  const [outOfBand, setOutOfBand] = useState([]);
   const [isLoading, setIsLoading] = useState(true);
 
   const handleButtonClick = () => {
@@ -66,7 +67,8 @@ export const InvestStep = ({
       console.debug(`solution=${JSON.stringify(sol, replacer)}`);
       setIsLoading(false);
       if (sol) {
-        setSolution(sol);
+        setSolution(sol.amount);
+        setOutOfBand(sol.outOfBand);
       }
     };
 
@@ -159,6 +161,15 @@ export const InvestStep = ({
             <span>{t("investStep.toInsertAmount")}</span>
           </>
         ) : null}
+        {outOfBand.length > 0 ? (
+          <div className="mt-2">
+            <Trans
+              i18nKey="investStep.assetsOutOfBand"
+              values={{ symbols: outOfBand.join(", ") }}
+              components={[<span className="font-medium" />]}
+            />
+          </div>
+        ) : null}
       </div>
 
       <div className="w-full flex flex-col gap-4 mt-20">
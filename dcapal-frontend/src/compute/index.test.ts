@@ -30,8 +30,8 @@ describe("compute boundary", () => {
     const analyzerRpc = {
       analyzeAndSolve: vi
         .fn()
-        .mockResolvedValueOnce(123.45)
-        .mockResolvedValueOnce(678.9),
+        .mockResolvedValueOnce({ amount: 123.45, outOfBand: [] })
+        .mockResolvedValueOnce({ amount: 678.9, outOfBand: ["SPY"] }),
     };
     spawnMock.mockResolvedValue(analyzerRpc);
 
@@ -47,8 +47,8 @@ describe("compute boundary", () => {
     const result1 = await analyze(assets);
     const result2 = await analyze(assets);
 
-    expect(result1).toBe(123.45);
-    expect(result2).toBe(678.9);
+    expect(result1).toStrictEqual({ amount: 123.45, outOfBand: [] });
+    expect(result2).toStrictEqual({ amount: 678.9, outOfBand: ["SPY"] });
     expect(spawnMock).toHaveBeenCalledTimes(1);
     expect(workerCtorMock).toHaveBeenCalledTimes(1);
     expect(workerCtorMock.mock.calls[0][1]).toStrictEqual({
@@ -109,7 +109,7 @@ describe("compute boundary", () => {
       analyzeAndSolve: vi.fn().mockRejectedValue(new Error("boom")),
     };
     const healthyRpc = {
-      analyzeAndSolve: vi.fn().mockResolvedValue(42),
+      analyzeAndSolve: vi.fn().mockResolvedValue({ amount: 42, outOfBand: [] }),
     };
     spawnMock
       .mockResolvedValueOnce(failingRpc)
@@ -122,7 +122,7 @@ describe("compute boundary", () => {
     const secondResult = await analyze(assets);
 
     expect(firstResult).toBeNull();
-    expect(secondResult).toBe(42);
+    expect(secondResult).toStrictEqual({ amount: 42, outOfBand: [] });
     expect(spawnMock).toHaveBeenCalledTimes(2);
     expect(terminateMock).toHaveBeenCalledTimes(1);
     expect(terminateMock).toHaveBeenCalledWith(failingRpc);
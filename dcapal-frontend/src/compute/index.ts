@@ -1,6 +1,7 @@
 import { spawn, Thread, Worker } from "threads";
 import {
   type AnalyzeRequest,
+  type ComputeAnalyzeSolution,
   type ComputeSolution,
   type SolveAssets,
   type TransactionFeesInput,
@@ -9,7 +10,9 @@ import {
 } from "./types";
 
 interface AnalyzerWorkerRpc {
-  analyzeAndSolve(assets: AnalyzeRequest): Promise<ComputeSolution>;
+  analyzeAndSolve(
+    assets: AnalyzeRequest
+  ): Promise<ComputeAnalyzeSolution | null>;
 }
 
 interface SolverWorkerRpc {
@@ -114,7 +117,7 @@ const runWithWorker = async <TWorker extends WorkerRpc, TResult>(
 
 export const analyze = async (
   assets: AnalyzeRequest
-): Promise<ComputeSolution> => {
+): Promise<ComputeAnalyzeSolution | null> => {
   if (!isAnalyzeRequest(assets)) return null;
 
   try {
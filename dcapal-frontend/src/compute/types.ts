@@ -20,6 +20,7 @@ export interface FeeStructureInput {
 export interface TransactionFeesInput {
   maxFeeImpact?: number | null;
   feeStructure: FeeStructureInput;
+  sellFeeStructure?: FeeStructureInput | null;
 }
 
 export interface SolveAsset {
@@ -42,11 +43,41 @@ export interface SolveRequest {
   useAllBudget: boolean;
 }
 
+export interface ComputeAnalyzeSolution {
+  amount: number;
+  outOfBand: string[];
+}
+
+export interface ComputeAssetExplanation {
+  state: string;
+  reason: string;
+  current_weight: number;
+  weight: number;
+  target_weight: number;
+  drift: number;
+  fees: number;
+}
+
+export interface ComputeSolutionWarning {
+  code: string;
+  symbol?: string;
+  message: string;
+}
+
+export interface ComputeWithdrawal {
+  requested: number;
+  raised: number;
+  shortfall: number;
+}
+
 export interface ComputeSolveSolution {
   amounts?: Map<string, number>;
   shares?: Map<string, number>;
   theo_allocs?: Map<string, number>;
   budget_left?: number;
+  explanations?: Map<string, ComputeAssetExplanation>;
+  warnings?: ComputeSolutionWarning[];
+  withdrawal?: ComputeWithdrawal;
 }
 
 export type ComputeSolution = number | ComputeSolveSolution | null;
@@ -16,12 +16,9 @@ expose({
 
     const input = buildProblemInput(assets);
 
-    const handle = Solver.build_problem(input);
-    const solution = Solver.solve(handle);
-    if (!Solver.delete_problem(handle)) {
-      console.error(`Failed to delete solved problem (handle=${handle})`);
-    }
-
-    return solution;
+    const solution = Solver.solve_once(input);
+    return solution
+      ? { amount: solution.amount, outOfBand: solution.out_of_band ?? [] }
+      : null;
   },
 });
@@ -36,12 +36,6 @@ expose({
       useAllBudget
     );
 
-    const handle = Solver.build_problem(input);
-    const solution = Solver.solve(handle);
-    if (!Solver.delete_problem(handle)) {
-      console.error(`Failed to delete solved problem (handle=${handle})`);
-    }
-
-    return solution;
+    return Solver.solve_once(input);
   },
 });
@@ -241,7 +241,8 @@ export const handlers = [
 
     return HttpResponse.json({
       price,
-      ts: FIXED_TS_SECONDS,
+      observedAt: FIXED_TS_SECONDS,
+      fetchedAt: FIXED_TS_SECONDS,
     });
   }),
 
@@ -4,9 +4,10 @@ extern crate lazy_static;
 use std::{collections::HashMap, sync::Mutex};
 
 use optimize::{
-    FeeStructure, FeeStructureFixed, FeeStructureVariable, TransactionFees,
-    advanced::{self, TheoreticalAllocation},
-    basic,
+    FeeStructure, FeeStructureComposite, FeeStructureFixed, FeeStructureTiered,
+    FeeStructureVariable, FeeTier, OptimizeError, ToleranceBand, TransactionFees,
+    advanced::{self, AllocationReason, SolutionState, TheoreticalAllocation},
+    basic, exact, planner, projection, sweep,
 };
 use rand::{RngExt, distr};
 use rust_decimal::{
@@ -14,7 +15,7 @@ use rust_decimal::{
     prelude::{One, ToPrimitive},
 };
 use serde::{Deserialize, Serialize};
-use utils::{parse_amount, parse_percentage, parse_shares};
+use utils::{parse_amount, parse_fx_rate, parse_percentage, parse_shares};
 use wasm_bindgen::prelude::*;
 
 use crate::optimize::suggestions;
@@ -22,15 +23,28 @@ use crate::optimize::suggestions;
 pub mod optimize;
 mod utils;
 
-const AMOUNT_DECIMALS: u32 = 4;
-const PERCENTAGE_DECIMALS: u32 = 6;
-const SHARES_DECIMALS: u32 = 8;
+/// Decimal places of monetary amounts and prices.
+pub const AMOUNT_DECIMALS: u32 = 4;
+/// Decimal places of weights and rates.
+pub const PERCENTAGE_DECIMALS: u32 = 6;
+/// Decimal places of share quantities.
+pub const SHARES_DECIMALS: u32 = 8;
+/// Decimal places of exchange rates.
+pub const FX_RATE_DECIMALS: u32 = 8;
 
 lazy_static! {
     static ref BASIC_PROBLEMS: Mutex<HashMap<String, optimize::basic::Problem>> =
         Mutex::new(HashMap::new());
     static ref ADVANCED_PROBLEMS: Mutex<HashMap<String, optimize::advanced::Problem>> =
         Mutex::new(HashMap::new());
+    static ref EXACT_PROBLEMS: Mutex<HashMap<String, optimize::exact::Problem>> =
+        Mutex::new(HashMap::new());
+    static ref SWEEP_PROBLEMS: Mutex<HashMap<String, optimize::sweep::Problem>> =
+        Mutex::new(HashMap::new());
+    static ref PLANNER_PROBLEMS: Mutex<HashMap<String, optimize::planner::Problem>> =
+        Mutex::new(HashMap::new());
+    static ref PROJECTION_PROBLEMS: Mutex<HashMap<String, optimize::projection::Problem>> =
+        Mutex::new(HashMap::new());
     static ref SUGGESTION_PROBLEMS: Mutex<HashMap<String, optimize::suggestions::Problem>> =
         Mutex::new(HashMap::new());
     static ref NUMERIC_DIST: distr::Uniform<u8> = distr::Uniform::new_inclusive(0, 9).unwrap();
@@ -62,6 +76,54 @@ impl Solver {
                     kind: ProblemKind::Advanced,
                 })
             }
+            JsProblemOptions::Exact(options) => {
+                let options = exact::ProblemOptions::try_from(options)?;
+                let problem = optimize::exact::Problem::new(options);
+
+                let mut problems = EXACT_PROBLEMS.lock().unwrap();
+                problems.insert(id.clone(), problem);
+
+                Ok(ProblemHandle {
+                    id,
+                    kind: ProblemKind::Exact,
+                })
+            }
+            JsProblemOptions::Sweep(options) => {
+                let options = sweep::ProblemOptions::try_from(options)?;
+                let problem = optimize::sweep::Problem::new(options);
+
+                let mut problems = SWEEP_PROBLEMS.lock().unwrap();
+                problems.insert(id.clone(), problem);
+
+                Ok(ProblemHandle {
+                    id,
+                    kind: ProblemKind::Sweep,
+                })
+            }
+            JsProblemOptions::Planner(options) => {
+                let options = planner::ProblemOptions::try_from(options)?;
+                let problem = optimize::planner::Problem::new(options);
+
+                let mut problems = PLANNER_PROBLEMS.lock().unwrap();
+                problems.insert(id.clone(), problem);
+
+                Ok(ProblemHandle {
+                    id,
+                    kind: ProblemKind::Planner,
+                })
+            }
+            JsProblemOptions::Projection(options) => {
+                let options = projection::ProblemOptions::try_from(options)?;
+                let problem = optimize::projection::Problem::new(options);
+
+                let mut problems = PROJECTION_PROBLEMS.lock().unwrap();
+                problems.insert(id.clone(), problem);
+
+                Ok(ProblemHandle {
+                    id,
+                    kind: ProblemKind::Projection,
+                })
+            }
             JsProblemOptions::Basic(options) => {
                 let options = basic::ProblemOptions::try_from(options)?;
                 let problem = optimize::basic::Problem::new(options);
@@ -93,17 +155,64 @@ impl Solver {
 
         match handle.kind {
             ProblemKind::Advanced => Self::solve_advanced(&handle.id),
+            ProblemKind::Exact => Self::solve_exact(&handle.id),
+            ProblemKind::Sweep => Self::solve_sweep(&handle.id),
+            ProblemKind::Planner => Self::solve_planner(&handle.id),
+            ProblemKind::Projection => Self::solve_projection(&handle.id),
             ProblemKind::Basic => Self::solve_basic(&handle.id),
             ProblemKind::Analyze => Self::suggest_amount_to_invest(&handle.id),
         }
     }
 
+    /// Builds and solves a problem in one call, without registering it.
+    pub fn solve_once(options: JsValue) -> Result<JsValue, JsValue> {
+        utils::require_init();
+
+        let options: JsProblemOptions =
+            serde_wasm_bindgen::from_value(options).map_err(|e| e.to_string())?;
+
+        match options {
+            JsProblemOptions::Advanced(options) => {
+                let options = advanced::ProblemOptions::try_from(options)?;
+                Self::advanced_solution(&options)
+            }
+            JsProblemOptions::Exact(options) => {
+                let options = exact::ProblemOptions::try_from(options)?;
+                Self::exact_solution(&options)
+            }
+            JsProblemOptions::Sweep(options) => {
+                let options = sweep::ProblemOptions::try_from(options)?;
+                Self::sweep_solution(&options)
+            }
+            JsProblemOptions::Planner(options) => {
+                let options = planner::ProblemOptions::try_from(options)?;
+                Self::planner_solution(&options)
+            }
+            JsProblemOptions::Projection(options) => {
+                let options = projection::ProblemOptions::try_from(options)?;
+                Self::projection_solution(&options)
+            }
+            JsProblemOptions::Basic(options) => {
+                let options = basic::ProblemOptions::try_from(options)?;
+                Self::basic_solution(&optimize::basic::Problem::new(options))
+            }
+            JsProblemOptions::Analyze(options) => {
+                let options = suggestions::ProblemOptions::try_from(options)?;
+                Self::suggestion(&optimize::suggestions::Problem::new(options))
+            }
+        }
+    }
+
     fn solve_basic(id: &str) -> Result<JsValue, JsValue> {
         let problems = BASIC_PROBLEMS.lock().unwrap();
         let problem = problems
             .get(id)
             .ok_or_else(|| format!("Invalid problem id {id}"))?;
 
+        Self::basic_solution(problem)
+    }
+
+    fn basic_solution(problem: &basic::Problem) -> Result<JsValue, JsValue> {
         let solution = problem.problem.solve().map_err(|e| e.to_string())?;
         let objective = solution.objective();
         let vars = problem
@@ -128,39 +237,92 @@ impl Solver {
             .get(id)
             .ok_or_else(|| format!("Invalid problem id {id}"))?;
 
-        let solution = problem.solve();
+        Self::advanced_solution(&problem.options)
+    }
 
-        if !solution.is_solved {
-            let js_solution = JsAdvancedSolution::default();
-            return Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap());
-        }
+    fn advanced_solution(options: &advanced::ProblemOptions) -> Result<JsValue, JsValue> {
+        let js_solution = match optimize::solve_advanced(options) {
+            Ok(solution) => JsAdvancedSolution::from(solution),
+            Err(OptimizeError::Unsolved) => JsAdvancedSolution::default(),
+            Err(e) => return Err(e.to_string().into()),
+        };
 
-        let budget_left = solution.budget_left.to_f64().unwrap();
+        Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
+    }
 
-        let amounts = solution
-            .assets
-            .iter()
-            .map(|(aid, v)| (aid.clone(), v.amount.to_f64().unwrap()))
-            .collect();
+    fn solve_exact(id: &str) -> Result<JsValue, JsValue> {
+        let problems = EXACT_PROBLEMS.lock().unwrap();
+        let problem = problems
+            .get(id)
+            .ok_or_else(|| format!("Invalid problem id {id}"))?;
 
-        let shares = solution
-            .assets
-            .iter()
-            .map(|(aid, v)| (aid.clone(), v.shares.to_f64().unwrap()))
-            .collect();
+        Self::exact_solution(&problem.options)
+    }
 
-        let theo_allocs = solution
-            .assets
-            .iter()
-            .filter_map(|(aid, v)| v.theo_alloc.clone().map(|t| (aid.clone(), t.into())))
-            .collect();
+    fn exact_solution(options: &exact::ProblemOptions) -> Result<JsValue, JsValue> {
+        let js_solution = match optimize::solve_exact(options) {
+            Ok(solution) => JsExactSolution::from(solution),
+            Err(OptimizeError::Unsolved) => JsExactSolution::default(),
+            Err(e) => return Err(e.to_string().into()),
+        };
 
-        let js_solution = JsAdvancedSolution {
-            budget_left,
-            amounts,
-            shares,
-            theo_allocs,
+        Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
+    }
+
+    fn solve_sweep(id: &str) -> Result<JsValue, JsValue> {
+        let problems = SWEEP_PROBLEMS.lock().unwrap();
+        let problem = problems
+            .get(id)
+            .ok_or_else(|| format!("Invalid problem id {id}"))?;
+
+        Self::sweep_solution(&problem.options)
+    }
+
+    fn sweep_solution(options: &sweep::ProblemOptions) -> Result<JsValue, JsValue> {
+        let js_solution = match optimize::solve_sweep(options) {
+            Ok(solution) => JsSweepSolution::from(solution),
+            Err(OptimizeError::Unsolved) => JsSweepSolution::default(),
+            Err(e) => return Err(e.to_string().into()),
         };
+
+        Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
+    }
+
+    fn solve_planner(id: &str) -> Result<JsValue, JsValue> {
+        let problems = PLANNER_PROBLEMS.lock().unwrap();
+        let problem = problems
+            .get(id)
+            .ok_or_else(|| format!("Invalid problem id {id}"))?;
+
+        Self::planner_solution(&problem.options)
+    }
+
+    fn planner_solution(options: &planner::ProblemOptions) -> Result<JsValue, JsValue> {
+        let js_solution = match optimize::solve_planner(options) {
+            Ok(solution) => JsPlannerSolution::from(solution),
+            Err(OptimizeError::Unsolved) => JsPlannerSolution::default(),
+            Err(e) => return Err(e.to_string().into()),
+        };
+
+        Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
+    }
+
+    fn solve_projection(id: &str) -> Result<JsValue, JsValue> {
+        let problems = PROJECTION_PROBLEMS.lock().unwrap();
+        let problem = problems
+            .get(id)
+            .ok_or_else(|| format!("Invalid problem id {id}"))?;
+
+        Self::projection_solution(&problem.options)
+    }
+
+    fn projection_solution(options: &projection::ProblemOptions) -> Result<JsValue, JsValue> {
+        let js_solution = match optimize::solve_projection(options) {
+            Ok(solution) => JsProjectionSolution::from(solution),
+            Err(OptimizeError::Unsolved) => JsProjectionSolution::default(),
+            Err(e) => return Err(e.to_string().into()),
+        };
+
         Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
     }
 
@@ -170,9 +332,17 @@ impl Solver {
             .get(id)
             .ok_or_else(|| format!("Invalid problem id {id}"))?;
 
-        let solution = problem.suggest_invest_amount().round_dp(AMOUNT_DECIMALS);
+        Self::suggestion(problem)
+    }
+
+    fn suggestion(problem: &suggestions::Problem) -> Result<JsValue, JsValue> {
+        let amount = problem.suggest_invest_amount().round_dp(AMOUNT_DECIMALS);
 
-        Ok(serde_wasm_bindgen::to_value(&solution).unwrap())
+        let js_solution = JsAnalyzeSolution {
+            amount: amount.to_f64().unwrap(),
+            out_of_band: problem.out_of_band_assets(),
+        };
+        Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
     }
 
     pub fn delete_problem(handle: &ProblemHandle) -> Result<bool, JsValue> {
@@ -180,6 +350,10 @@ impl Solver {
 
         Ok(match handle.kind {
             ProblemKind::Advanced => delete_problem(&ADVANCED_PROBLEMS, &handle.id),
+            ProblemKind::Exact => delete_problem(&EXACT_PROBLEMS, &handle.id),
+            ProblemKind::Sweep => delete_problem(&SWEEP_PROBLEMS, &handle.id),
+            ProblemKind::Planner => delete_problem(&PLANNER_PROBLEMS, &handle.id),
+            ProblemKind::Projection => delete_problem(&PROJECTION_PROBLEMS, &handle.id),
             ProblemKind::Basic => delete_problem(&BASIC_PROBLEMS, &handle.id),
             ProblemKind::Analyze => delete_problem(&SUGGESTION_PROBLEMS, &handle.id),
         })
@@ -195,6 +369,14 @@ pub enum ProblemKind {
     Advanced,
     Basic,
     Analyze,
+    /// Advanced problem solved with integer shares, see [`optimize::exact`]
+    Exact,
+    /// Advanced problem solved across a range of budgets, see [`optimize::sweep`]
+    Sweep,
+    /// Advanced problem planned over several periods, see [`optimize::planner`]
+    Planner,
+    /// Monte Carlo projection of a contribution plan, see [`optimize::projection`]
+    Projection,
 }
 
 #[wasm_bindgen]
@@ -230,6 +412,132 @@ pub struct JsAdvancedSolution {
     pub amounts: HashMap<String, f64>,
     pub shares: HashMap<String, f64>,
     pub theo_allocs: HashMap<String, JsTheoreticalAllocation>,
+    /// Gain realized by the sells of each sold asset
+    pub realized_gains: HashMap<String, f64>,
+    /// Capital-gains tax due by each sold asset
+    pub taxes: HashMap<String, f64>,
+    /// Allocation of each asset group, by group name
+    pub groups: HashMap<String, JsGroupAllocation>,
+    /// FX fees of each traded asset not priced in the portfolio currency
+    pub fx_fees: HashMap<String, f64>,
+    /// Outcome of the allocation of each asset
+    pub explanations: HashMap<String, JsAssetExplanation>,
+    pub warnings: Vec<JsSolutionWarning>,
+    /// Cash raised by the sells, for negative budgets
+    pub withdrawal: Option<JsWithdrawal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsWithdrawal {
+    pub requested: f64,
+    pub raised: f64,
+    pub shortfall: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsAssetExplanation {
+    pub state: SolutionState,
+    pub reason: AllocationReason,
+    pub current_weight: f64,
+    pub weight: f64,
+    pub target_weight: f64,
+    /// Distance of the weight from its target, negative if underweight
+    pub drift: f64,
+    /// Fees charged on the asset trade
+    pub fees: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsSolutionWarning {
+    pub code: String,
+    pub symbol: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsExactSolution {
+    #[serde(flatten)]
+    pub allocation: JsAdvancedSolution,
+    /// Whether the allocation is proven optimal, otherwise it is the greedy one
+    pub is_exact: bool,
+    pub explored_nodes: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsSweepSolution {
+    /// Outcome at each budget swept, by increasing budget
+    pub points: Vec<JsSweepPoint>,
+    /// Smallest budget swept at which each asset is bought
+    pub first_allocations: HashMap<String, f64>,
+    /// Smallest budget swept at which every asset under its target weight has
+    /// been bought
+    pub full_allocation_budget: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsSweepPoint {
+    pub budget: f64,
+    pub max_drift: f64,
+    pub budget_left: f64,
+    pub fees: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsPlannerSolution {
+    pub periods: Vec<JsPlannedPeriod>,
+    pub total_fees: f64,
+    /// Whether every period ends within the drift bound
+    pub is_within_bound: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsPlannedPeriod {
+    pub budget: f64,
+    /// Shares traded by each asset, negative if sold. Deferred assets are missing
+    pub shares: HashMap<String, f64>,
+    /// Amount traded by each asset, negative if sold
+    pub amounts: HashMap<String, f64>,
+    pub fees: f64,
+    /// Cash carried to the next period
+    pub cash_left: f64,
+    pub max_drift: f64,
+    pub is_within_bound: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsProjectionSolution {
+    /// Value percentiles at the start and at the end of every year
+    pub bands: Vec<JsProjectionBand>,
+    /// Share of the paths whose final value reaches the goal
+    pub goal_probability: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsProjectionBand {
+    pub year: u32,
+    pub invested: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsGroupAllocation {
+    pub target_weight: f64,
+    pub current_weight: f64,
+    pub weight: f64,
+    pub drift: f64,
+    pub member_drifts: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct JsAnalyzeSolution {
+    /// Amount to invest to reach target weights without selling
+    pub amount: f64,
+    /// Symbols of the assets out of their tolerance band
+    pub out_of_band: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -239,6 +547,217 @@ pub struct JsTheoreticalAllocation {
     pub fees: f64,
 }
 
+impl From<advanced::Solution> for JsAdvancedSolution {
+    fn from(solution: advanced::Solution) -> Self {
+        let budget_left = solution.budget_left.to_f64().unwrap();
+
+        let amounts = solution
+            .assets
+            .iter()
+            .map(|(aid, v)| (aid.clone(), v.amount.to_f64().unwrap()))
+            .collect();
+
+        let shares = solution
+            .assets
+            .iter()
+            .map(|(aid, v)| (aid.clone(), v.shares.to_f64().unwrap()))
+            .collect();
+
+        let realized_gains = solution
+            .assets
+            .iter()
+            .filter(|(_, v)| v.shares < v.current_shares)
+            .map(|(aid, v)| (aid.clone(), v.realized_gain.to_f64().unwrap()))
+            .collect();
+
+        let taxes = solution
+            .assets
+            .iter()
+            .filter(|(_, v)| v.shares < v.current_shares)
+            .map(|(aid, v)| (aid.clone(), v.tax.to_f64().unwrap()))
+            .collect();
+
+        let fx_fees = solution
+            .assets
+            .iter()
+            .filter(|(_, v)| v.fx.is_some() && v.amount != v.current_amount)
+            .map(|(aid, v)| (aid.clone(), v.fx_fees.to_f64().unwrap()))
+            .collect();
+
+        let groups = solution
+            .groups
+            .into_iter()
+            .map(|(name, g)| (name, g.into()))
+            .collect();
+
+        let explanations = solution
+            .assets
+            .iter()
+            .map(|(aid, v)| (aid.clone(), v.into()))
+            .collect();
+
+        let warnings = solution.warnings.iter().map(Into::into).collect();
+
+        let withdrawal = solution.withdrawal.map(|w| JsWithdrawal {
+            requested: w.requested.to_f64().unwrap(),
+            raised: w.raised.to_f64().unwrap(),
+            shortfall: w.shortfall.to_f64().unwrap(),
+        });
+
+        let theo_allocs = solution
+            .assets
+            .into_iter()
+            .filter_map(|(aid, v)| v.theo_alloc.map(|t| (aid, t.into())))
+            .collect();
+
+        Self {
+            budget_left,
+            amounts,
+            shares,
+            theo_allocs,
+            realized_gains,
+            taxes,
+            groups,
+            fx_fees,
+            explanations,
+            warnings,
+            withdrawal,
+        }
+    }
+}
+
+impl From<&advanced::Asset> for JsAssetExplanation {
+    fn from(asset: &advanced::Asset) -> Self {
+        Self {
+            state: asset.state.clone(),
+            reason: asset.reason(),
+            current_weight: asset.current_weight.to_f64().unwrap(),
+            weight: asset.weight.to_f64().unwrap(),
+            target_weight: asset.target_weight.to_f64().unwrap(),
+            drift: asset.drift().to_f64().unwrap(),
+            fees: asset.trade_fees.to_f64().unwrap(),
+        }
+    }
+}
+
+impl From<&advanced::SolutionWarning> for JsSolutionWarning {
+    fn from(warning: &advanced::SolutionWarning) -> Self {
+        Self {
+            code: warning.code().to_string(),
+            symbol: warning.symbol().map(str::to_string),
+            message: warning.to_string(),
+        }
+    }
+}
+
+impl From<exact::Solution> for JsExactSolution {
+    fn from(solution: exact::Solution) -> Self {
+        Self {
+            allocation: solution.allocation.into(),
+            is_exact: solution.is_exact,
+            explored_nodes: solution.explored_nodes,
+        }
+    }
+}
+
+impl From<sweep::Solution> for JsSweepSolution {
+    fn from(solution: sweep::Solution) -> Self {
+        Self {
+            points: solution
+                .points
+                .into_iter()
+                .map(|p| JsSweepPoint {
+                    budget: p.budget.to_f64().unwrap(),
+                    max_drift: p.max_drift.to_f64().unwrap(),
+                    budget_left: p.budget_left.to_f64().unwrap(),
+                    fees: p.fees.to_f64().unwrap(),
+                })
+                .collect(),
+            first_allocations: solution
+                .first_allocations
+                .into_iter()
+                .map(|(aid, budget)| (aid, budget.to_f64().unwrap()))
+                .collect(),
+            full_allocation_budget: solution
+                .full_allocation_budget
+                .map(|budget| budget.to_f64().unwrap()),
+        }
+    }
+}
+
+impl From<planner::Solution> for JsPlannerSolution {
+    fn from(solution: planner::Solution) -> Self {
+        Self {
+            periods: solution.periods.into_iter().map(Into::into).collect(),
+            total_fees: solution.total_fees.to_f64().unwrap(),
+            is_within_bound: solution.is_within_bound,
+        }
+    }
+}
+
+impl From<planner::PlannedPeriod> for JsPlannedPeriod {
+    fn from(period: planner::PlannedPeriod) -> Self {
+        let shares = period
+            .trades
+            .iter()
+            .map(|(aid, t)| (aid.clone(), t.shares.to_f64().unwrap()))
+            .collect();
+
+        let amounts = period
+            .trades
+            .iter()
+            .map(|(aid, t)| (aid.clone(), t.amount.to_f64().unwrap()))
+            .collect();
+
+        Self {
+            budget: period.budget.to_f64().unwrap(),
+            shares,
+            amounts,
+            fees: period.fees.to_f64().unwrap(),
+            cash_left: period.cash_left.to_f64().unwrap(),
+            max_drift: period.max_drift.to_f64().unwrap(),
+            is_within_bound: period.is_within_bound,
+        }
+    }
+}
+
+impl From<projection::Solution> for JsProjectionSolution {
+    fn from(solution: projection::Solution) -> Self {
+        Self {
+            bands: solution
+                .bands
+                .into_iter()
+                .map(|b| JsProjectionBand {
+                    year: b.year,
+                    invested: b.invested.to_f64().unwrap(),
+                    p5: b.p5.to_f64().unwrap(),
+                    p25: b.p25.to_f64().unwrap(),
+                    p50: b.p50.to_f64().unwrap(),
+                    p75: b.p75.to_f64().unwrap(),
+                    p95: b.p95.to_f64().unwrap(),
+                })
+                .collect(),
+            goal_probability: solution.goal_probability.map(|p| p.to_f64().unwrap()),
+        }
+    }
+}
+
+impl From<advanced::GroupAllocation> for JsGroupAllocation {
+    fn from(value: advanced::GroupAllocation) -> Self {
+        Self {
+            target_weight: value.target_weight.to_f64().unwrap(),
+            current_weight: value.current_weight.to_f64().unwrap(),
+            weight: value.weight.to_f64().unwrap(),
+            drift: value.drift.to_f64().unwrap(),
+            member_drifts: value
+                .member_drifts
+                .into_iter()
+                .map(|(aid, d)| (aid, d.to_f64().unwrap()))
+                .collect(),
+        }
+    }
+}
+
 impl From<TheoreticalAllocation> for JsTheoreticalAllocation {
     fn from(value: TheoreticalAllocation) -> Self {
         Self {
@@ -251,14 +770,20 @@ impl From<TheoreticalAllocation> for JsTheoreticalAllocation {
 
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
 pub enum JsProblemOptions {
     Advanced(JsAdvancedOptions),
+    Exact(JsExactOptions),
+    Sweep(JsSweepOptions),
+    Planner(JsPlannerOptions),
+    Projection(JsProjectionOptions),
     Basic(JsBasicOptions),
     Analyze(JsAnalyzeOptions),
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct JsAdvancedOptions {
+    /// Amount to invest, or to withdraw if negative
     pub budget: f64,
     pub pfolio_ccy: String,
     pub assets: HashMap<String, JsAdvancedAsset>,
@@ -266,11 +791,122 @@ pub struct JsAdvancedOptions {
     pub is_buy_only: bool,
     #[serde(default)]
     pub use_all_budget: bool,
+    #[serde(default)]
+    pub capital_gains_tax: Option<JsCapitalGainsTax>,
+    #[serde(default)]
+    pub tolerance_band: Option<JsToleranceBand>,
+    #[serde(default)]
+    pub groups: Vec<JsTargetGroup>,
+    /// Value of one unit of each currency in the portfolio currency
+    #[serde(default)]
+    pub fx_rates: HashMap<String, f64>,
+    /// Fees charged to convert into each currency
+    #[serde(default)]
+    pub fx_fees: HashMap<String, JsFeeStructure>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsExactOptions {
+    #[serde(flatten)]
+    pub options: JsAdvancedOptions,
+    /// Maximum number of LP relaxations solved before falling back to greedy
+    #[serde(default)]
+    pub max_nodes: Option<u32>,
+    /// Maximum search time in milliseconds before falling back to greedy
+    #[serde(default)]
+    pub max_millis: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsSweepOptions {
+    /// Advanced options, whose budget is the largest one swept
+    #[serde(flatten)]
+    pub options: JsAdvancedOptions,
+    #[serde(default)]
+    pub min_budget: f64,
+    /// Equal intervals between the budgets swept, fewer than 256. Budgets
+    /// are refined adaptively if not set
+    #[serde(default)]
+    pub steps: Option<u32>,
+    /// Narrowest budget interval refined by an adaptive sweep
+    #[serde(default)]
+    pub resolution: Option<f64>,
+    /// Maximum number of budgets solved by an adaptive sweep, at most 256
+    #[serde(default)]
+    pub max_points: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsPlannerOptions {
+    /// Advanced options, whose budget is the contribution of every period
+    #[serde(flatten)]
+    pub options: JsAdvancedOptions,
+    /// Periods planned, at most 120
+    pub periods: u32,
+    /// Largest weight drift tolerated at the end of every period
+    pub max_drift: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsProjectionOptions {
+    pub classes: HashMap<String, JsAssetClass>,
+    pub initial_value: f64,
+    /// Amount contributed at the end of every period
+    pub contribution: f64,
+    pub periods_per_year: u32,
+    pub years: u32,
+    pub paths: u32,
+    /// Seed of the simulated paths. A random one is drawn if not set
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Portfolio value to reach by the end of the projection
+    #[serde(default)]
+    pub goal: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsAssetClass {
+    pub target_weight: f64,
+    /// Mean yearly return, as a rate
+    pub expected_return: f64,
+    /// Standard deviation of the yearly log return
+    pub volatility: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsTargetGroup {
+    pub name: String,
+    pub target_weight: f64,
+    /// Weight of each member within the group, by asset id
+    pub members: HashMap<String, f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsCapitalGainsTax {
+    /// Tax rate applied to realized gains (in [0..1] range)
+    pub rate: f64,
+    #[serde(default)]
+    pub offset_losses: bool,
+    /// Largest tax the rebalancing sells may owe altogether, if any
+    #[serde(default)]
+    pub max_tax: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct JsAnalyzeOptions {
     pub assets: HashMap<String, JsAnalyzeAsset>,
+    #[serde(default)]
+    pub tolerance_band: Option<JsToleranceBand>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct JsToleranceBand {
+    /// Maximum drift of the weight, in weight units (in [0..1] range)
+    #[serde(default)]
+    pub absolute: Option<f64>,
+    /// Maximum drift of the weight, as a rate of the target weight
+    #[serde(default)]
+    pub relative: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -278,9 +914,24 @@ pub struct JsAdvancedAsset {
     pub symbol: String,
     pub shares: f64,
     pub price: f64,
+    /// Ignored when the asset belongs to a group
+    #[serde(default)]
     pub target_weight: f64,
     pub is_whole_shares: bool,
     pub fees: Option<JsTransactionFees>,
+    #[serde(default)]
+    pub quantity_step: Option<f64>,
+    #[serde(default)]
+    pub min_quantity: Option<f64>,
+    #[serde(default)]
+    pub min_order_amount: Option<f64>,
+    #[serde(default)]
+    pub average_buy_price: Option<f64>,
+    #[serde(default)]
+    pub tolerance_band: Option<JsToleranceBand>,
+    /// Currency of `price` and `average_buy_price`, if other than the portfolio one
+    #[serde(default)]
+    pub price_ccy: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -290,6 +941,8 @@ pub struct JsAnalyzeAsset {
     pub price: f64,
     pub target_weight: f64,
     pub is_whole_shares: bool,
+    #[serde(default)]
+    pub tolerance_band: Option<JsToleranceBand>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -298,6 +951,9 @@ pub struct JsTransactionFees {
     /// Maximum acceptable fee impact (as a rate, in [0..1] range)
     pub max_fee_impact: Option<f64>,
     pub fee_structure: JsFeeStructure,
+    /// Fee structure charged on sells, if different from `fee_structure`
+    #[serde(default)]
+    pub sell_fee_structure: Option<JsFeeStructure>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -306,6 +962,8 @@ pub enum JsFeeStructure {
     ZeroFee,
     Fixed(JsFeeStructureFixed),
     Variable(JsFeeStructureVariable),
+    Tiered(JsFeeStructureTiered),
+    Composite(JsFeeStructureComposite),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -322,63 +980,161 @@ pub struct JsFeeStructureVariable {
     pub fee_rate: Option<f64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsFeeStructureTiered {
+    pub tiers: Vec<JsFeeTier>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsFeeTier {
+    /// Inclusive upper bound of the order amounts in this tier, if any
+    pub up_to: Option<f64>,
+    pub fee_structure: JsFeeStructure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsFeeStructureComposite {
+    pub components: Vec<JsFeeStructure>,
+}
+
 impl TryFrom<JsAdvancedOptions> for advanced::ProblemOptions {
     type Error = String;
 
     fn try_from(options: JsAdvancedOptions) -> Result<Self, Self::Error> {
-        if options.budget < 0. {
-            return Err(format!(
-                "Invalid budget ({}). Must be positive",
-                options.budget
-            ));
-        }
+        let options = advanced::ProblemOptions {
+            pfolio_ccy: options.pfolio_ccy,
+            current_pfolio_amount: Decimal::ZERO,
+            assets: options
+                .assets
+                .into_iter()
+                .map(|(aid, a)| (aid, a.into()))
+                .collect(),
+            budget: parse_amount(options.budget),
+            fees: options.fees.map(Into::into).unwrap_or_default(),
+            is_buy_only: options.is_buy_only,
+            use_all_budget: options.use_all_budget,
+            capital_gains_tax: options
+                .capital_gains_tax
+                .map(|t| advanced::CapitalGainsTax {
+                    rate: parse_percentage(t.rate),
+                    offset_losses: t.offset_losses,
+                    max_tax: t.max_tax.map(parse_amount),
+                }),
+            tolerance_band: options.tolerance_band.map(Into::into),
+            groups: options
+                .groups
+                .into_iter()
+                .map(|g| advanced::TargetGroup {
+                    name: g.name,
+                    target_weight: parse_percentage(g.target_weight),
+                    members: g
+                        .members
+                        .into_iter()
+                        .map(|(aid, w)| (aid, parse_percentage(w)))
+                        .collect(),
+                })
+                .collect(),
+            fx_rates: options
+                .fx_rates
+                .into_iter()
+                .map(|(ccy, rate)| (ccy, parse_fx_rate(rate)))
+                .collect(),
+            fx_fees: options
+                .fx_fees
+                .into_iter()
+                .map(|(ccy, fees)| (ccy, fees.into()))
+                .collect(),
+        };
 
-        let assets = options
-            .assets
-            .into_iter()
-            .map(|(aid, a)| advanced::ProblemAsset::try_from(a).map(|a| (aid, a)))
-            .collect::<Result<HashMap<_, _>, _>>()?;
+        options.validated().map_err(|e| e.to_string())
+    }
+}
 
-        let target_total = assets
-            .values()
-            .map(|a| a.target_weight)
-            .sum::<Decimal>()
-            .round_dp(AMOUNT_DECIMALS);
+impl TryFrom<JsExactOptions> for exact::ProblemOptions {
+    type Error = String;
 
-        if target_total != Decimal::one() {
-            return Err(format!(
-                "Invalid target weights. Sum must be equal to 1 ({target_total} instead)"
-            ));
-        }
+    fn try_from(options: JsExactOptions) -> Result<Self, Self::Error> {
+        let defaults = exact::SearchLimits::default();
+        let options = exact::ProblemOptions {
+            advanced: options.options.try_into()?,
+            limits: exact::SearchLimits {
+                max_nodes: options.max_nodes.unwrap_or(defaults.max_nodes),
+                max_millis: options.max_millis.or(defaults.max_millis),
+            },
+        };
 
-        let budget = parse_amount(options.budget);
-        let current_total = assets
-            .values()
-            .map(|a| a.price * a.shares)
-            .sum::<Decimal>()
-            .round_dp(AMOUNT_DECIMALS);
+        options.validated().map_err(|e| e.to_string())
+    }
+}
 
-        if budget + current_total <= Decimal::ZERO {
-            return Err(format!(
-                "Invalid input. Budget + portfolio value must be positive. (budget={budget} portfolio_value={current_total})"
-            ));
-        }
+impl TryFrom<JsSweepOptions> for sweep::ProblemOptions {
+    type Error = String;
 
-        let fees = options
-            .fees
-            .map(TransactionFees::try_from)
-            .transpose()?
-            .unwrap_or_default();
+    fn try_from(options: JsSweepOptions) -> Result<Self, Self::Error> {
+        let sampling = match options.steps {
+            Some(steps) => sweep::Sampling::Grid { steps },
+            None => sweep::Sampling::Adaptive {
+                resolution: options.resolution.map(parse_amount),
+                max_points: options.max_points.unwrap_or(sweep::DEFAULT_MAX_POINTS),
+            },
+        };
 
-        Ok(advanced::ProblemOptions {
-            pfolio_ccy: options.pfolio_ccy,
-            current_pfolio_amount: current_total,
-            assets,
-            budget,
-            fees,
-            is_buy_only: options.is_buy_only,
-            use_all_budget: options.use_all_budget,
-        })
+        let options = sweep::ProblemOptions {
+            advanced: options.options.try_into()?,
+            min_budget: parse_amount(options.min_budget),
+            sampling,
+        };
+
+        options.validated().map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<JsPlannerOptions> for planner::ProblemOptions {
+    type Error = String;
+
+    fn try_from(options: JsPlannerOptions) -> Result<Self, Self::Error> {
+        let options = planner::ProblemOptions {
+            advanced: options.options.try_into()?,
+            periods: options.periods,
+            max_drift: parse_percentage(options.max_drift),
+        };
+
+        options.validated().map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<JsProjectionOptions> for projection::ProblemOptions {
+    type Error = String;
+
+    fn try_from(options: JsProjectionOptions) -> Result<Self, Self::Error> {
+        let options = projection::ProblemOptions {
+            classes: options
+                .classes
+                .into_iter()
+                .map(|(id, c)| {
+                    let class = projection::AssetClass {
+                        target_weight: parse_percentage(c.target_weight),
+                        returns: projection::ReturnModel {
+                            expected_return: parse_percentage(c.expected_return),
+                            volatility: parse_percentage(c.volatility),
+                        },
+                    };
+                    (id, class)
+                })
+                .collect(),
+            initial_value: parse_amount(options.initial_value),
+            contribution: parse_amount(options.contribution),
+            periods_per_year: options.periods_per_year,
+            years: options.years,
+            paths: options.paths,
+            seed: options.seed.unwrap_or_else(|| rand::rng().random()),
+            goal: options.goal.map(parse_amount),
+        };
+
+        options.validated().map_err(|e| e.to_string())
     }
 }
 
@@ -401,14 +1157,13 @@ impl TryFrom<JsAnalyzeOptions> for suggestions::ProblemOptions {
         Ok(suggestions::ProblemOptions {
             current_pfolio_amount: current_total,
             assets,
+            tolerance_band: validated_band(options.tolerance_band)?,
         })
     }
 }
 
-impl TryFrom<JsAdvancedAsset> for advanced::ProblemAsset {
-    type Error = String;
-
-    fn try_from(asset: JsAdvancedAsset) -> Result<Self, Self::Error> {
+impl From<JsAdvancedAsset> for advanced::ProblemAsset {
+    fn from(asset: JsAdvancedAsset) -> Self {
         let JsAdvancedAsset {
             symbol,
             shares,
@@ -416,50 +1171,28 @@ impl TryFrom<JsAdvancedAsset> for advanced::ProblemAsset {
             target_weight,
             is_whole_shares,
             fees,
+            quantity_step,
+            min_quantity,
+            min_order_amount,
+            average_buy_price,
+            tolerance_band,
+            price_ccy,
         } = asset;
 
-        if symbol.is_empty() {
-            return Err("Invalid symbol. Must not be empty".to_string());
-        }
-
-        if shares < 0. {
-            return Err(format!(
-                "Invalid shares ({shares}). Must be zero or positive"
-            ));
-        }
-
-        if price < 0. {
-            return Err(format!("Invalid price ({price}). Must be zero or positive"));
-        }
-
-        if target_weight < 0. {
-            return Err(format!(
-                "Invalid target weight ({target_weight}). Must be zero or positive"
-            ));
-        }
-
-        if target_weight > 1. {
-            return Err(format!(
-                "Invalid target weight ({target_weight}). Must be less then or equal to 1."
-            ));
-        }
-
-        let shares = if is_whole_shares {
-            shares.trunc()
-        } else {
-            shares
-        };
-
-        let fees = fees.map(TransactionFees::try_from).transpose()?;
-
-        Ok(advanced::ProblemAsset {
+        advanced::ProblemAsset {
             symbol,
             shares: parse_shares(shares),
             price: parse_amount(price),
             target_weight: parse_percentage(target_weight),
             is_whole_shares,
-            fees,
-        })
+            fees: fees.map(Into::into),
+            quantity_step: quantity_step.map(parse_shares),
+            min_quantity: min_quantity.map(parse_shares),
+            min_order_amount: min_order_amount.map(parse_amount),
+            average_buy_price: average_buy_price.map(parse_amount),
+            tolerance_band: tolerance_band.map(Into::into),
+            price_ccy,
+        }
     }
 }
 
@@ -473,6 +1206,7 @@ impl TryFrom<JsAnalyzeAsset> for suggestions::ProblemAsset {
             price,
             target_weight,
             is_whole_shares,
+            tolerance_band,
         } = asset;
 
         if symbol.is_empty() {
@@ -513,84 +1247,97 @@ impl TryFrom<JsAnalyzeAsset> for suggestions::ProblemAsset {
             price: parse_amount(price),
             target_weight: parse_percentage(target_weight),
             is_whole_shares,
+            tolerance_band: validated_band(tolerance_band)?,
         })
     }
 }
 
-impl TryFrom<JsTransactionFees> for TransactionFees {
-    type Error = String;
-
-    fn try_from(value: JsTransactionFees) -> Result<Self, Self::Error> {
-        if let Some(max) = value.max_fee_impact
-            && !(0.0..=1.0).contains(&max)
-        {
-            return Err(format!(
-                "Invalid max_fee_impact ({max}). Must be in [0, 1] range"
-            ));
+impl From<JsToleranceBand> for ToleranceBand {
+    fn from(value: JsToleranceBand) -> Self {
+        Self {
+            absolute: value.absolute.map(parse_percentage),
+            relative: value.relative.map(parse_percentage),
         }
+    }
+}
+
+fn validated_band(band: Option<JsToleranceBand>) -> Result<Option<ToleranceBand>, String> {
+    band.map(|b| {
+        ToleranceBand::from(b)
+            .validated()
+            .map_err(|e| e.to_string())
+    })
+    .transpose()
+}
 
+impl From<JsTransactionFees> for TransactionFees {
+    fn from(value: JsTransactionFees) -> Self {
         let max_fee_impact = value
             .max_fee_impact
             .map(parse_percentage)
             .unwrap_or_else(TransactionFees::default_max_fee_impact);
 
-        Ok(Self {
+        Self {
             max_fee_impact,
-            fee_structure: value.fee_structure.try_into()?,
-        })
+            fee_structure: value.fee_structure.into(),
+            sell_fee_structure: value.sell_fee_structure.map(Into::into),
+        }
     }
 }
 
-impl TryFrom<JsFeeStructure> for FeeStructure {
-    type Error = String;
-
-    fn try_from(value: JsFeeStructure) -> Result<Self, Self::Error> {
-        Ok(match value {
+impl From<JsFeeStructure> for FeeStructure {
+    fn from(value: JsFeeStructure) -> Self {
+        match value {
             JsFeeStructure::ZeroFee => Self::default(),
-            JsFeeStructure::Fixed(fee) => FeeStructure::Fixed(fee.try_into()?),
-            JsFeeStructure::Variable(fee) => FeeStructure::Variable(fee.try_into()?),
-        })
+            JsFeeStructure::Fixed(fee) => FeeStructure::Fixed(fee.into()),
+            JsFeeStructure::Variable(fee) => FeeStructure::Variable(fee.into()),
+            JsFeeStructure::Tiered(fee) => FeeStructure::Tiered(fee.into()),
+            JsFeeStructure::Composite(fee) => FeeStructure::Composite(fee.into()),
+        }
     }
 }
 
-impl TryFrom<JsFeeStructureFixed> for FeeStructureFixed {
-    type Error = String;
-
-    fn try_from(value: JsFeeStructureFixed) -> Result<Self, Self::Error> {
-        if let Some(amount) = value.fee_amount
-            && amount < 0.
-        {
-            return Err(format!("Invalid fee_amount ({amount}). Must be positive"));
+impl From<JsFeeStructureFixed> for FeeStructureFixed {
+    fn from(value: JsFeeStructureFixed) -> Self {
+        Self {
+            fee_amount: value.fee_amount.map(parse_amount).unwrap_or(Decimal::ZERO),
         }
-
-        let fee_amount = value.fee_amount.map(parse_amount).unwrap_or(Decimal::ZERO);
-
-        Ok(Self { fee_amount })
     }
 }
 
-impl TryFrom<JsFeeStructureVariable> for FeeStructureVariable {
-    type Error = String;
-
-    fn try_from(value: JsFeeStructureVariable) -> Result<Self, Self::Error> {
-        if let Some(rate) = value.fee_rate
-            && !(0.0..=1.0).contains(&rate)
-        {
-            return Err(format!(
-                "Invalid fee_rate ({rate}). Must be in [0, 1] range"
-            ));
+impl From<JsFeeStructureVariable> for FeeStructureVariable {
+    fn from(value: JsFeeStructureVariable) -> Self {
+        Self {
+            min_fee: value.min_fee.map(parse_amount),
+            max_fee: value.max_fee.map(parse_amount),
+            fee_rate: value
+                .fee_rate
+                .map(parse_percentage)
+                .unwrap_or(Decimal::ZERO),
         }
+    }
+}
 
-        let fee_rate = value
-            .fee_rate
-            .map(parse_percentage)
-            .unwrap_or(Decimal::ZERO);
+impl From<JsFeeStructureTiered> for FeeStructureTiered {
+    fn from(value: JsFeeStructureTiered) -> Self {
+        Self {
+            tiers: value
+                .tiers
+                .into_iter()
+                .map(|t| FeeTier {
+                    up_to: t.up_to.map(parse_amount),
+                    fee_structure: t.fee_structure.into(),
+                })
+                .collect(),
+        }
+    }
+}
 
-        Ok(Self {
-            min_fee: value.min_fee.map(parse_amount),
-            max_fee: value.max_fee.map(parse_amount),
-            fee_rate,
-        })
+impl From<JsFeeStructureComposite> for FeeStructureComposite {
+    fn from(value: JsFeeStructureComposite) -> Self {
+        Self {
+            components: value.components.into_iter().map(Into::into).collect(),
+        }
     }
 }
 
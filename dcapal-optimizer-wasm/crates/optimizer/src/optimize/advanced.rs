@@ -1,34 +1,476 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use log::debug;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
-use super::TransactionFees;
-use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS, SHARES_DECIMALS};
+use super::{FeeStructure, OptimizeError, ToleranceBand, TransactionFees};
+use crate::{AMOUNT_DECIMALS, FX_RATE_DECIMALS, PERCENTAGE_DECIMALS, SHARES_DECIMALS};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProblemOptions {
     pub pfolio_ccy: String,
+    /// Current portfolio value. Derived from `assets` by [`ProblemOptions::validated`].
+    #[serde(default)]
     pub current_pfolio_amount: Decimal,
     pub assets: HashMap<String, ProblemAsset>,
+    /// Amount to invest, or to withdraw by selling assets if negative.
     pub budget: Decimal,
+    #[serde(default)]
     pub fees: TransactionFees,
     pub is_buy_only: bool,
+    #[serde(default)]
     pub use_all_budget: bool,
+    /// Tax due on the gains realized by selling over allocated assets.
+    #[serde(default)]
+    pub capital_gains_tax: Option<CapitalGainsTax>,
+    /// Drift tolerated around target weights, unless overridden by an asset.
+    #[serde(default)]
+    pub tolerance_band: Option<ToleranceBand>,
+    /// Asset groups with their own target weights. When set, asset target
+    /// weights are derived from the group and intra-group weights.
+    #[serde(default)]
+    pub groups: Vec<TargetGroup>,
+    /// Exchange rates into `pfolio_ccy`, by currency: how much portfolio
+    /// currency one unit of the currency is worth.
+    #[serde(default)]
+    pub fx_rates: HashMap<String, Decimal>,
+    /// Fees charged to convert into a currency, by currency. They add up to
+    /// the transaction fees of every trade of the assets priced in it.
+    #[serde(default)]
+    pub fx_fees: HashMap<String, FeeStructure>,
 }
 
-#[derive(Debug, Clone)]
+impl ProblemOptions {
+    /// Checks the problem is well formed and normalizes it for solving:
+    /// values are rounded to the optimizer precision, whole-share holdings
+    /// are truncated and the current portfolio value is recomputed.
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        let mut assets = self
+            .assets
+            .iter()
+            .map(|(aid, a)| a.validated().map(|a| (aid.clone(), a)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let groups = validated_groups(&self.groups, &mut assets)?;
+
+        let mut fx_rates = HashMap::with_capacity(self.fx_rates.len());
+        for (ccy, rate) in &self.fx_rates {
+            if *rate <= Decimal::ZERO {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid FX rate for {ccy} ({rate}). Must be positive"
+                )));
+            }
+            fx_rates.insert(ccy.to_lowercase(), rate.round_dp(FX_RATE_DECIMALS));
+        }
+
+        let fx_fees = self
+            .fx_fees
+            .iter()
+            .map(|(ccy, fees)| fees.validated().map(|fees| (ccy.to_lowercase(), fees)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        for asset in assets.values_mut() {
+            let Some(ccy) = asset.price_ccy.take() else {
+                continue;
+            };
+
+            if ccy.eq_ignore_ascii_case(&self.pfolio_ccy) {
+                continue;
+            }
+
+            if !fx_rates.contains_key(&ccy) {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Missing FX rate for {} price currency ({ccy})",
+                    asset.symbol
+                )));
+            }
+            asset.price_ccy = Some(ccy);
+        }
+
+        let target_total = assets
+            .values()
+            .map(|a| a.target_weight)
+            .sum::<Decimal>()
+            .round_dp(AMOUNT_DECIMALS);
+
+        if target_total != Decimal::ONE {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid target weights. Sum must be equal to 1 ({target_total} instead)"
+            )));
+        }
+
+        let budget = self.budget.round_dp(AMOUNT_DECIMALS);
+        let current_total = assets
+            .values()
+            .map(|a| {
+                let rate = a
+                    .price_ccy
+                    .as_ref()
+                    .map_or(Decimal::ONE, |ccy| fx_rates[ccy]);
+                convert(a.price, rate) * a.shares
+            })
+            .sum::<Decimal>()
+            .round_dp(AMOUNT_DECIMALS);
+
+        if budget + current_total <= Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid input. Budget + portfolio value must be positive. (budget={budget} portfolio_value={current_total})"
+            )));
+        }
+
+        Ok(Self {
+            pfolio_ccy: self.pfolio_ccy.clone(),
+            current_pfolio_amount: current_total,
+            assets,
+            budget,
+            fees: self.fees.validated()?,
+            is_buy_only: self.is_buy_only,
+            use_all_budget: self.use_all_budget,
+            capital_gains_tax: self
+                .capital_gains_tax
+                .as_ref()
+                .map(CapitalGainsTax::validated)
+                .transpose()?,
+            tolerance_band: self
+                .tolerance_band
+                .as_ref()
+                .map(ToleranceBand::validated)
+                .transpose()?,
+            groups,
+            fx_rates,
+            fx_fees,
+        })
+    }
+
+    /// Conversion of `asset` prices into the portfolio currency, if priced in
+    /// another currency. Expects validated options.
+    pub fn fx_conversion(&self, asset: &ProblemAsset) -> Option<FxConversion> {
+        let ccy = asset.price_ccy.as_ref()?;
+        Some(FxConversion {
+            ccy: ccy.clone(),
+            rate: self.fx_rates[ccy],
+            fee_structure: self.fx_fees.get(ccy).cloned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FxConversion {
+    pub ccy: String,
+    pub rate: Decimal,
+    pub fee_structure: Option<FeeStructure>,
+}
+
+impl FxConversion {
+    pub fn convert(&self, amount: Decimal) -> Decimal {
+        convert(amount, self.rate)
+    }
+
+    pub fn compute_fee(&self, amount: &Decimal) -> Decimal {
+        self.fee_structure
+            .as_ref()
+            .map_or(Decimal::ZERO, |fees| fees.compute_fee(amount))
+    }
+}
+
+fn convert(amount: Decimal, rate: Decimal) -> Decimal {
+    (amount * rate).round_dp(AMOUNT_DECIMALS)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetGroup {
+    pub name: String,
+    /// Target weight of the group in the portfolio
+    pub target_weight: Decimal,
+    /// Target weight of each member within the group, by asset id
+    pub members: HashMap<String, Decimal>,
+}
+
+/// Checks groups partition the assets with weights summing to 1 at both
+/// levels, and sets each asset target weight to its share of the portfolio.
+fn validated_groups(
+    groups: &[TargetGroup],
+    assets: &mut HashMap<String, ProblemAsset>,
+) -> Result<Vec<TargetGroup>, OptimizeError> {
+    if groups.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let check_weights = |weights: &mut dyn Iterator<Item = &Decimal>, what: &str| {
+        let mut total = Decimal::ZERO;
+        for w in weights {
+            if !(Decimal::ZERO..=Decimal::ONE).contains(w) {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid {what} weight ({w}). Must be in [0, 1] range"
+                )));
+            }
+            total += w.round_dp(PERCENTAGE_DECIMALS);
+        }
+
+        let total = total.round_dp(AMOUNT_DECIMALS);
+        if total != Decimal::ONE {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid {what} weights. Sum must be equal to 1 ({total} instead)"
+            )));
+        }
+
+        Ok(())
+    };
+
+    check_weights(&mut groups.iter().map(|g| &g.target_weight), "group")?;
+
+    let mut validated = Vec::with_capacity(groups.len());
+    let mut grouped = HashMap::new();
+    for group in groups {
+        if group.name.is_empty() {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid group name. Must not be empty".to_string(),
+            ));
+        }
+
+        if validated.iter().any(|g: &TargetGroup| g.name == group.name) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid group ({}). Names must be unique",
+                group.name
+            )));
+        }
+
+        check_weights(&mut group.members.values(), "member")?;
+
+        let group_weight = group.target_weight.round_dp(PERCENTAGE_DECIMALS);
+        for (aid, member_weight) in &group.members {
+            let Some(asset) = assets.get_mut(aid) else {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid group ({}). Unknown member asset {aid}",
+                    group.name
+                )));
+            };
+
+            if let Some(other) = grouped.insert(aid.clone(), group.name.clone()) {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid groups. Asset {aid} belongs to both {other} and {}",
+                    group.name
+                )));
+            }
+
+            asset.target_weight = (group_weight * member_weight).round_dp(PERCENTAGE_DECIMALS);
+        }
+
+        validated.push(TargetGroup {
+            name: group.name.clone(),
+            target_weight: group_weight,
+            members: group
+                .members
+                .iter()
+                .map(|(aid, w)| (aid.clone(), w.round_dp(PERCENTAGE_DECIMALS)))
+                .collect(),
+        });
+    }
+
+    if let Some(aid) = assets.keys().find(|aid| !grouped.contains_key(*aid)) {
+        return Err(OptimizeError::InvalidInput(format!(
+            "Invalid groups. Asset {aid} does not belong to any group"
+        )));
+    }
+
+    Ok(validated)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapitalGainsTax {
+    /// Tax rate applied to realized gains (in [0..1] range)
+    pub rate: Decimal,
+    /// Whether losses realized by a sell reduce the taxable gains of the others
+    #[serde(default)]
+    pub offset_losses: bool,
+    /// Largest tax the rebalancing sells may owe altogether. Overweight assets
+    /// are sold by increasing gain ratio until it is spent, the last one only
+    /// partially. Unbounded if missing
+    #[serde(default)]
+    pub max_tax: Option<Decimal>,
+}
+
+impl CapitalGainsTax {
+    fn validated(&self) -> Result<Self, OptimizeError> {
+        if !(Decimal::ZERO..=Decimal::ONE).contains(&self.rate) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid capital gains tax rate ({}). Must be in [0, 1] range",
+                self.rate
+            )));
+        }
+
+        if let Some(max_tax) = self.max_tax
+            && max_tax < Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid maximum capital gains tax ({max_tax}). Must be non-negative"
+            )));
+        }
+
+        Ok(Self {
+            rate: self.rate.round_dp(PERCENTAGE_DECIMALS),
+            offset_losses: self.offset_losses,
+            max_tax: self.max_tax.map(|t| t.round_dp(AMOUNT_DECIMALS)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProblemAsset {
     pub symbol: String,
     pub shares: Decimal,
     pub price: Decimal,
     pub target_weight: Decimal,
+    #[serde(default)]
     pub is_whole_shares: bool,
     pub fees: Option<TransactionFees>,
+    /// Smallest tradable increment of shares, e.g. `0.001` for a market with
+    /// three lot decimals. Whole-share assets trade in steps of at least 1.
+    #[serde(default)]
+    pub quantity_step: Option<Decimal>,
+    /// Minimum number of shares accepted by a single order.
+    #[serde(default)]
+    pub min_quantity: Option<Decimal>,
+    /// Minimum order value accepted by a single order, in portfolio currency.
+    #[serde(default)]
+    pub min_order_amount: Option<Decimal>,
+    /// Average price paid for the shares held, used as cost basis of sells.
+    /// Without it, sells are assumed to realize no gain.
+    #[serde(default)]
+    pub average_buy_price: Option<Decimal>,
+    /// Drift tolerated around the target weight, overriding the portfolio one.
+    #[serde(default)]
+    pub tolerance_band: Option<ToleranceBand>,
+    /// Currency of `price` and `average_buy_price`, if other than the
+    /// portfolio one. Converted at the rate given in [`ProblemOptions::fx_rates`].
+    #[serde(default)]
+    pub price_ccy: Option<String>,
+}
+
+impl ProblemAsset {
+    fn validated(&self) -> Result<Self, OptimizeError> {
+        let ProblemAsset {
+            symbol,
+            shares,
+            price,
+            target_weight,
+            is_whole_shares,
+            fees,
+            quantity_step,
+            min_quantity,
+            min_order_amount,
+            average_buy_price,
+            tolerance_band,
+            price_ccy,
+        } = self;
+
+        if symbol.is_empty() {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid symbol. Must not be empty".to_string(),
+            ));
+        }
+
+        if *shares < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid shares ({shares}). Must be zero or positive"
+            )));
+        }
+
+        if *price < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid price ({price}). Must be zero or positive"
+            )));
+        }
+
+        if *target_weight < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid target weight ({target_weight}). Must be zero or positive"
+            )));
+        }
+
+        if *target_weight > Decimal::ONE {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid target weight ({target_weight}). Must be less then or equal to 1."
+            )));
+        }
+
+        let quantity_step = quantity_step.map(|step| step.round_dp(SHARES_DECIMALS));
+        if let Some(step) = quantity_step
+            && step <= Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid quantity step ({step}). Must be positive"
+            )));
+        }
+
+        if let Some(min) = min_quantity
+            && *min < Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid minimum quantity ({min}). Must be zero or positive"
+            )));
+        }
+
+        if let Some(min) = min_order_amount
+            && *min < Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid minimum order amount ({min}). Must be zero or positive"
+            )));
+        }
+
+        if let Some(avg) = average_buy_price
+            && *avg < Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid average buy price ({avg}). Must be zero or positive"
+            )));
+        }
+
+        if price_ccy.as_ref().is_some_and(String::is_empty) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid price currency for {symbol}. Must not be empty"
+            )));
+        }
+
+        let shares = if *is_whole_shares {
+            shares.trunc()
+        } else {
+            *shares
+        };
+
+        Ok(ProblemAsset {
+            symbol: symbol.clone(),
+            shares: shares.round_dp(SHARES_DECIMALS),
+            price: price.round_dp(AMOUNT_DECIMALS),
+            target_weight: target_weight.round_dp(PERCENTAGE_DECIMALS),
+            is_whole_shares: *is_whole_shares,
+            fees: fees.as_ref().map(TransactionFees::validated).transpose()?,
+            quantity_step,
+            min_quantity: min_quantity.map(|min| min.round_dp(SHARES_DECIMALS)),
+            min_order_amount: min_order_amount.map(|min| min.round_dp(AMOUNT_DECIMALS)),
+            average_buy_price: average_buy_price.map(|avg| avg.round_dp(AMOUNT_DECIMALS)),
+            tolerance_band: tolerance_band
+                .as_ref()
+                .map(ToleranceBand::validated)
+                .transpose()?,
+            price_ccy: price_ccy.as_ref().map(|ccy| ccy.to_lowercase()),
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Asset {
     pub symbol: String,
     pub price: Decimal,
@@ -38,22 +480,44 @@ pub struct Asset {
     pub target_amount: Decimal,
     pub target_weight: Decimal,
     pub is_whole_shares: bool,
+    pub quantity_step: Option<Decimal>,
+    pub min_quantity: Option<Decimal>,
+    pub min_order_amount: Option<Decimal>,
+    pub average_buy_price: Option<Decimal>,
     pub shares: Decimal,
     pub amount: Decimal,
     pub weight: Decimal,
     pub fees: Option<TransactionFees>,
+    /// Fees charged on the shares sold to rebalance the asset.
+    pub sell_fees: Decimal,
+    /// Gain (or loss, if negative) realized by the shares sold.
+    pub realized_gain: Decimal,
+    /// Capital-gains tax due on the realized gain.
+    pub tax: Decimal,
     pub theo_alloc: Option<TheoreticalAllocation>,
+    /// Whether the current weight is within the tolerance band of the target.
+    /// In-band assets are only topped up with their share of the budget.
+    pub is_in_band: bool,
+    /// Conversion of the asset prices, if not in the portfolio currency.
+    /// `price` and `average_buy_price` are already converted.
+    pub fx: Option<FxConversion>,
+    /// FX fees of the asset trade, included in its transaction fees.
+    pub fx_fees: Decimal,
+    /// Fees charged on the asset trade, whether bought or sold.
+    pub trade_fees: Decimal,
     pub state: SolutionState,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TheoreticalAllocation {
     pub shares: Decimal,
     pub amount: Decimal,
     pub fees: Decimal,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum SolutionState {
     Open,
     DoNotAllocate,
@@ -61,10 +525,92 @@ pub enum SolutionState {
     PriceTooHigh,
     FeesTooHigh,
     TieBreaker,
+    /// The order needed to reach the target is smaller than the asset minimum quantity.
+    MinQuantityNotMet,
+    /// The order needed to reach the target is worth less than the asset minimum order amount.
+    MinOrderAmountNotMet,
+    /// The sell needed to reach the target would owe more capital-gains tax than the budget left.
+    TaxTooHigh,
+}
+
+/// Why an asset ended up with its allocation, for display to users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AllocationReason {
+    /// The asset reached its target amount.
+    TargetReached,
+    /// The asset was above its target and got sold down to it.
+    Sold,
+    /// The asset was already at or above its target.
+    AlreadyAllocated,
+    /// The budget ran out before the asset reached its target.
+    BudgetExhausted,
+    /// One more unit of the asset costs more than the budget left.
+    PriceTooHigh,
+    /// The fees of the order exceed the maximum fee impact.
+    FeesTooHigh,
+    /// The capital-gains tax of the sell exceeds the tax budget left.
+    TaxTooHigh,
+    /// The order is smaller than the asset minimum quantity.
+    MinQuantityNotMet,
+    /// The order is worth less than the asset minimum order amount.
+    MinOrderAmountNotMet,
+}
+
+/// Portfolio-level issue worth pointing out alongside a solution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "camelCase")]
+pub enum SolutionWarning {
+    /// The whole budget is not enough to buy one unit of an underweight asset.
+    #[serde(rename_all = "camelCase")]
+    BudgetBelowUnitPrice { symbol: String, unit_price: Decimal },
+    /// The exact search could not run or complete: the greedy allocation is
+    /// returned instead.
+    GreedyFallback { reason: String },
+}
+
+impl SolutionWarning {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SolutionWarning::BudgetBelowUnitPrice { .. } => "budgetBelowUnitPrice",
+            SolutionWarning::GreedyFallback { .. } => "greedyFallback",
+        }
+    }
+
+    /// Asset the warning is about, if any.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            SolutionWarning::BudgetBelowUnitPrice { symbol, .. } => Some(symbol),
+            SolutionWarning::GreedyFallback { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SolutionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolutionWarning::BudgetBelowUnitPrice { symbol, unit_price } => write!(
+                f,
+                "Budget too small to buy one unit of {symbol} ({unit_price})"
+            ),
+            SolutionWarning::GreedyFallback { reason } => {
+                write!(
+                    f,
+                    "Exact allocation unavailable ({reason}), greedy one returned"
+                )
+            }
+        }
+    }
 }
 
 impl Asset {
-    pub fn new(asset: ProblemAsset, current_pfolio_amount: Decimal, budget: Decimal) -> Self {
+    pub fn new(
+        asset: ProblemAsset,
+        fx: Option<FxConversion>,
+        current_pfolio_amount: Decimal,
+        budget: Decimal,
+        pfolio_band: Option<&ToleranceBand>,
+    ) -> Self {
         let ProblemAsset {
             symbol,
             shares,
@@ -72,8 +618,19 @@ impl Asset {
             target_weight,
             is_whole_shares,
             fees,
+            quantity_step,
+            min_quantity,
+            min_order_amount,
+            average_buy_price,
+            tolerance_band,
+            price_ccy: _,
         } = asset;
 
+        let (price, average_buy_price) = match &fx {
+            Some(fx) => (fx.convert(price), average_buy_price.map(|p| fx.convert(p))),
+            None => (price, average_buy_price),
+        };
+
         let pfolio_amount = current_pfolio_amount + budget;
 
         let current_shares = shares;
@@ -83,7 +640,15 @@ impl Asset {
         } else {
             Decimal::ZERO
         };
-        let target_amount = (target_weight * pfolio_amount).round_dp(AMOUNT_DECIMALS);
+        let is_in_band = tolerance_band
+            .as_ref()
+            .or(pfolio_band)
+            .is_some_and(|band| band.contains(current_weight, target_weight));
+        let target_amount = if is_in_band {
+            current_amount + (target_weight * budget).round_dp(AMOUNT_DECIMALS)
+        } else {
+            (target_weight * pfolio_amount).round_dp(AMOUNT_DECIMALS)
+        };
         let amount = current_amount;
         let weight = current_weight;
 
@@ -96,21 +661,52 @@ impl Asset {
             target_amount,
             target_weight,
             is_whole_shares,
+            quantity_step,
+            min_quantity,
+            min_order_amount,
+            average_buy_price,
             shares,
             amount,
             weight,
             fees,
+            sell_fees: Decimal::ZERO,
+            realized_gain: Decimal::ZERO,
+            tax: Decimal::ZERO,
             theo_alloc: None,
+            is_in_band,
+            fx,
+            fx_fees: Decimal::ZERO,
+            trade_fees: Decimal::ZERO,
             state: SolutionState::Open,
         }
     }
 
     pub fn compute_fee(&self, amount: &Decimal, general_fees: &TransactionFees) -> Decimal {
-        if let Some(ref fee) = self.fees {
+        let fee = if let Some(ref fee) = self.fees {
             fee.fee_structure.compute_fee(amount)
         } else {
             general_fees.fee_structure.compute_fee(amount)
-        }
+        };
+
+        fee + self.compute_fx_fee(amount)
+    }
+
+    pub fn compute_sell_fee(&self, amount: &Decimal, general_fees: &TransactionFees) -> Decimal {
+        let fee = self
+            .fees
+            .as_ref()
+            .unwrap_or(general_fees)
+            .sell_fee_structure()
+            .compute_fee(amount);
+
+        fee + self.compute_fx_fee(amount)
+    }
+
+    /// FX fees charged to convert `amount`, zero for portfolio currency assets.
+    pub fn compute_fx_fee(&self, amount: &Decimal) -> Decimal {
+        self.fx
+            .as_ref()
+            .map_or(Decimal::ZERO, |fx| fx.compute_fee(amount))
     }
 
     pub fn get_max_fee_impact(&self, general_fees: &TransactionFees) -> Decimal {
@@ -124,55 +720,283 @@ impl Asset {
     pub fn get_allocated_amount(&self) -> Decimal {
         self.amount - self.current_amount
     }
+
+    /// Distance of the weight from its target, negative if underweight.
+    pub fn drift(&self) -> Decimal {
+        self.weight - self.target_weight
+    }
+
+    /// Reason of the asset allocation, derived from its final state.
+    pub fn reason(&self) -> AllocationReason {
+        match self.state {
+            SolutionState::FullyAllocated => AllocationReason::TargetReached,
+            SolutionState::DoNotAllocate if self.shares < self.current_shares => {
+                AllocationReason::Sold
+            }
+            SolutionState::DoNotAllocate => AllocationReason::AlreadyAllocated,
+            SolutionState::Open | SolutionState::TieBreaker => AllocationReason::BudgetExhausted,
+            SolutionState::PriceTooHigh => AllocationReason::PriceTooHigh,
+            SolutionState::FeesTooHigh => AllocationReason::FeesTooHigh,
+            SolutionState::TaxTooHigh => AllocationReason::TaxTooHigh,
+            SolutionState::MinQuantityNotMet => AllocationReason::MinQuantityNotMet,
+            SolutionState::MinOrderAmountNotMet => AllocationReason::MinOrderAmountNotMet,
+        }
+    }
+
+    /// Smallest tradable increment of shares, if the asset is not freely divisible.
+    pub fn lot_size(&self) -> Option<Decimal> {
+        match (self.is_whole_shares, self.quantity_step) {
+            (true, Some(step)) => Some(step.ceil().max(Decimal::ONE)),
+            (true, None) => Some(Decimal::ONE),
+            (false, step) => step,
+        }
+    }
+
+    /// Returns the state blocking an order of `shares` (bought or sold), if
+    /// it breaks one of the asset minimum order rules.
+    pub fn check_order_rules(&self, shares: Decimal) -> Option<SolutionState> {
+        let shares = shares.abs();
+        if shares.is_zero() {
+            return None;
+        }
+
+        if self.min_quantity.is_some_and(|min| shares < min) {
+            return Some(SolutionState::MinQuantityNotMet);
+        }
+
+        let amount = (shares * self.price).round_dp(AMOUNT_DECIMALS);
+        if self.min_order_amount.is_some_and(|min| amount < min) {
+            return Some(SolutionState::MinOrderAmountNotMet);
+        }
+
+        None
+    }
 }
 
 pub struct Problem {
     pub(crate) options: ProblemOptions,
+    /// Assets neither bought nor sold, by asset id.
+    pub(crate) frozen: HashSet<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Solution {
     pub is_solved: bool,
     pub assets: HashMap<String, Asset>,
     pub budget_left: Decimal,
+    /// Allocation of each asset group, by group name
+    #[serde(default)]
+    pub groups: HashMap<String, GroupAllocation>,
+    #[serde(default)]
+    pub warnings: Vec<SolutionWarning>,
+    /// Cash raised by the sells, for negative budgets
+    #[serde(default)]
+    pub withdrawal: Option<Withdrawal>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    pub requested: Decimal,
+    /// Cash raised, net of sell fees and capital-gains tax
+    pub raised: Decimal,
+    /// Part of the requested amount the sells could not raise
+    pub shortfall: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupAllocation {
+    pub target_weight: Decimal,
+    pub current_weight: Decimal,
+    pub weight: Decimal,
+    /// Distance of the group weight from its target, negative if underweight
+    pub drift: Decimal,
+    /// Distance of each member weight within the group from its intra-group
+    /// target, by asset id
+    pub member_drifts: HashMap<String, Decimal>,
 }
 
 impl Solution {
     pub fn new(options: ProblemOptions) -> Self {
         let (pfolio_amount, budget) = (options.current_pfolio_amount, options.budget);
+        let band = options.tolerance_band.as_ref();
 
-        let assets = options
+        let mut assets = options
             .assets
-            .into_iter()
-            .map(|(aid, asset)| (aid, Asset::new(asset, pfolio_amount, budget)))
+            .iter()
+            .map(|(aid, asset)| {
+                let fx = options.fx_conversion(asset);
+                let asset = Asset::new(asset.clone(), fx, pfolio_amount, budget, band);
+                (aid.clone(), asset)
+            })
             .collect::<HashMap<_, _>>();
 
+        // Selling reaches group targets anyway: only buys need to favor
+        // underweight groups over the underweight members of other groups
+        if options.is_buy_only && budget >= Decimal::ZERO {
+            apply_group_targets(&mut assets, &options.groups, pfolio_amount, budget);
+        }
+
         Self {
             is_solved: false,
             assets,
             budget_left: Decimal::ZERO,
+            groups: HashMap::new(),
+            warnings: Vec::new(),
+            withdrawal: None,
+        }
+    }
+}
+
+/// Caps asset targets so that the budget first goes to the groups below
+/// their target, then to the members below their target within each group.
+fn apply_group_targets(
+    assets: &mut HashMap<String, Asset>,
+    groups: &[TargetGroup],
+    current_pfolio_amount: Decimal,
+    budget: Decimal,
+) {
+    let pfolio_amount = current_pfolio_amount + budget;
+    let current_amount = |g: &TargetGroup, assets: &HashMap<String, Asset>| {
+        g.members
+            .keys()
+            .map(|aid| assets[aid].current_amount)
+            .sum::<Decimal>()
+    };
+
+    let group_gaps = groups
+        .iter()
+        .map(|g| g.target_weight * pfolio_amount - current_amount(g, assets))
+        .collect::<Vec<_>>();
+    let group_budgets = split_by_gap(budget, &group_gaps);
+
+    for (group, group_budget) in groups.iter().zip(group_budgets) {
+        let group_amount = current_amount(group, assets) + group_budget;
+
+        // In-band members keep their proportional top-up
+        let members = group
+            .members
+            .iter()
+            .filter(|(aid, _)| !assets[*aid].is_in_band)
+            .collect::<Vec<_>>();
+        let member_gaps = members
+            .iter()
+            .map(|(aid, w)| *w * group_amount - assets[*aid].current_amount)
+            .collect::<Vec<_>>();
+        let member_budgets = split_by_gap(group_budget, &member_gaps);
+
+        for ((aid, _), member_budget) in members.into_iter().zip(member_budgets) {
+            let asset = assets.get_mut(aid).unwrap();
+            asset.target_amount = (asset.current_amount + member_budget).round_dp(AMOUNT_DECIMALS);
         }
     }
 }
 
+/// Splits `budget` proportionally to the positive gaps, never exceeding a gap.
+fn split_by_gap(budget: Decimal, gaps: &[Decimal]) -> Vec<Decimal> {
+    let total_gap = gaps.iter().map(|g| g.max(&Decimal::ZERO)).sum::<Decimal>();
+    if total_gap <= Decimal::ZERO {
+        return vec![Decimal::ZERO; gaps.len()];
+    }
+
+    let budget = budget.min(total_gap);
+    gaps.iter()
+        .map(|g| (*g).max(Decimal::ZERO) * budget / total_gap)
+        .collect()
+}
+
+/// Reports how far groups and their members ended from their targets.
+fn group_allocations(
+    assets: &HashMap<String, Asset>,
+    groups: &[TargetGroup],
+    current_pfolio_amount: Decimal,
+    pfolio_amount: Decimal,
+) -> HashMap<String, GroupAllocation> {
+    let weight_of = |amount: Decimal, total: Decimal| {
+        if total > Decimal::ZERO {
+            (amount / total).round_dp(PERCENTAGE_DECIMALS)
+        } else {
+            Decimal::ZERO
+        }
+    };
+
+    groups
+        .iter()
+        .map(|g| {
+            let members = g.members.keys().map(|aid| &assets[aid]);
+            let current_amount = members.clone().map(|a| a.current_amount).sum::<Decimal>();
+            let amount = members.map(|a| a.amount).sum::<Decimal>();
+            let weight = weight_of(amount, pfolio_amount);
+
+            let member_drifts = g
+                .members
+                .iter()
+                .map(|(aid, w)| (aid.clone(), weight_of(assets[aid].amount, amount) - w))
+                .collect();
+
+            let allocation = GroupAllocation {
+                target_weight: g.target_weight,
+                current_weight: weight_of(current_amount, current_pfolio_amount),
+                weight,
+                drift: weight - g.target_weight,
+                member_drifts,
+            };
+            (g.name.clone(), allocation)
+        })
+        .collect()
+}
+
 impl Problem {
     pub fn new(options: ProblemOptions) -> Self {
-        Self { options }
+        Self::with_frozen(options, HashSet::new())
+    }
+
+    /// Problem whose `frozen` assets keep their current shares.
+    pub(crate) fn with_frozen(options: ProblemOptions, frozen: HashSet<String>) -> Self {
+        Self { options, frozen }
     }
 
     pub fn solve(&self) -> Solution {
-        debug!("[Options] {:?}", self.options);
+        debug!("[Options] {:?} frozen={:?}", self.options, self.frozen);
 
         let mut solution = Solution::new(self.options.clone());
+        for aid in &self.frozen {
+            if let Some(asset) = solution.assets.get_mut(aid) {
+                asset.state = SolutionState::DoNotAllocate;
+            }
+        }
 
         // New portfolio amount
         let pfolio_amount = self.options.current_pfolio_amount + self.options.budget;
 
+        if self.options.budget < Decimal::ZERO {
+            let withdrawal = withdraw_from_over_allocated_assets(
+                &mut solution,
+                -self.options.budget,
+                &self.options.fees,
+                self.options.capital_gains_tax.as_ref(),
+            );
+
+            debug!("[Withdrawal] withdrawal={withdrawal:?}");
+
+            // Lot rounding may raise more than requested: keep it as cash
+            let budget_left = (withdrawal.raised - withdrawal.requested).max(Decimal::ZERO);
+            solution.withdrawal = Some(withdrawal);
+            return self.reconcile(solution, pfolio_amount, budget_left, Decimal::ZERO);
+        }
+
         let sold_amount = if self.options.is_buy_only {
             close_fully_allocated_assets(&mut solution.assets);
             Decimal::ZERO
         } else {
-            sell_over_allocated_assets(&mut solution, pfolio_amount)
+            sell_over_allocated_assets(
+                &mut solution,
+                pfolio_amount,
+                &self.options.fees,
+                self.options.capital_gains_tax.as_ref(),
+            )
         };
 
         // Budget available to allocate
@@ -194,11 +1018,39 @@ impl Problem {
             Self::exhaust_budget_left(&mut solution.assets, &mut budget_left, pfolio_amount);
         }
 
-        // Reconcile solution weights
+        let available_budget = self.options.budget + sold_amount;
+        self.reconcile(solution, pfolio_amount, budget_left, available_budget)
+    }
+
+    /// Finalizes the solution weights, trade costs and reports.
+    pub(super) fn reconcile(
+        &self,
+        mut solution: Solution,
+        pfolio_amount: Decimal,
+        budget_left: Decimal,
+        available_budget: Decimal,
+    ) -> Solution {
+        // Reconcile solution weights and trade costs
         for asset in solution.assets.values_mut() {
             asset.weight = (asset.amount / pfolio_amount).round_dp(PERCENTAGE_DECIMALS);
+
+            let allocated = asset.get_allocated_amount();
+            asset.fx_fees = asset.compute_fx_fee(&allocated.abs());
+            asset.trade_fees = if allocated > Decimal::ZERO {
+                asset.compute_fee(&allocated, &self.options.fees)
+            } else {
+                asset.sell_fees
+            };
         }
 
+        solution.warnings = unaffordable_asset_warnings(&solution.assets, available_budget);
+
+        solution.groups = group_allocations(
+            &solution.assets,
+            &self.options.groups,
+            self.options.current_pfolio_amount,
+            pfolio_amount,
+        );
         solution.is_solved = true;
         solution.budget_left = budget_left;
 
@@ -270,12 +1122,10 @@ impl Problem {
                 let was_negligible = is_negligible(budget_left);
                 *budget_left = budget_left_next + freed_budget;
                 if was_negligible && is_negligible(budget_left) {
-                    // Check fee impact for open assets
+                    // Check fee impact and order rules for open assets
                     freed_budget = Decimal::ZERO;
                     for asset in &mut open_assets {
-                        if is_fee_impact_too_high(asset, pfolio_fees) {
-                            freed_budget += deallocate_asset_fee_too_high(asset, pfolio_fees);
-                        }
+                        freed_budget += reject_unacceptable_order(asset, pfolio_fees);
                     }
 
                     *budget_left += freed_budget;
@@ -327,6 +1177,9 @@ impl Problem {
             SolutionState::DoNotAllocate,
             SolutionState::FeesTooHigh,
             SolutionState::PriceTooHigh,
+            SolutionState::MinQuantityNotMet,
+            SolutionState::MinOrderAmountNotMet,
+            SolutionState::TaxTooHigh,
         ];
 
         // For starters, allocate remaining budget to under-allocated assets,
@@ -349,7 +1202,11 @@ impl Problem {
             let distance = Decimal::min(asset.target_amount - asset.amount, *budget_left);
 
             let allocated_shares = shares_to_allocate(asset, distance);
-            if allocated_shares == Decimal::ZERO {
+            if allocated_shares == Decimal::ZERO
+                || asset
+                    .check_order_rules(asset.shares + allocated_shares - asset.current_shares)
+                    .is_some()
+            {
                 continue;
             }
 
@@ -381,7 +1238,11 @@ impl Problem {
         for (i, asset) in candidates.iter_mut().enumerate() {
             let w_i = adjusted_weights[i];
             let allocated_shares = shares_to_allocate(asset, w_i * (*budget_left));
-            if allocated_shares == Decimal::ZERO {
+            if allocated_shares == Decimal::ZERO
+                || asset
+                    .check_order_rules(asset.shares + allocated_shares - asset.current_shares)
+                    .is_some()
+            {
                 continue;
             }
 
@@ -407,7 +1268,11 @@ impl Problem {
         debug!("[Exhaust/3] Before: budget_left={budget_left} candidates={candidates:?}");
         for asset in &mut candidates {
             let allocated_shares = shares_to_allocate(asset, *budget_left);
-            if allocated_shares == Decimal::ZERO {
+            if allocated_shares == Decimal::ZERO
+                || asset
+                    .check_order_rules(asset.shares + allocated_shares - asset.current_shares)
+                    .is_some()
+            {
                 continue;
             }
 
@@ -421,6 +1286,27 @@ impl Problem {
     }
 }
 
+/// Warns about underweight assets whose unit price exceeds the whole budget.
+fn unaffordable_asset_warnings(
+    assets: &HashMap<String, Asset>,
+    budget: Decimal,
+) -> Vec<SolutionWarning> {
+    let mut warnings = assets
+        .values()
+        .filter(|a| a.state == SolutionState::PriceTooHigh)
+        .filter_map(|a| {
+            let unit_price = (a.lot_size()? * a.price).round_dp(AMOUNT_DECIMALS);
+            (unit_price > budget).then(|| SolutionWarning::BudgetBelowUnitPrice {
+                symbol: a.symbol.clone(),
+                unit_price,
+            })
+        })
+        .collect::<Vec<_>>();
+    warnings.sort_by(|a, b| a.symbol().cmp(&b.symbol()));
+
+    warnings
+}
+
 fn is_negligible(budget: &Decimal) -> bool {
     *budget < dec!(0.01)
 }
@@ -436,7 +1322,7 @@ fn refresh_open_assets<'a>(
             a.state == SolutionState::Open
                 || a.state == SolutionState::TieBreaker
                 || (a.state == SolutionState::PriceTooHigh
-                    && (!a.is_whole_shares || a.price <= *budget_left))
+                    && a.lot_size().is_none_or(|lot| lot * a.price <= *budget_left))
         })
         .map(|a| {
             a.state = SolutionState::Open;
@@ -454,39 +1340,388 @@ fn close_fully_allocated_assets(assets: &mut HashMap<String, Asset>) {
     }
 }
 
-fn sell_over_allocated_assets(solution: &mut Solution, pfolio_amount: Decimal) -> Decimal {
-    let mut sold_amount = Decimal::ZERO;
-    for asset in solution.assets.values_mut() {
-        if asset.current_amount <= asset.target_amount {
-            continue;
-        }
+/// Sells the excess of over allocated assets. Returns the amount freed by
+/// the sales, net of their fees and capital-gains tax.
+///
+/// Assets are sold by increasing gain per unit of value, so that losses are
+/// realized first and can offset the gains of the following sells.
+fn sell_over_allocated_assets(
+    solution: &mut Solution,
+    pfolio_amount: Decimal,
+    general_fees: &TransactionFees,
+    capital_gains_tax: Option<&CapitalGainsTax>,
+) -> Decimal {
+    let mut over_allocated = solution
+        .assets
+        .values_mut()
+        .filter(|a| a.state != SolutionState::DoNotAllocate && a.current_amount > a.target_amount)
+        .collect::<Vec<_>>();
+    over_allocated.sort_by(|a, b| {
+        gain_ratio(a)
+            .cmp(&gain_ratio(b))
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
 
+    let mut sold_amount = Decimal::ZERO;
+    let mut gains = RealizedGains::new(capital_gains_tax);
+    let mut tax_left = capital_gains_tax.and_then(|t| t.max_tax);
+    for asset in over_allocated {
         asset.state = SolutionState::DoNotAllocate;
 
         let overallocated = asset.current_amount - asset.target_amount;
-        let sell_shares = shares_to_allocate(asset, overallocated);
-        if sell_shares.is_zero() {
+        let mut sell_shares = shares_to_allocate(asset, overallocated);
+
+        // Sell only as much as the tax budget left allows
+        if let Some(tax_left) = tax_left
+            && gains.assess(asset, sell_shares).1 > tax_left
+        {
+            let taxed_shares = shares_within(asset, gains.shares_taxed_within(asset, tax_left));
+            if taxed_shares.is_zero() {
+                debug!(
+                    "[Rebalance] Tax budget spent before selling over allocated asset: {asset:?} (tax_left={tax_left})"
+                );
+                let sell_amount = (sell_shares * asset.price).round_dp(AMOUNT_DECIMALS);
+                let sell_fees = asset.compute_sell_fee(&sell_amount, general_fees);
+                reject_sell(
+                    asset,
+                    sell_shares,
+                    sell_amount,
+                    sell_fees,
+                    SolutionState::TaxTooHigh,
+                );
+                continue;
+            }
+
+            sell_shares = sell_shares.min(taxed_shares);
+        }
+        if sell_shares.is_zero() || asset.check_order_rules(sell_shares).is_some() {
             debug!(
                 "[Rebalance] Cannot sell over allocated asset: {asset:?} (overallocated={overallocated}, sell_shares={sell_shares})"
             );
-            continue; // If cannot sell a single share, do nothing -- Better slightly overbalanced
+            continue; // If cannot sell a single lot, do nothing -- Better slightly overbalanced
         }
 
         let sell_amount = (sell_shares * asset.price).round_dp(AMOUNT_DECIMALS);
+        if sell_amount.is_zero() {
+            debug!(
+                "[Rebalance] Sell of over allocated asset rounds to nothing: {asset:?} (sell_shares={sell_shares})"
+            );
+            continue;
+        }
+
+        let sell_fees = asset.compute_sell_fee(&sell_amount, general_fees);
+        let max_fee_impact = asset.get_max_fee_impact(general_fees);
+        let fees_impact = (sell_fees / sell_amount).round_dp(PERCENTAGE_DECIMALS);
+        if sell_fees >= sell_amount || fees_impact > max_fee_impact {
+            debug!(
+                "[Rebalance] Fee impact too high to sell over allocated asset: {asset:?} (sell_amount={sell_amount}, sell_fees={sell_fees})"
+            );
+            reject_sell(
+                asset,
+                sell_shares,
+                sell_amount,
+                sell_fees,
+                SolutionState::FeesTooHigh,
+            );
+            continue;
+        }
+
+        let (realized_gain, tax) = gains.assess(asset, sell_shares);
+        if sell_fees + tax >= sell_amount || tax_left.is_some_and(|left| tax > left) {
+            debug!(
+                "[Rebalance] Tax too high to sell over allocated asset: {asset:?} (sell_amount={sell_amount}, realized_gain={realized_gain}, tax={tax})"
+            );
+            reject_sell(
+                asset,
+                sell_shares,
+                sell_amount,
+                sell_fees,
+                SolutionState::TaxTooHigh,
+            );
+            continue;
+        }
+
+        gains.record(realized_gain);
+        if let Some(left) = tax_left.as_mut() {
+            *left -= tax;
+        }
 
         // Update solution values
         asset.amount -= sell_amount;
         asset.shares -= sell_shares;
         asset.weight = (asset.amount / pfolio_amount).round_dp(PERCENTAGE_DECIMALS);
-        sold_amount += sell_amount;
+        asset.sell_fees = sell_fees;
+        asset.realized_gain = realized_gain;
+        asset.tax = tax;
+        sold_amount += sell_amount - sell_fees - tax;
     }
 
     sold_amount.round_dp(AMOUNT_DECIMALS)
 }
 
-/// Get a view over under allocated assets i.e. assets with `current_weight`
-/// less than `target_weight`
-fn under_allocated_view(assets: &mut HashMap<String, Asset>) -> Vec<&mut Asset> {
+/// Most refinements of the gross amount a withdrawal sell must raise to cover
+/// its own costs. Costs grow with the amount sold, so the refinements converge.
+const MAX_GROSS_UP_STEPS: usize = 16;
+
+/// Sells assets to raise `requested` cash, net of sell fees and capital-gains
+/// tax. The most overweight assets are sold first, down to their target, then
+/// further if the sells still fall short.
+fn withdraw_from_over_allocated_assets(
+    solution: &mut Solution,
+    requested: Decimal,
+    general_fees: &TransactionFees,
+    capital_gains_tax: Option<&CapitalGainsTax>,
+) -> Withdrawal {
+    let mut order = solution
+        .assets
+        .iter()
+        .filter(|(_, a)| a.state != SolutionState::DoNotAllocate)
+        .map(|(aid, _)| aid.clone())
+        .collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let (a, b) = (&solution.assets[a], &solution.assets[b]);
+        (b.current_amount - b.target_amount)
+            .cmp(&(a.current_amount - a.target_amount))
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+
+    // Assets left untouched keep no budget to buy with
+    close_fully_allocated_assets(&mut solution.assets);
+
+    let mut sells = HashMap::<String, Decimal>::new();
+    let mut blocked = HashMap::<String, SolutionState>::new();
+
+    // First down to targets, then below them for what is still missing
+    for is_capped in [true, false] {
+        for aid in &order {
+            let raised = settle_sells(solution, &order, &sells, general_fees, capital_gains_tax);
+            let remaining = requested - raised;
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+
+            let asset = &solution.assets[aid];
+            if blocked.contains_key(aid) || asset.price.is_zero() {
+                continue;
+            }
+
+            let sold = sells.get(aid).copied().unwrap_or(Decimal::ZERO);
+            let sold_amount = (sold * asset.price).round_dp(AMOUNT_DECIMALS);
+            let cap = if is_capped {
+                (asset.current_amount - asset.target_amount).max(Decimal::ZERO)
+            } else {
+                asset.current_amount
+            };
+
+            // Gross amount netting the remaining cash after its own costs, up
+            // to the most the asset can sell
+            let mut gross = (sold_amount + remaining).min(cap);
+            for _ in 0..MAX_GROSS_UP_STEPS {
+                let costs = sell_costs(asset, gross, general_fees, capital_gains_tax);
+                let next = (sold_amount + remaining + costs)
+                    .min(cap)
+                    .round_dp(AMOUNT_DECIMALS);
+                if next == gross {
+                    break;
+                }
+                gross = next;
+            }
+
+            let shares = shares_to_sell(asset, gross);
+            if shares <= sold {
+                continue;
+            }
+
+            if let Some(state) = asset.check_order_rules(shares) {
+                blocked.insert(aid.clone(), state);
+                continue;
+            }
+
+            let sell_amount = (shares * asset.price).round_dp(AMOUNT_DECIMALS);
+            if sell_amount.is_zero() {
+                continue;
+            }
+
+            let sell_fees = asset.compute_sell_fee(&sell_amount, general_fees);
+            let fees_impact = (sell_fees / sell_amount).round_dp(PERCENTAGE_DECIMALS);
+            if sell_fees >= sell_amount || fees_impact > asset.get_max_fee_impact(general_fees) {
+                blocked.insert(aid.clone(), SolutionState::FeesTooHigh);
+                continue;
+            }
+
+            sells.insert(aid.clone(), shares);
+        }
+    }
+
+    let raised = settle_sells(solution, &order, &sells, general_fees, capital_gains_tax);
+    for (aid, state) in blocked {
+        let asset = solution.assets.get_mut(&aid).unwrap();
+        if asset.shares == asset.current_shares {
+            asset.state = state;
+        }
+    }
+
+    Withdrawal {
+        requested,
+        raised,
+        shortfall: (requested - raised).max(Decimal::ZERO),
+    }
+}
+
+/// Applies the planned `sells` from scratch, realizing capital losses in
+/// `order`. Only the assets sold are settled. Returns the cash they raise net
+/// of fees and tax.
+fn settle_sells(
+    solution: &mut Solution,
+    order: &[String],
+    sells: &HashMap<String, Decimal>,
+    general_fees: &TransactionFees,
+    capital_gains_tax: Option<&CapitalGainsTax>,
+) -> Decimal {
+    let mut raised = Decimal::ZERO;
+    let mut gains = RealizedGains::new(capital_gains_tax);
+    for aid in order {
+        let asset = solution.assets.get_mut(aid).unwrap();
+        let sell_shares = sells.get(aid).copied().unwrap_or(Decimal::ZERO);
+        let sell_amount = (sell_shares * asset.price).round_dp(AMOUNT_DECIMALS);
+        let sell_fees = asset.compute_sell_fee(&sell_amount, general_fees);
+
+        let (realized_gain, tax) = gains.assess(asset, sell_shares);
+        gains.record(realized_gain);
+
+        asset.shares = asset.current_shares - sell_shares;
+        asset.amount = asset.current_amount - sell_amount;
+        asset.sell_fees = sell_fees;
+        asset.realized_gain = realized_gain;
+        asset.tax = tax;
+        if sell_shares > Decimal::ZERO {
+            asset.state = SolutionState::DoNotAllocate;
+        }
+        raised += sell_amount - sell_fees - tax;
+    }
+
+    raised.round_dp(AMOUNT_DECIMALS)
+}
+
+/// Capital gains realized by a sequence of sells, carrying the realized
+/// losses forward to offset the gains of the following sells when allowed.
+struct RealizedGains<'a> {
+    tax: Option<&'a CapitalGainsTax>,
+    losses: Decimal,
+}
+
+impl<'a> RealizedGains<'a> {
+    fn new(tax: Option<&'a CapitalGainsTax>) -> Self {
+        Self {
+            tax,
+            losses: Decimal::ZERO,
+        }
+    }
+
+    /// Gain realized by selling `shares` of `asset` at its average buy price
+    /// basis, and the tax due on it net of the losses carried forward.
+    fn assess(&self, asset: &Asset, shares: Decimal) -> (Decimal, Decimal) {
+        let basis = asset.average_buy_price.unwrap_or(asset.price);
+        let realized_gain = (shares * (asset.price - basis)).round_dp(AMOUNT_DECIMALS);
+        let tax = self.tax.map_or(Decimal::ZERO, |t| {
+            let taxable = if t.offset_losses {
+                realized_gain - self.losses
+            } else {
+                realized_gain
+            };
+            (taxable.max(Decimal::ZERO) * t.rate).round_dp(AMOUNT_DECIMALS)
+        });
+
+        (realized_gain, tax)
+    }
+
+    /// Most shares of `asset` whose sell owes at most `max_tax`, net of the
+    /// losses carried forward. All of them if their sell realizes no gain.
+    fn shares_taxed_within(&self, asset: &Asset, max_tax: Decimal) -> Decimal {
+        let basis = asset.average_buy_price.unwrap_or(asset.price);
+        let gain_per_share = asset.price - basis;
+        match self.tax {
+            Some(t) if t.rate > Decimal::ZERO && gain_per_share > Decimal::ZERO => {
+                let offset = if t.offset_losses {
+                    self.losses
+                } else {
+                    Decimal::ZERO
+                };
+                ((max_tax / t.rate + offset) / gain_per_share).min(asset.current_shares)
+            }
+            _ => asset.current_shares,
+        }
+    }
+
+    /// Records the gain of an executed sell.
+    fn record(&mut self, realized_gain: Decimal) {
+        if self.tax.is_some_and(|t| t.offset_losses) {
+            self.losses = (self.losses - realized_gain).max(Decimal::ZERO);
+        }
+    }
+}
+
+/// Fees and capital-gains tax of selling `amount` of `asset`, ignoring the
+/// losses other sells may offset.
+fn sell_costs(
+    asset: &Asset,
+    amount: Decimal,
+    general_fees: &TransactionFees,
+    capital_gains_tax: Option<&CapitalGainsTax>,
+) -> Decimal {
+    let gain_ratio = gain_ratio(asset).max(Decimal::ZERO);
+    let tax = capital_gains_tax.map_or(Decimal::ZERO, |t| amount * gain_ratio * t.rate);
+
+    asset.compute_sell_fee(&amount, general_fees) + tax
+}
+
+/// Shares to sell to raise at least `amount`, in whole lots and within the
+/// shares held.
+fn shares_to_sell(asset: &Asset, amount: Decimal) -> Decimal {
+    let shares = amount / asset.price;
+    let shares = match asset.lot_size() {
+        Some(lot) => (shares / lot).ceil() * lot,
+        None => shares.round_dp_with_strategy(SHARES_DECIMALS, RoundingStrategy::AwayFromZero),
+    };
+
+    shares.min(asset.current_shares)
+}
+
+/// Tradable shares of `asset` not exceeding `shares`.
+fn shares_within(asset: &Asset, shares: Decimal) -> Decimal {
+    match asset.lot_size() {
+        Some(lot) => ((shares / lot).trunc() * lot).round_dp(SHARES_DECIMALS),
+        None => shares.round_dp_with_strategy(SHARES_DECIMALS, RoundingStrategy::ToZero),
+    }
+}
+
+/// Gain realized per unit of value sold, negative for assets at a loss.
+fn gain_ratio(asset: &Asset) -> Decimal {
+    match asset.average_buy_price {
+        Some(basis) if asset.price > Decimal::ZERO => (asset.price - basis) / asset.price,
+        _ => Decimal::ZERO,
+    }
+}
+
+/// Records a sell that was computed but rejected because of its cost.
+fn reject_sell(
+    asset: &mut Asset,
+    sell_shares: Decimal,
+    sell_amount: Decimal,
+    sell_fees: Decimal,
+    state: SolutionState,
+) {
+    asset.theo_alloc.replace(TheoreticalAllocation {
+        shares: asset.shares - sell_shares,
+        amount: asset.amount - sell_amount,
+        fees: sell_fees,
+    });
+    asset.state = state;
+}
+
+/// Get a view over under allocated assets i.e. assets with `current_weight`
+/// less than `target_weight`
+fn under_allocated_view(assets: &mut HashMap<String, Asset>) -> Vec<&mut Asset> {
     assets
         .values_mut()
         .filter_map(|a| (a.state == SolutionState::Open).then_some(a))
@@ -502,15 +1737,14 @@ fn amount_to_allocate(asset: &Asset, budget: Decimal, w_i: Decimal) -> Decimal {
 fn shares_to_allocate(asset: &Asset, allocated_amount: Decimal) -> Decimal {
     let shares = allocated_amount / asset.price;
 
-    if asset.is_whole_shares {
-        shares.trunc()
-    } else {
-        shares.round_dp(SHARES_DECIMALS)
+    match asset.lot_size() {
+        Some(lot) => ((shares / lot).trunc() * lot).round_dp(SHARES_DECIMALS),
+        None => shares.round_dp(SHARES_DECIMALS),
     }
 }
 
 /// Remove fully allocated assets. Returns budget freed from assets with too
-/// high fee impact.
+/// high fee impact or orders breaking their minimum order rules.
 fn check_fully_allocated_assets(
     open_assets: &mut Vec<&mut Asset>,
     budget_left: &Decimal,
@@ -530,8 +1764,11 @@ fn check_fully_allocated_assets(
                 return true;
             }
 
-            // Cannot allocate more -- Budget left higher than asset price
-            if asset.is_whole_shares && asset.price > *budget_left {
+            // Cannot allocate more -- Budget left lower than one lot price
+            if asset
+                .lot_size()
+                .is_some_and(|lot| lot * asset.price > *budget_left)
+            {
                 asset.state = SolutionState::PriceTooHigh;
                 return true;
             }
@@ -540,9 +1777,7 @@ fn check_fully_allocated_assets(
         }();
 
         if to_remove {
-            if is_fee_impact_too_high(asset, fees) {
-                freed_budget += deallocate_asset_fee_too_high(asset, fees);
-            }
+            freed_budget += reject_unacceptable_order(asset, fees);
             open_assets.remove(i);
         } else {
             i += 1;
@@ -570,10 +1805,7 @@ fn unblock_ties(open_assets: &mut Vec<&mut Asset>, fees: &TransactionFees) -> De
     if let Some(idx) = not_enough_budget_idx {
         let asset = &mut open_assets[idx];
         asset.state = SolutionState::TieBreaker;
-
-        if is_fee_impact_too_high(asset, fees) {
-            freed_budget = deallocate_asset_fee_too_high(asset, fees);
-        }
+        freed_budget = reject_unacceptable_order(asset, fees);
 
         open_assets.remove(idx);
     }
@@ -598,6 +1830,18 @@ fn is_fee_impact_too_high(asset: &Asset, general_fees: &TransactionFees) -> bool
     impact > asset.get_max_fee_impact(general_fees)
 }
 
+/// Roll back the purchase of an asset leaving the open set if its fee impact
+/// is too high or it breaks a minimum order rule. Returns the budget freed.
+fn reject_unacceptable_order(asset: &mut Asset, fees: &TransactionFees) -> Decimal {
+    if is_fee_impact_too_high(asset, fees) {
+        deallocate_asset_fee_too_high(asset, fees)
+    } else if let Some(state) = asset.check_order_rules(asset.shares - asset.current_shares) {
+        deallocate_asset(asset, state)
+    } else {
+        Decimal::ZERO
+    }
+}
+
 fn deallocate_asset_fee_too_high(asset: &mut Asset, general_fees: &TransactionFees) -> Decimal {
     let allocated = asset.get_allocated_amount();
 
@@ -607,11 +1851,17 @@ fn deallocate_asset_fee_too_high(asset: &mut Asset, general_fees: &TransactionFe
         fees: asset.compute_fee(&allocated, general_fees),
     });
 
+    deallocate_asset(asset, SolutionState::FeesTooHigh)
+}
+
+fn deallocate_asset(asset: &mut Asset, state: SolutionState) -> Decimal {
+    let allocated = asset.get_allocated_amount();
+
     asset.amount = asset.current_amount;
     asset.shares = asset.current_shares;
     asset.weight = asset.current_weight;
 
-    asset.state = SolutionState::FeesTooHigh;
+    asset.state = state;
 
     allocated
 }
@@ -621,6 +1871,7 @@ mod tests {
     use rust_decimal_macros::dec;
 
     use super::*;
+    use crate::optimize::{FeeStructure, FeeStructureFixed};
 
     #[test_log::test]
     fn it_solves_60_40_portfolio_buy_only() {
@@ -752,6 +2003,700 @@ mod tests {
         assert_eq!(solution.budget_left, dec!(0.58));
     }
 
+    #[test_log::test]
+    fn it_deducts_sell_fees_from_freed_budget() {
+        // Given
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(false, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.fees.sell_fee_structure = Some(FeeStructure::Fixed(FeeStructureFixed {
+            fee_amount: dec!(0.5),
+        }));
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        {
+            let sol = &solution.assets[&vwce];
+            assert_eq!(sol.shares, dec!(57));
+            assert_eq!(sol.sell_fees, dec!(0.5));
+        }
+        assert_eq!(solution.assets[&aggh].shares, dec!(30));
+        assert_eq!(solution.budget_left, dec!(0.08));
+    }
+
+    #[test_log::test]
+    fn it_rejects_sells_with_fee_impact_too_high() {
+        // Given
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(false, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.fees.max_fee_impact = dec!(0.1);
+        problem.options.fees.sell_fee_structure = Some(FeeStructure::Fixed(FeeStructureFixed {
+            fee_amount: dec!(0.5),
+        }));
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        {
+            let sol = &solution.assets[&vwce];
+            assert_eq!(sol.state, SolutionState::FeesTooHigh);
+            assert_eq!(sol.shares, dec!(60));
+            assert_eq!(sol.sell_fees, dec!(0));
+            let theo_alloc = sol.theo_alloc.as_ref().unwrap();
+            assert_eq!(theo_alloc.shares, dec!(57));
+            assert_eq!(theo_alloc.fees, dec!(0.5));
+        }
+        assert_eq!(solution.assets[&aggh].shares, dec!(28));
+        assert_eq!(solution.budget_left, dec!(0));
+    }
+
+    #[test_log::test]
+    fn it_deducts_capital_gains_tax_from_freed_budget() {
+        // Given
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(false, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.capital_gains_tax = Some(CapitalGainsTax {
+            rate: dec!(0.26),
+            offset_losses: false,
+            max_tax: None,
+        });
+        problem
+            .options
+            .assets
+            .get_mut(&vwce)
+            .unwrap()
+            .average_buy_price = Some(dec!(0.56));
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        {
+            let sol = &solution.assets[&vwce];
+            assert_eq!(sol.shares, dec!(57));
+            assert_eq!(sol.realized_gain, dec!(1.5));
+            assert_eq!(sol.tax, dec!(0.39));
+        }
+        assert_eq!(solution.assets[&aggh].shares, dec!(30));
+        assert_eq!(solution.budget_left, dec!(0.19));
+    }
+
+    #[test_log::test]
+    fn it_offsets_gains_with_losses_realized_first() {
+        for (offset_losses, gain_tax, new_amount) in
+            [(true, dec!(5), dec!(95)), (false, dec!(10), dec!(90))]
+        {
+            // Given
+            let asset = |symbol: &str, shares, target_weight, average_buy_price| ProblemAsset {
+                symbol: symbol.to_string(),
+                shares,
+                price: dec!(10),
+                target_weight,
+                is_whole_shares: false,
+                fees: None,
+                quantity_step: None,
+                min_quantity: None,
+                min_order_amount: None,
+                average_buy_price,
+                tolerance_band: None,
+                price_ccy: None,
+            };
+            let options = ProblemOptions {
+                pfolio_ccy: "eur".into(),
+                current_pfolio_amount: dec!(0),
+                assets: HashMap::from([
+                    (
+                        "GAIN".into(),
+                        asset("GAIN", dec!(10), dec!(0.25), Some(dec!(6))),
+                    ),
+                    (
+                        "LOSS".into(),
+                        asset("LOSS", dec!(10), dec!(0.25), Some(dec!(12))),
+                    ),
+                    ("NEW".into(), asset("NEW", dec!(0), dec!(0.5), None)),
+                ]),
+                budget: dec!(0),
+                fees: TransactionFees::default(),
+                is_buy_only: false,
+                use_all_budget: false,
+                capital_gains_tax: Some(CapitalGainsTax {
+                    rate: dec!(0.5),
+                    offset_losses,
+                    max_tax: None,
+                }),
+                tolerance_band: None,
+                groups: Vec::new(),
+                fx_rates: HashMap::new(),
+                fx_fees: HashMap::new(),
+            };
+
+            // When
+            let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+            // Expect
+            let loss = &solution.assets["LOSS"];
+            assert_eq!(loss.realized_gain, dec!(-10));
+            assert_eq!(loss.tax, dec!(0));
+            let gain = &solution.assets["GAIN"];
+            assert_eq!(gain.realized_gain, dec!(20));
+            assert_eq!(gain.tax, gain_tax);
+            assert_eq!(solution.assets["NEW"].amount, new_amount);
+        }
+    }
+
+    #[test_log::test]
+    fn it_sells_lowest_gains_first_within_the_tax_budget() {
+        for (max_tax, high_shares, new_amount) in
+            [(dec!(10), dec!(8), dec!(60)), (dec!(0), dec!(10), dec!(0))]
+        {
+            // Given -- LOW realizes 2 per share sold, HIGH 5
+            let asset = |symbol: &str, shares, target_weight, average_buy_price| ProblemAsset {
+                symbol: symbol.to_string(),
+                shares,
+                price: dec!(10),
+                target_weight,
+                is_whole_shares: false,
+                fees: None,
+                quantity_step: None,
+                min_quantity: None,
+                min_order_amount: None,
+                average_buy_price,
+                tolerance_band: None,
+                price_ccy: None,
+            };
+            let options = ProblemOptions {
+                pfolio_ccy: "eur".into(),
+                current_pfolio_amount: dec!(0),
+                assets: HashMap::from([
+                    (
+                        "LOW".into(),
+                        asset("LOW", dec!(10), dec!(0.25), Some(dec!(8))),
+                    ),
+                    (
+                        "HIGH".into(),
+                        asset("HIGH", dec!(10), dec!(0.25), Some(dec!(5))),
+                    ),
+                    ("NEW".into(), asset("NEW", dec!(0), dec!(0.5), None)),
+                ]),
+                budget: dec!(0),
+                fees: TransactionFees::default(),
+                is_buy_only: false,
+                use_all_budget: false,
+                capital_gains_tax: Some(CapitalGainsTax {
+                    rate: dec!(0.5),
+                    offset_losses: false,
+                    max_tax: Some(max_tax),
+                }),
+                tolerance_band: None,
+                groups: Vec::new(),
+                fx_rates: HashMap::new(),
+                fx_fees: HashMap::new(),
+            };
+
+            // When
+            let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+            // Expect
+            let tax = solution.assets.values().map(|a| a.tax).sum::<Decimal>();
+            assert!(tax <= max_tax, "tax {tax} over budget {max_tax}");
+            let high = &solution.assets["HIGH"];
+            assert_eq!(high.shares, high_shares);
+            if max_tax.is_zero() {
+                assert_eq!(high.reason(), AllocationReason::TaxTooHigh);
+                assert_eq!(
+                    solution.assets["LOW"].reason(),
+                    AllocationReason::TaxTooHigh
+                );
+            } else {
+                assert_eq!(solution.assets["LOW"].shares, dec!(5));
+                assert_eq!(high.reason(), AllocationReason::Sold);
+            }
+            assert_eq!(solution.assets["NEW"].amount, new_amount);
+        }
+    }
+
+    #[test_log::test]
+    fn it_sells_most_overweight_assets_to_withdraw() {
+        // Given -- VWCE weighs 71% for a 60% target
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(true, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.budget = dec!(-20);
+        problem.options.fees.fee_structure = FeeStructure::Fixed(FeeStructureFixed {
+            fee_amount: dec!(1),
+        });
+        let problem = Problem::new(problem.options.validated().unwrap());
+
+        // When
+        let solution = problem.solve();
+
+        // Expect -- 20 VWCE shares cover the withdrawal and the sell fee
+        assert!(solution.is_solved);
+        {
+            let sol = &solution.assets[&vwce];
+            assert_eq!(sol.shares, dec!(40));
+            assert_eq!(sol.sell_fees, dec!(1));
+            assert_eq!(sol.reason(), AllocationReason::Sold);
+        }
+        {
+            let sol = &solution.assets[&aggh];
+            assert_eq!(sol.shares, dec!(20));
+            assert_eq!(sol.reason(), AllocationReason::BudgetExhausted);
+        }
+        let withdrawal = solution.withdrawal.unwrap();
+        assert_eq!(withdrawal.raised, dec!(20.2));
+        assert_eq!(withdrawal.shortfall, dec!(0));
+        assert_eq!(solution.budget_left, dec!(0.2));
+    }
+
+    #[test_log::test]
+    fn it_reports_withdrawal_shortfall() {
+        // Given -- selling everything raises 89.6 before fees
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(true, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.budget = dec!(-89);
+        problem.options.fees.fee_structure = FeeStructure::Fixed(FeeStructureFixed {
+            fee_amount: dec!(1),
+        });
+        let problem = Problem::new(problem.options.validated().unwrap());
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets[&vwce].shares, dec!(0));
+        assert_eq!(solution.assets[&aggh].shares, dec!(0));
+        let withdrawal = solution.withdrawal.unwrap();
+        assert_eq!(withdrawal.requested, dec!(89));
+        assert_eq!(withdrawal.raised, dec!(87.6));
+        assert_eq!(withdrawal.shortfall, dec!(1.4));
+        assert_eq!(solution.budget_left, dec!(0));
+    }
+
+    #[test_log::test]
+    fn it_only_tops_up_assets_within_tolerance_band() {
+        // Given -- VWCE weighs 71% for a 60% target
+        let (mut problem, assets) = build_60_40_portfolio_unbalanced(false, true, false);
+        let [vwce, aggh] = <[String; 2]>::try_from(assets).ok().unwrap();
+        problem.options.tolerance_band = Some(ToleranceBand {
+            absolute: Some(dec!(0.05)),
+            relative: Some(dec!(0.25)),
+        });
+        problem
+            .options
+            .assets
+            .get_mut(&vwce)
+            .unwrap()
+            .tolerance_band = Some(ToleranceBand {
+            absolute: Some(dec!(0.15)),
+            relative: None,
+        });
+
+        // When
+        let solution = problem.solve();
+
+        // Expect -- VWCE is not sold and gets its 60% share of the budget
+        assert!(solution.is_solved);
+        {
+            let sol = &solution.assets[&vwce];
+            assert!(sol.is_in_band);
+            assert_eq!(sol.target_amount, dec!(69.84));
+            assert_eq!(sol.shares, dec!(65));
+        }
+        {
+            let sol = &solution.assets[&aggh];
+            assert!(!sol.is_in_band);
+            assert_eq!(sol.target_amount, dec!(40));
+            assert_eq!(sol.shares, dec!(23));
+        }
+        assert_eq!(solution.budget_left, dec!(1.2));
+    }
+
+    #[test_log::test]
+    fn it_solves_stateless_problems_from_json() {
+        // Given
+        let options: ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "VWCE": {
+                    "symbol": "VWCE",
+                    "shares": 0.,
+                    "price": 5.3,
+                    "targetWeight": 0.6,
+                    "isWholeShares": true,
+                    "fees": null
+                },
+                "AGGH": {
+                    "symbol": "AGGH",
+                    "shares": 0.,
+                    "price": 1.3,
+                    "targetWeight": 0.4,
+                    "isWholeShares": true,
+                    "fees": null
+                }
+            },
+            "budget": 100.,
+            "isBuyOnly": true
+        }))
+        .unwrap();
+
+        // When
+        let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.assets["VWCE"].shares, dec!(11));
+        assert_eq!(solution.assets["AGGH"].shares, dec!(30));
+        assert_eq!(solution.budget_left, dec!(2.7));
+
+        let serialized = serde_json::to_value(&solution).unwrap();
+        assert_eq!(serialized["assets"]["VWCE"]["state"], "priceTooHigh");
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_options() {
+        let (problem, _) = build_60_40_portfolio_no_allocation(true, false);
+        let valid = problem.options;
+
+        let mut excessive_withdrawal = valid.clone();
+        excessive_withdrawal.budget = dec!(-1);
+
+        let mut bad_weights = valid.clone();
+        bad_weights.assets.get_mut("VWCE").unwrap().target_weight = dec!(0.5);
+
+        let mut bad_fees = valid.clone();
+        bad_fees.fees.max_fee_impact = dec!(2);
+
+        for options in [excessive_withdrawal, bad_weights, bad_fees] {
+            assert!(matches!(
+                crate::optimize::solve_advanced(&options),
+                Err(OptimizeError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn it_meets_group_targets_before_member_targets() {
+        // Given -- equities are underweight, bonds are overweight as a group
+        // but BND2 is empty within it
+        let asset = |symbol: &str, shares: f64| {
+            serde_json::json!({
+                "symbol": symbol,
+                "shares": shares,
+                "price": 1.,
+                "targetWeight": 0.,
+                "isWholeShares": false,
+                "fees": null
+            })
+        };
+        let options: ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "EQ": asset("EQ", 60.),
+                "BND1": asset("BND1", 40.),
+                "BND2": asset("BND2", 0.),
+            },
+            "budget": 10.,
+            "isBuyOnly": true,
+            "groups": [
+                { "name": "equity", "targetWeight": 0.7, "members": { "EQ": 1. } },
+                { "name": "bonds", "targetWeight": 0.3, "members": { "BND1": 0.5, "BND2": 0.5 } }
+            ]
+        }))
+        .unwrap();
+
+        // When
+        let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.assets["EQ"].target_weight, dec!(0.7));
+        assert_eq!(solution.assets["BND2"].target_weight, dec!(0.15));
+        assert_eq!(solution.assets["EQ"].amount, dec!(70));
+        assert_eq!(solution.assets["BND2"].amount, dec!(0));
+        assert_eq!(solution.budget_left, dec!(0));
+
+        let equity = &solution.groups["equity"];
+        assert_eq!(equity.current_weight, dec!(0.6));
+        assert_eq!(equity.drift, dec!(-0.063636));
+        let bonds = &solution.groups["bonds"];
+        assert_eq!(bonds.drift, dec!(0.063636));
+        assert_eq!(bonds.member_drifts["BND1"], dec!(0.5));
+        assert_eq!(bonds.member_drifts["BND2"], dec!(-0.5));
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_groups() {
+        let (problem, _) = build_60_40_portfolio_no_allocation(true, false);
+        let group = |name: &str, weight: Decimal, members: &[(&str, Decimal)]| TargetGroup {
+            name: name.to_string(),
+            target_weight: weight,
+            members: members
+                .iter()
+                .map(|(aid, w)| (aid.to_string(), *w))
+                .collect(),
+        };
+
+        let mut bad_group_weights = problem.options.clone();
+        bad_group_weights.groups = vec![
+            group("equity", dec!(0.5), &[("VWCE", dec!(1))]),
+            group("bonds", dec!(0.4), &[("AGGH", dec!(1))]),
+        ];
+
+        let mut bad_member_weights = problem.options.clone();
+        bad_member_weights.groups = vec![group(
+            "all",
+            dec!(1),
+            &[("VWCE", dec!(0.5)), ("AGGH", dec!(0.4))],
+        )];
+
+        let mut ungrouped_asset = problem.options.clone();
+        ungrouped_asset.groups = vec![group("equity", dec!(1), &[("VWCE", dec!(1))])];
+
+        let mut duplicate_member = problem.options.clone();
+        duplicate_member.groups = vec![
+            group("equity", dec!(0.6), &[("VWCE", dec!(1))]),
+            group(
+                "bonds",
+                dec!(0.4),
+                &[("AGGH", dec!(0.5)), ("VWCE", dec!(0.5))],
+            ),
+        ];
+
+        for options in [
+            bad_group_weights,
+            bad_member_weights,
+            ungrouped_asset,
+            duplicate_member,
+        ] {
+            assert!(matches!(
+                crate::optimize::solve_advanced(&options),
+                Err(OptimizeError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test_log::test]
+    fn it_converts_foreign_prices_and_charges_fx_fees() {
+        // Given -- SPY is priced in USD, converted at 0.9 EUR with a 1% FX fee
+        let mut options: ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "VWCE": {
+                    "symbol": "VWCE",
+                    "shares": 0.,
+                    "price": 10.,
+                    "targetWeight": 0.5,
+                    "isWholeShares": false,
+                    "fees": null
+                },
+                "SPY": {
+                    "symbol": "SPY",
+                    "shares": 0.,
+                    "price": 10.,
+                    "priceCcy": "USD",
+                    "targetWeight": 0.5,
+                    "isWholeShares": false,
+                    "fees": null
+                }
+            },
+            "budget": 100.,
+            "isBuyOnly": true,
+            "fxRates": { "usd": 0.9 },
+            "fxFees": {
+                "usd": { "type": "variable", "minFee": 0., "maxFee": 100., "feeRate": 0.01 }
+            }
+        }))
+        .unwrap();
+
+        // When
+        let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+        // Expect
+        let spy = &solution.assets["SPY"];
+        assert_eq!(spy.price, dec!(9));
+        assert_eq!(spy.amount, dec!(50));
+        assert_eq!(spy.fx_fees, dec!(0.5));
+        assert_eq!(solution.assets["VWCE"].fx_fees, dec!(0));
+        assert_eq!(solution.budget_left, dec!(0));
+
+        // Given -- FX fees count toward the fee impact
+        options.fees.max_fee_impact = dec!(0.005);
+
+        // When
+        let solution = crate::optimize::solve_advanced(&options).unwrap();
+
+        // Expect
+        let spy = &solution.assets["SPY"];
+        assert_eq!(spy.state, SolutionState::FeesTooHigh);
+        assert_eq!(spy.amount, dec!(0));
+        assert_eq!(spy.theo_alloc.as_ref().unwrap().fees, dec!(0.5));
+        assert_eq!(solution.assets["VWCE"].amount, dec!(50));
+
+        // Given -- every foreign currency needs a rate
+        options.fx_rates.clear();
+
+        // Expect
+        assert!(matches!(
+            crate::optimize::solve_advanced(&options),
+            Err(OptimizeError::InvalidInput(_))
+        ));
+    }
+
+    #[test_log::test]
+    fn it_explains_asset_outcomes() {
+        // Given
+        let (mut problem, _) = build_crypto_portfolio();
+        problem
+            .options
+            .assets
+            .get_mut("BTC")
+            .unwrap()
+            .is_whole_shares = true;
+        problem.options.fees.fee_structure = FeeStructure::Fixed(FeeStructureFixed {
+            fee_amount: dec!(1),
+        });
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        let eth = &solution.assets["ETH"];
+        assert_eq!(eth.reason(), AllocationReason::TargetReached);
+        assert_eq!(eth.drift(), dec!(0));
+        assert_eq!(eth.trade_fees, dec!(1));
+
+        let btc = &solution.assets["BTC"];
+        assert_eq!(btc.reason(), AllocationReason::PriceTooHigh);
+        assert_eq!(btc.drift(), dec!(-0.5));
+        assert_eq!(btc.trade_fees, dec!(0));
+
+        assert_eq!(
+            solution.warnings,
+            vec![SolutionWarning::BudgetBelowUnitPrice {
+                symbol: "BTC".to_string(),
+                unit_price: dec!(3000),
+            }]
+        );
+    }
+
+    #[test_log::test]
+    fn it_allocates_in_quantity_steps() {
+        // Given
+        let (mut problem, _) = build_crypto_portfolio();
+        problem.options.assets.get_mut("BTC").unwrap().quantity_step = Some(dec!(0.001));
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        let btc = &solution.assets["BTC"];
+        assert_eq!(btc.shares, dec!(0.016));
+        assert_eq!(btc.amount, dec!(48));
+        assert_eq!(btc.state, SolutionState::PriceTooHigh);
+        assert_eq!(solution.assets["ETH"].shares, dec!(5));
+        assert_eq!(solution.budget_left, dec!(2));
+    }
+
+    #[test_log::test]
+    fn it_skips_orders_below_minimums() {
+        for (min_quantity, min_order_amount, state) in [
+            (Some(dec!(6)), None, SolutionState::MinQuantityNotMet),
+            (None, Some(dec!(60)), SolutionState::MinOrderAmountNotMet),
+        ] {
+            // Given
+            let (mut problem, _) = build_crypto_portfolio();
+            let eth = problem.options.assets.get_mut("ETH").unwrap();
+            eth.min_quantity = min_quantity;
+            eth.min_order_amount = min_order_amount;
+            problem.options.use_all_budget = true;
+
+            // When
+            let solution = problem.solve();
+
+            // Expect
+            assert!(solution.is_solved);
+            let eth = &solution.assets["ETH"];
+            assert_eq!(eth.state, state);
+            assert_eq!(eth.shares, dec!(0));
+            assert_eq!(eth.amount, dec!(0));
+            assert_eq!(solution.assets["BTC"].amount, dec!(100));
+            assert_eq!(solution.budget_left, dec!(0));
+        }
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_order_rules() {
+        let (problem, _) = build_crypto_portfolio();
+        let valid = problem.options;
+
+        let mut zero_step = valid.clone();
+        zero_step.assets.get_mut("BTC").unwrap().quantity_step = Some(dec!(0));
+
+        let mut negative_min_quantity = valid.clone();
+        negative_min_quantity
+            .assets
+            .get_mut("BTC")
+            .unwrap()
+            .min_quantity = Some(dec!(-1));
+
+        let mut negative_min_order = valid.clone();
+        negative_min_order
+            .assets
+            .get_mut("ETH")
+            .unwrap()
+            .min_order_amount = Some(dec!(-1));
+
+        for options in [zero_step, negative_min_quantity, negative_min_order] {
+            assert!(matches!(
+                crate::optimize::solve_advanced(&options),
+                Err(OptimizeError::InvalidInput(_))
+            ));
+        }
+    }
+
+    fn build_crypto_portfolio() -> (Problem, Vec<String>) {
+        let asset = |symbol: &str, price| ProblemAsset {
+            symbol: symbol.to_string(),
+            shares: dec!(0),
+            price,
+            target_weight: dec!(0.5),
+            is_whole_shares: false,
+            fees: None,
+            quantity_step: None,
+            min_quantity: None,
+            min_order_amount: None,
+            average_buy_price: None,
+            tolerance_band: None,
+            price_ccy: None,
+        };
+
+        let options = ProblemOptions {
+            pfolio_ccy: "eur".into(),
+            current_pfolio_amount: dec!(0),
+            assets: HashMap::from([
+                ("BTC".to_string(), asset("BTC", dec!(3000))),
+                ("ETH".to_string(), asset("ETH", dec!(10))),
+            ]),
+            budget: dec!(100),
+            fees: TransactionFees::default(),
+            is_buy_only: true,
+            use_all_budget: false,
+            capital_gains_tax: None,
+            tolerance_band: None,
+            groups: Vec::new(),
+            fx_rates: HashMap::new(),
+            fx_fees: HashMap::new(),
+        };
+
+        (Problem::new(options), vec!["BTC".into(), "ETH".into()])
+    }
+
     fn build_60_40_portfolio_no_allocation(
         is_buy_only: bool,
         use_all_budget: bool,
@@ -769,6 +2714,12 @@ mod tests {
                     target_weight: dec!(0.6),
                     is_whole_shares: true,
                     fees: None,
+                    quantity_step: None,
+                    min_quantity: None,
+                    min_order_amount: None,
+                    average_buy_price: None,
+                    tolerance_band: None,
+                    price_ccy: None,
                 },
             ),
             (
@@ -780,6 +2731,12 @@ mod tests {
                     target_weight: dec!(0.4),
                     is_whole_shares: true,
                     fees: None,
+                    quantity_step: None,
+                    min_quantity: None,
+                    min_order_amount: None,
+                    average_buy_price: None,
+                    tolerance_band: None,
+                    price_ccy: None,
                 },
             ),
         ]);
@@ -797,6 +2754,11 @@ mod tests {
             fees: TransactionFees::default(),
             is_buy_only,
             use_all_budget,
+            capital_gains_tax: None,
+            tolerance_band: None,
+            groups: Vec::new(),
+            fx_rates: HashMap::new(),
+            fx_fees: HashMap::new(),
         };
 
         (Problem::new(options), vec![vwce, aggh])
@@ -827,6 +2789,12 @@ mod tests {
                     target_weight: dec!(0.6),
                     is_whole_shares: true,
                     fees: None,
+                    quantity_step: None,
+                    min_quantity: None,
+                    min_order_amount: None,
+                    average_buy_price: None,
+                    tolerance_band: None,
+                    price_ccy: None,
                 },
             ),
             (
@@ -838,6 +2806,12 @@ mod tests {
                     target_weight: dec!(0.4),
                     is_whole_shares: true,
                     fees: None,
+                    quantity_step: None,
+                    min_quantity: None,
+                    min_order_amount: None,
+                    average_buy_price: None,
+                    tolerance_band: None,
+                    price_ccy: None,
                 },
             ),
         ]);
@@ -855,6 +2829,11 @@ mod tests {
             fees: TransactionFees::default(),
             is_buy_only,
             use_all_budget,
+            capital_gains_tax: None,
+            tolerance_band: None,
+            groups: Vec::new(),
+            fx_rates: HashMap::new(),
+            fx_fees: HashMap::new(),
         };
 
         (Problem::new(options), vec![vwce, aggh])
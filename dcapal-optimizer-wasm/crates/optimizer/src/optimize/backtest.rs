@@ -0,0 +1,438 @@
+//! Historical DCA backtesting.
+//!
+//! Replays periodic contributions over the price series of each asset,
+//! allocating every contribution with the [`advanced`] optimizer at the
+//! prices of its date. Reports how the portfolio value, the invested capital,
+//! the fees paid and the drift from target weights evolve, so that fee
+//! policies and contribution frequencies can be compared.
+
+use std::collections::{BTreeSet, HashMap};
+
+use log::debug;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{OptimizeError, advanced};
+use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Most allocations of a contribution tried before leaving it as cash. Each
+/// one holds back the buy fees the previous one could not pay.
+const MAX_FEE_RESERVE_STEPS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemOptions {
+    /// Problem replayed at each contribution date. Its `budget` is the
+    /// contribution, asset `shares` are the initial holdings and asset
+    /// `price`s are replaced by the series ones.
+    #[serde(flatten)]
+    pub advanced: advanced::ProblemOptions,
+    /// Price series of each asset, by asset id, in the asset price currency.
+    pub prices: HashMap<String, Vec<PriceSample>>,
+    /// Days between two contributions.
+    pub interval_days: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceSample {
+    /// Unix timestamp of the sample, in seconds.
+    pub timestamp: i64,
+    pub price: Decimal,
+}
+
+impl ProblemOptions {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        if self.interval_days == 0 {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid contribution interval. Must be at least one day".to_string(),
+            ));
+        }
+
+        let advanced = self.advanced.validated()?;
+        if advanced.budget < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid contribution ({}). Must be non-negative",
+                advanced.budget
+            )));
+        }
+
+        if let Some(aid) = self
+            .prices
+            .keys()
+            .find(|aid| !advanced.assets.contains_key(*aid))
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Price series of unknown asset {aid}"
+            )));
+        }
+
+        let mut prices = HashMap::with_capacity(advanced.assets.len());
+        for (aid, asset) in &advanced.assets {
+            let mut series = self.prices.get(aid).cloned().unwrap_or_default();
+            if series.is_empty() {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Missing price series for {}",
+                    asset.symbol
+                )));
+            }
+
+            if let Some(s) = series.iter().find(|s| s.price <= Decimal::ZERO) {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid price for {} at {} ({}). Must be positive",
+                    asset.symbol, s.timestamp, s.price
+                )));
+            }
+
+            series.sort_by_key(|s| s.timestamp);
+            series.dedup_by_key(|s| s.timestamp);
+            for s in &mut series {
+                s.price = s.price.round_dp(AMOUNT_DECIMALS);
+            }
+            prices.insert(aid.clone(), series);
+        }
+
+        Ok(Self {
+            advanced,
+            prices,
+            interval_days: self.interval_days,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Solution {
+    /// Portfolio state at each date on which every asset has a price.
+    pub points: Vec<BacktestPoint>,
+    /// Number of contributions replayed.
+    pub contributions: u32,
+    /// Shares held at the end of the backtest, by asset id.
+    pub holdings: HashMap<String, Decimal>,
+    /// Cash left unallocated at the end of the backtest.
+    pub cash: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestPoint {
+    pub timestamp: i64,
+    /// Value of the holdings plus the cash left.
+    pub value: Decimal,
+    /// Contributions made so far.
+    pub invested: Decimal,
+    /// Fees paid so far.
+    pub fees: Decimal,
+    /// Largest distance of an asset weight from its target.
+    pub max_drift: Decimal,
+}
+
+pub struct Problem {
+    pub(crate) options: ProblemOptions,
+}
+
+impl Problem {
+    pub fn new(options: ProblemOptions) -> Self {
+        Self { options }
+    }
+
+    /// Replays the contributions from the first date on which every asset
+    /// has a price. Prices are carried forward over the dates an asset has
+    /// no sample for.
+    pub fn solve(&self) -> Result<Solution, OptimizeError> {
+        let contribution = self.options.advanced.budget;
+        let interval = i64::from(self.options.interval_days) * SECONDS_PER_DAY;
+
+        let mut options = self.options.advanced.clone();
+        let mut cursors = HashMap::<&String, usize>::new();
+        let mut prices = HashMap::new();
+
+        let mut cash = Decimal::ZERO;
+        let mut invested = Decimal::ZERO;
+        let mut fees = Decimal::ZERO;
+        let mut contributions = 0;
+        let mut next_contribution = None;
+        let mut points = Vec::new();
+
+        for timestamp in self.timeline() {
+            for (aid, series) in &self.options.prices {
+                let cursor = cursors.entry(aid).or_default();
+                while *cursor < series.len() && series[*cursor].timestamp <= timestamp {
+                    prices.insert(aid.clone(), series[*cursor].price);
+                    *cursor += 1;
+                }
+            }
+
+            if prices.len() < options.assets.len() {
+                continue;
+            }
+
+            for (aid, asset) in &mut options.assets {
+                asset.price = prices[aid];
+            }
+
+            let next = *next_contribution.get_or_insert(timestamp);
+            if timestamp >= next {
+                let due = (timestamp - next) / interval + 1;
+                let due_amount = contribution * Decimal::from(due);
+                next_contribution = Some(next + due * interval);
+                contributions += due as u32;
+                invested += due_amount;
+                cash += due_amount;
+
+                if cash > Decimal::ZERO
+                    && let Some((solution, cash_left)) = allocate(&mut options, cash)?
+                {
+                    let paid = solution
+                        .assets
+                        .values()
+                        .map(|a| a.trade_fees)
+                        .sum::<Decimal>();
+                    for (aid, asset) in &mut options.assets {
+                        asset.shares = solution.assets[aid].shares;
+                    }
+                    cash = cash_left;
+                    fees += paid;
+
+                    debug!("[Backtest] timestamp={timestamp} cash={cash} fees={paid}");
+                }
+            }
+
+            points.push(self.point(&options, timestamp, cash, invested, fees));
+        }
+
+        Ok(Solution {
+            points,
+            contributions,
+            holdings: options
+                .assets
+                .iter()
+                .map(|(aid, a)| (aid.clone(), a.shares))
+                .collect(),
+            cash,
+        })
+    }
+
+    /// Dates of all the samples, in increasing order.
+    fn timeline(&self) -> BTreeSet<i64> {
+        self.options
+            .prices
+            .values()
+            .flatten()
+            .map(|s| s.timestamp)
+            .collect()
+    }
+
+    fn point(
+        &self,
+        options: &advanced::ProblemOptions,
+        timestamp: i64,
+        cash: Decimal,
+        invested: Decimal,
+        fees: Decimal,
+    ) -> BacktestPoint {
+        let amounts = options
+            .assets
+            .values()
+            .map(|a| {
+                let price = options
+                    .fx_conversion(a)
+                    .map_or(a.price, |fx| fx.convert(a.price));
+                (
+                    (price * a.shares).round_dp(AMOUNT_DECIMALS),
+                    a.target_weight,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let value = amounts.iter().map(|(amount, _)| *amount).sum::<Decimal>() + cash;
+        let max_drift = amounts
+            .iter()
+            .map(|(amount, target_weight)| match value > Decimal::ZERO {
+                true => ((amount / value).round_dp(PERCENTAGE_DECIMALS) - target_weight).abs(),
+                false => *target_weight,
+            })
+            .max()
+            .unwrap_or_default();
+
+        BacktestPoint {
+            timestamp,
+            value,
+            invested,
+            fees,
+            max_drift,
+        }
+    }
+}
+
+/// Allocates `cash`, holding back from it the fees of the purchases, and
+/// returns the solution with the cash left. Sell fees are already netted out
+/// of the solution budget left. Returns `None` if no allocation pays its own
+/// fees, leaving the cash untouched.
+fn allocate(
+    options: &mut advanced::ProblemOptions,
+    cash: Decimal,
+) -> Result<Option<(advanced::Solution, Decimal)>, OptimizeError> {
+    let mut reserve = Decimal::ZERO;
+    for _ in 0..MAX_FEE_RESERVE_STEPS {
+        options.budget = cash - reserve;
+        let solution = advanced::Problem::new(options.validated()?).solve();
+        if !solution.is_solved {
+            return Err(OptimizeError::Unsolved);
+        }
+
+        let buy_fees = solution
+            .assets
+            .values()
+            .filter(|a| a.shares > a.current_shares)
+            .map(|a| a.trade_fees)
+            .sum::<Decimal>();
+        let cash_left = solution.budget_left + reserve - buy_fees;
+        if cash_left >= Decimal::ZERO {
+            return Ok(Some((solution, cash_left)));
+        }
+
+        debug!("[Backtest] Buy fees exceed cash left: reserve={reserve} cash_left={cash_left}");
+        if reserve == cash {
+            break;
+        }
+        reserve = (reserve - cash_left).min(cash);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn daily(prices: &[Decimal]) -> Vec<PriceSample> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(day, price)| PriceSample {
+                timestamp: day as i64 * SECONDS_PER_DAY,
+                price: *price,
+            })
+            .collect()
+    }
+
+    fn build_two_asset_backtest(fee_amount: f64, interval_days: u32) -> ProblemOptions {
+        let advanced: advanced::ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "A": {
+                    "symbol": "A",
+                    "shares": 0.,
+                    "price": 10.,
+                    "targetWeight": 0.5,
+                    "fees": null
+                },
+                "B": {
+                    "symbol": "B",
+                    "shares": 0.,
+                    "price": 20.,
+                    "targetWeight": 0.5,
+                    "fees": null
+                }
+            },
+            "budget": 100.,
+            "fees": {
+                "feeStructure": {
+                    "type": "fixed",
+                    "feeAmount": fee_amount
+                }
+            },
+            "isBuyOnly": true
+        }))
+        .unwrap();
+
+        let prices = HashMap::from([
+            (
+                "A".to_string(),
+                daily(&[dec!(10), dec!(10), dec!(20), dec!(20)]),
+            ),
+            (
+                "B".to_string(),
+                daily(&[dec!(20), dec!(20), dec!(20), dec!(20)]),
+            ),
+        ]);
+
+        ProblemOptions {
+            advanced,
+            prices,
+            interval_days,
+        }
+    }
+
+    #[test_log::test]
+    fn it_replays_contributions_over_price_series() {
+        // Given
+        let options = build_two_asset_backtest(0., 2);
+
+        // When
+        let solution = crate::optimize::solve_backtest(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.contributions, 2);
+        assert_eq!(solution.points.len(), 4);
+
+        // Day 0: 50 in each asset
+        let first = &solution.points[0];
+        assert_eq!(first.value, dec!(100));
+        assert_eq!(first.max_drift, Decimal::ZERO);
+
+        // Day 1: no contribution, prices unchanged
+        assert_eq!(solution.points[1].invested, dec!(100));
+        assert_eq!(solution.points[1].value, dec!(100));
+
+        // Day 2: A doubled, so the contribution mostly buys B
+        let third = &solution.points[2];
+        assert_eq!(third.invested, dec!(200));
+        assert_eq!(third.value, dec!(250));
+        assert_eq!(third.max_drift, Decimal::ZERO);
+        assert_eq!(solution.holdings["A"], dec!(6.25));
+        assert_eq!(solution.holdings["B"], dec!(6.25));
+        assert_eq!(solution.cash, Decimal::ZERO);
+    }
+
+    #[test_log::test]
+    fn it_charges_fees_on_each_purchase() {
+        // Given
+        let options = build_two_asset_backtest(1., 1);
+
+        // When
+        let solution = crate::optimize::solve_backtest(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.contributions, 4);
+
+        // Both assets are bought every day but on day 2, when A is overweight
+        let last = solution.points.last().unwrap();
+        assert_eq!(last.invested, dec!(400));
+        assert_eq!(last.fees, dec!(7));
+
+        // Fees are held back from each contribution
+        // Fees are held back from each contribution, so no cash is owed
+        assert_eq!(solution.cash, Decimal::ZERO);
+        assert_eq!(solution.points[0].value, dec!(98));
+        assert_eq!(last.value, dec!(491));
+    }
+
+    #[test_log::test]
+    fn it_rejects_missing_series() {
+        // Given
+        let mut options = build_two_asset_backtest(0., 1);
+        options.prices.remove("B");
+
+        // When
+        let res = crate::optimize::solve_backtest(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+    }
+}
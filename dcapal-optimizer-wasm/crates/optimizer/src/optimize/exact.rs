@@ -0,0 +1,574 @@
+//! Exact allocation of whole-share and lot-sized assets.
+//!
+//! Minimizes the deviation from target weights with integer lot variables,
+//! solved by branch and bound over `minilp` relaxations. The greedy
+//! [`advanced`] solution seeds the search and is returned, with a
+//! [`SolutionWarning::GreedyFallback`], whenever the problem is out of the
+//! model scope or the search cannot complete within its limits.
+
+use std::collections::HashMap;
+
+use log::debug;
+use minilp::{ComparisonOp, LinearExpr, OptimizationDirection, Variable};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    OptimizeError, TransactionFees,
+    advanced::{self, Asset, SolutionState, SolutionWarning},
+};
+use crate::{AMOUNT_DECIMALS, SHARES_DECIMALS, utils::now_millis};
+
+/// Distance from an integer below which a relaxed lot count is integral.
+const INTEGRALITY_TOLERANCE: f64 = 1e-6;
+
+/// Minimum objective improvement for a node to be worth exploring.
+const OBJECTIVE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemOptions {
+    #[serde(flatten)]
+    pub advanced: advanced::ProblemOptions,
+    #[serde(default)]
+    pub limits: SearchLimits,
+}
+
+impl ProblemOptions {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        if self.limits.max_nodes == 0 {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid search limits. Maximum nodes must be positive".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            advanced: self.advanced.validated()?,
+            limits: self.limits,
+        })
+    }
+}
+
+/// Bounds the branch-and-bound search.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchLimits {
+    /// Maximum number of LP relaxations solved.
+    pub max_nodes: u32,
+    /// Maximum search time, in milliseconds.
+    #[serde(default)]
+    pub max_millis: Option<u64>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: 10_000,
+            max_millis: Some(1_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Solution {
+    pub allocation: advanced::Solution,
+    /// Whether the allocation is proven optimal. Otherwise it is the greedy one.
+    pub is_exact: bool,
+    /// LP relaxations solved by the search.
+    pub explored_nodes: u32,
+}
+
+pub struct Problem {
+    pub(crate) options: ProblemOptions,
+}
+
+impl Problem {
+    pub fn new(options: ProblemOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn solve(&self) -> Solution {
+        let options = &self.options.advanced;
+        let problem = advanced::Problem::new(options.clone());
+        let greedy = problem.solve();
+
+        // Costs and order rules are only enforced by the greedy solver
+        if let Some(feature) = unmodelled_feature(options) {
+            debug!("[Exact] Problem not modelled ({feature}), keeping greedy solution");
+            return Solution {
+                allocation: with_fallback(greedy, format!("{feature} not modelled")),
+                is_exact: false,
+                explored_nodes: 0,
+            };
+        }
+
+        let pfolio_amount = options.current_pfolio_amount + options.budget;
+        let model = Model::new(&greedy.assets, options, pfolio_amount);
+        let search = model.branch_and_bound(model.deviation(&greedy.assets), &self.options.limits);
+
+        debug!("[Exact] search={search:?}");
+
+        let (allocation, is_exact) = match search.lots {
+            _ if !search.is_complete => (with_fallback(greedy, "search limits reached"), false),
+            // Nothing beats the greedy allocation
+            None => (greedy, true),
+            Some(lots) => match model.allocation(&problem, greedy.clone(), &lots, pfolio_amount) {
+                Some(allocation) => (allocation, true),
+                None => (
+                    with_fallback(greedy, "rounding overspends the budget"),
+                    false,
+                ),
+            },
+        };
+
+        Solution {
+            allocation,
+            is_exact,
+            explored_nodes: search.nodes,
+        }
+    }
+}
+
+fn with_fallback(mut greedy: advanced::Solution, reason: impl Into<String>) -> advanced::Solution {
+    greedy.warnings.push(SolutionWarning::GreedyFallback {
+        reason: reason.into(),
+    });
+    greedy
+}
+
+/// The first problem feature the integer model does not cover, if any.
+fn unmodelled_feature(options: &advanced::ProblemOptions) -> Option<&'static str> {
+    let is_free = |fees: &TransactionFees| {
+        fees.fee_structure.is_free() && fees.sell_fee_structure().is_free()
+    };
+
+    if options.budget < Decimal::ZERO {
+        Some("withdrawals")
+    } else if !is_free(&options.fees)
+        || options
+            .assets
+            .values()
+            .any(|a| !a.fees.as_ref().is_none_or(is_free))
+    {
+        Some("transaction fees")
+    } else if !options.fx_fees.values().all(|fees| fees.is_free()) {
+        Some("FX fees")
+    } else if options.capital_gains_tax.is_some() {
+        Some("capital-gains tax")
+    } else if options.tolerance_band.is_some()
+        || options.assets.values().any(|a| a.tolerance_band.is_some())
+    {
+        Some("tolerance bands")
+    } else if options
+        .assets
+        .values()
+        .any(|a| a.min_quantity.is_some() || a.min_order_amount.is_some())
+    {
+        Some("minimum order rules")
+    } else {
+        None
+    }
+}
+
+/// Lots traded by an asset, the unknowns of the integer model.
+struct LotVar {
+    aid: String,
+    var: Variable,
+    /// Shares in a lot, if the asset is not freely divisible
+    lot_size: Option<Decimal>,
+}
+
+/// Weight-deviation minimization over the lots traded by each asset:
+///
+/// ```text
+///    minimize sum_i(s_i_neg + s_i_pos)
+///    s.t. u_i * t_i + s_i_neg - s_i_pos = w_i - cw_i
+///         sum_i(u_i * t_i) <= budget / pfolio_amount
+/// ```
+///
+/// where `t_i` is the number of lots traded (negative if sold), `u_i` the
+/// value of a lot as a fraction of the portfolio and `cw_i` the current weight.
+struct Model {
+    problem: minilp::Problem,
+    vars: Vec<LotVar>,
+    pfolio_amount: f64,
+}
+
+#[derive(Debug)]
+struct Search {
+    /// Lots of the best integer solution found, if better than the greedy one
+    lots: Option<HashMap<String, f64>>,
+    nodes: u32,
+    is_complete: bool,
+}
+
+impl Model {
+    fn new(
+        assets: &HashMap<String, Asset>,
+        options: &advanced::ProblemOptions,
+        pfolio_amount: Decimal,
+    ) -> Self {
+        let mut problem = minilp::Problem::new(OptimizationDirection::Minimize);
+        let pfolio = pfolio_amount.to_f64().unwrap();
+
+        let mut aids = assets.keys().collect::<Vec<_>>();
+        aids.sort();
+
+        let mut vars = Vec::with_capacity(aids.len());
+        let mut spent = LinearExpr::empty();
+        for aid in aids {
+            let asset = &assets[aid];
+            if asset.price.is_zero() {
+                continue;
+            }
+
+            let lot_size = asset.lot_size();
+            let lot_shares = lot_size.unwrap_or(Decimal::ONE);
+            let lot_value = (asset.price * lot_shares).to_f64().unwrap() / pfolio;
+            let min_lots = if options.is_buy_only {
+                0.
+            } else {
+                -(asset.current_shares / lot_shares)
+                    .floor()
+                    .to_f64()
+                    .unwrap()
+            };
+
+            let t_i = problem.add_var(0., (min_lots, f64::INFINITY));
+            let s_i_neg = problem.add_var(1., (0., f64::INFINITY));
+            let s_i_pos = problem.add_var(1., (0., f64::INFINITY));
+
+            let current_weight = asset.current_amount.to_f64().unwrap() / pfolio;
+            problem.add_constraint(
+                [(t_i, lot_value), (s_i_neg, 1.), (s_i_pos, -1.)],
+                ComparisonOp::Eq,
+                asset.target_weight.to_f64().unwrap() - current_weight,
+            );
+
+            spent.add(t_i, lot_value);
+            vars.push(LotVar {
+                aid: aid.clone(),
+                var: t_i,
+                lot_size,
+            });
+        }
+
+        problem.add_constraint(
+            spent,
+            ComparisonOp::Le,
+            options.budget.to_f64().unwrap() / pfolio,
+        );
+
+        Self {
+            problem,
+            vars,
+            pfolio_amount: pfolio,
+        }
+    }
+
+    /// Objective value of an allocation.
+    fn deviation(&self, assets: &HashMap<String, Asset>) -> f64 {
+        self.vars
+            .iter()
+            .map(|v| {
+                let asset = &assets[&v.aid];
+                let weight = asset.amount.to_f64().unwrap() / self.pfolio_amount;
+                (weight - asset.target_weight.to_f64().unwrap()).abs()
+            })
+            .sum()
+    }
+
+    /// Depth-first branch and bound, pruning nodes that cannot beat `bound`.
+    fn branch_and_bound(&self, mut bound: f64, limits: &SearchLimits) -> Search {
+        let start = now_millis();
+        let is_exhausted = |nodes: u32| {
+            nodes >= limits.max_nodes
+                || limits
+                    .max_millis
+                    .is_some_and(|max| now_millis() - start >= max as f64)
+        };
+
+        let mut search = Search {
+            lots: None,
+            nodes: 1,
+            is_complete: false,
+        };
+
+        let Ok(root) = self.problem.solve() else {
+            search.is_complete = true;
+            return search;
+        };
+
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if node.objective() >= bound - OBJECTIVE_TOLERANCE {
+                continue;
+            }
+
+            let Some((var, value)) = self.branching_var(&node) else {
+                bound = node.objective();
+                search.lots = Some(
+                    self.vars
+                        .iter()
+                        .map(|v| (v.aid.clone(), node[v.var]))
+                        .collect(),
+                );
+                continue;
+            };
+
+            for (op, lots) in [
+                (ComparisonOp::Le, value.floor()),
+                (ComparisonOp::Ge, value.ceil()),
+            ] {
+                if is_exhausted(search.nodes) {
+                    return search;
+                }
+
+                search.nodes += 1;
+                if let Ok(child) = node.clone().add_constraint([(var, 1.)], op, lots) {
+                    stack.push(child);
+                }
+            }
+        }
+
+        search.is_complete = true;
+        search
+    }
+
+    /// Most fractional lot count of an integer asset, if any.
+    fn branching_var(&self, node: &minilp::Solution) -> Option<(Variable, f64)> {
+        self.vars
+            .iter()
+            .filter(|v| v.lot_size.is_some())
+            .map(|v| (v.var, node[v.var]))
+            .map(|(var, value)| (var, value, (value - value.round()).abs()))
+            .filter(|(_, _, frac)| *frac > INTEGRALITY_TOLERANCE)
+            .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .map(|(var, value, _)| (var, value))
+    }
+
+    /// Applies the traded `lots` to the greedy solution, then reconciles its
+    /// weights, costs and reports as the greedy solver does. Returns `None`
+    /// if rounding the divisible assets overspends the budget.
+    fn allocation(
+        &self,
+        problem: &advanced::Problem,
+        mut solution: advanced::Solution,
+        lots: &HashMap<String, f64>,
+        pfolio_amount: Decimal,
+    ) -> Option<advanced::Solution> {
+        let options = &problem.options;
+        for v in &self.vars {
+            let asset = solution.assets.get_mut(&v.aid).unwrap();
+            let traded = match v.lot_size {
+                Some(lot) => Decimal::from_f64(lots[&v.aid].round()).unwrap() * lot,
+                None => Decimal::from_f64(lots[&v.aid])
+                    .unwrap()
+                    .round_dp_with_strategy(SHARES_DECIMALS, RoundingStrategy::ToNegativeInfinity),
+            };
+
+            let shares = (asset.current_shares + traded).max(Decimal::ZERO);
+            let sold = (asset.current_shares - shares).max(Decimal::ZERO);
+            let sold_amount = (sold * asset.price).round_dp(AMOUNT_DECIMALS);
+            let basis = asset.average_buy_price.unwrap_or(asset.price);
+
+            asset.shares = shares;
+            asset.amount = (shares * asset.price).round_dp(AMOUNT_DECIMALS);
+            asset.sell_fees = asset.compute_sell_fee(&sold_amount, &options.fees);
+            asset.realized_gain = (sold * (asset.price - basis)).round_dp(AMOUNT_DECIMALS);
+            asset.tax = Decimal::ZERO;
+            asset.theo_alloc = None;
+        }
+
+        let spent = solution
+            .assets
+            .values()
+            .map(|a| a.amount - a.current_amount)
+            .sum::<Decimal>();
+        let budget_left = options.budget - spent;
+        if budget_left < Decimal::ZERO {
+            return None;
+        }
+
+        let sold_amount = solution
+            .assets
+            .values()
+            .map(|a| (a.current_amount - a.amount).max(Decimal::ZERO))
+            .sum::<Decimal>();
+
+        for v in &self.vars {
+            let asset = solution.assets.get_mut(&v.aid).unwrap();
+            asset.state = settled_state(asset, budget_left);
+        }
+
+        Some(problem.reconcile(
+            solution,
+            pfolio_amount,
+            budget_left,
+            options.budget + sold_amount,
+        ))
+    }
+}
+
+/// State explaining the trade of an asset in the integer optimum.
+///
+/// An asset short of its target is at its target when one more lot would
+/// overshoot it by more than the remaining gap.
+fn settled_state(asset: &Asset, budget_left: Decimal) -> SolutionState {
+    if asset.shares < asset.current_shares
+        || (asset.shares == asset.current_shares && asset.current_amount >= asset.target_amount)
+    {
+        return SolutionState::DoNotAllocate;
+    }
+
+    let gap = asset.target_amount - asset.amount;
+    if gap <= Decimal::ZERO {
+        return SolutionState::FullyAllocated;
+    }
+
+    match asset.lot_size().map(|lot| lot * asset.price) {
+        Some(lot_value) if lot_value > gap * Decimal::TWO => SolutionState::FullyAllocated,
+        Some(lot_value) if lot_value > budget_left => SolutionState::PriceTooHigh,
+        _ => SolutionState::Open,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::optimize::advanced::AllocationReason;
+
+    fn build_high_price_portfolio(limits: SearchLimits) -> ProblemOptions {
+        // Greedy buys 1 share of each asset, leaving 400 of cash
+        let options: advanced::ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "A": {
+                    "symbol": "A",
+                    "shares": 0.,
+                    "price": 300.,
+                    "targetWeight": 0.55,
+                    "isWholeShares": true,
+                    "fees": null
+                },
+                "B": {
+                    "symbol": "B",
+                    "shares": 0.,
+                    "price": 300.,
+                    "targetWeight": 0.45,
+                    "isWholeShares": true,
+                    "fees": null
+                }
+            },
+            "budget": 1000.,
+            "isBuyOnly": true
+        }))
+        .unwrap();
+
+        ProblemOptions {
+            advanced: options,
+            limits,
+        }
+    }
+
+    #[test_log::test]
+    fn it_finds_the_integer_optimum() {
+        // Given
+        let options = build_high_price_portfolio(SearchLimits::default());
+
+        // When
+        let solution = crate::optimize::solve_exact(&options).unwrap();
+
+        // Expect
+        assert!(solution.is_exact);
+        let assets = &solution.allocation.assets;
+        assert_eq!(assets["A"].shares, dec!(2));
+        assert_eq!(assets["A"].weight, dec!(0.6));
+        assert_eq!(assets["A"].reason(), AllocationReason::TargetReached);
+        assert_eq!(assets["B"].shares, dec!(1));
+        assert_eq!(assets["B"].weight, dec!(0.3));
+        assert_eq!(assets["B"].reason(), AllocationReason::PriceTooHigh);
+        assert_eq!(solution.allocation.budget_left, dec!(100));
+        assert!(solution.allocation.warnings.is_empty());
+    }
+
+    #[test_log::test]
+    fn it_explains_assets_left_untouched() {
+        // Given
+        let mut options = build_high_price_portfolio(SearchLimits::default());
+        let cash: advanced::ProblemAsset = serde_json::from_value(serde_json::json!({
+            "symbol": "C",
+            "shares": 5.,
+            "price": 10.,
+            "targetWeight": 0.,
+            "isWholeShares": true,
+            "fees": null
+        }))
+        .unwrap();
+        options.advanced.assets.insert("C".to_string(), cash);
+
+        // When
+        let solution = crate::optimize::solve_exact(&options).unwrap();
+
+        // Expect
+        assert!(solution.is_exact);
+        let assets = &solution.allocation.assets;
+        assert_eq!(assets["A"].shares, dec!(2));
+        assert_eq!(assets["B"].shares, dec!(1));
+        assert_eq!(assets["C"].shares, dec!(5));
+        assert_eq!(assets["C"].reason(), AllocationReason::AlreadyAllocated);
+    }
+
+    #[test_log::test]
+    fn it_warns_when_falling_back_to_greedy() {
+        // Given
+        let mut options = build_high_price_portfolio(SearchLimits::default());
+        options.advanced.fees = serde_json::from_value(serde_json::json!({
+            "feeStructure": { "type": "fixed", "feeAmount": 1. }
+        }))
+        .unwrap();
+
+        // When
+        let solution = crate::optimize::solve_exact(&options).unwrap();
+
+        // Expect
+        assert!(!solution.is_exact);
+        assert_eq!(
+            solution.allocation.warnings,
+            vec![SolutionWarning::GreedyFallback {
+                reason: "transaction fees not modelled".to_string()
+            }]
+        );
+    }
+
+    #[test_log::test]
+    fn it_falls_back_to_greedy_when_out_of_nodes() {
+        // Given
+        let options = build_high_price_portfolio(SearchLimits {
+            max_nodes: 1,
+            max_millis: None,
+        });
+        let greedy = crate::optimize::solve_advanced(&options.advanced).unwrap();
+        assert_eq!(greedy.budget_left, dec!(400));
+
+        // When
+        let solution = crate::optimize::solve_exact(&options).unwrap();
+
+        // Expect
+        assert!(!solution.is_exact);
+        assert_eq!(solution.explored_nodes, 1);
+        assert_eq!(
+            solution.allocation.warnings,
+            vec![SolutionWarning::GreedyFallback {
+                reason: "search limits reached".to_string()
+            }]
+        );
+        for (aid, asset) in &greedy.assets {
+            assert_eq!(solution.allocation.assets[aid].shares, asset.shares);
+        }
+        assert_eq!(solution.allocation.budget_left, greedy.budget_left);
+    }
+}
@@ -1,11 +1,83 @@
 pub mod advanced;
+pub mod backtest;
 pub mod basic;
+pub mod exact;
+pub mod planner;
+pub mod projection;
 pub mod suggestions;
+pub mod sweep;
 
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::AMOUNT_DECIMALS;
+use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS};
+
+/// Errors returned by the native optimizer API.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OptimizeError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("Problem could not be solved")]
+    Unsolved,
+}
+
+/// Validates `options` and computes the advanced allocation they describe.
+///
+/// Keeps no state across calls: the problem is built, solved and dropped.
+pub fn solve_advanced(
+    options: &advanced::ProblemOptions,
+) -> Result<advanced::Solution, OptimizeError> {
+    let options = options.validated()?;
+    let solution = advanced::Problem::new(options).solve();
+    if !solution.is_solved {
+        return Err(OptimizeError::Unsolved);
+    }
+
+    Ok(solution)
+}
+
+/// Validates `options` and computes the exact allocation they describe,
+/// falling back to the advanced one when the search runs out of budget.
+pub fn solve_exact(options: &exact::ProblemOptions) -> Result<exact::Solution, OptimizeError> {
+    let options = options.validated()?;
+    let solution = exact::Problem::new(options).solve();
+    if !solution.allocation.is_solved {
+        return Err(OptimizeError::Unsolved);
+    }
+
+    Ok(solution)
+}
+
+/// Validates `options` and computes the advanced allocation at each budget
+/// of the sweep.
+pub fn solve_sweep(options: &sweep::ProblemOptions) -> Result<sweep::Solution, OptimizeError> {
+    let options = options.validated()?;
+    sweep::Problem::new(options).solve()
+}
+
+/// Validates `options` and plans the purchases of each period.
+pub fn solve_planner(
+    options: &planner::ProblemOptions,
+) -> Result<planner::Solution, OptimizeError> {
+    let options = options.validated()?;
+    planner::Problem::new(options).solve()
+}
+
+/// Validates `options` and replays their contributions over the price series.
+pub fn solve_backtest(
+    options: &backtest::ProblemOptions,
+) -> Result<backtest::Solution, OptimizeError> {
+    let options = options.validated()?;
+    backtest::Problem::new(options).solve()
+}
+
+/// Validates `options` and simulates the paths of their projection.
+pub fn solve_projection(
+    options: &projection::ProblemOptions,
+) -> Result<projection::Solution, OptimizeError> {
+    let options = options.validated()?;
+    projection::Problem::new(options).solve()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +90,9 @@ pub struct TransactionFees {
     pub max_fee_impact: Decimal,
     #[serde(default)]
     pub fee_structure: FeeStructure,
+    /// Fee charged on sells, when different from `fee_structure`
+    #[serde(default)]
+    pub sell_fee_structure: Option<FeeStructure>,
 }
 
 impl Default for TransactionFees {
@@ -25,6 +100,7 @@ impl Default for TransactionFees {
         Self {
             max_fee_impact: Self::default_max_fee_impact(),
             fee_structure: Default::default(),
+            sell_fee_structure: None,
         }
     }
 }
@@ -33,6 +109,35 @@ impl TransactionFees {
     pub fn default_max_fee_impact() -> Decimal {
         Decimal::MAX
     }
+
+    /// Checks fee values are in range and rounds them to the optimizer precision.
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        let max_fee_impact = self.max_fee_impact;
+        if max_fee_impact != Self::default_max_fee_impact()
+            && !(Decimal::ZERO..=Decimal::ONE).contains(&max_fee_impact)
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid max_fee_impact ({max_fee_impact}). Must be in [0, 1] range"
+            )));
+        }
+
+        Ok(Self {
+            max_fee_impact: max_fee_impact.round_dp(PERCENTAGE_DECIMALS),
+            fee_structure: self.fee_structure.validated()?,
+            sell_fee_structure: self
+                .sell_fee_structure
+                .as_ref()
+                .map(FeeStructure::validated)
+                .transpose()?,
+        })
+    }
+
+    /// The fee structure applied to sells, falling back to the buy one.
+    pub fn sell_fee_structure(&self) -> &FeeStructure {
+        self.sell_fee_structure
+            .as_ref()
+            .unwrap_or(&self.fee_structure)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,9 +145,23 @@ impl TransactionFees {
 pub enum FeeStructure {
     Fixed(FeeStructureFixed),
     Variable(FeeStructureVariable),
+    Tiered(FeeStructureTiered),
+    Composite(FeeStructureComposite),
 }
 
 impl FeeStructure {
+    /// Whether no order is ever charged a fee.
+    pub fn is_free(&self) -> bool {
+        match self {
+            FeeStructure::Fixed(fee) => fee.fee_amount.is_zero(),
+            FeeStructure::Variable(fee) => {
+                fee.fee_rate.is_zero() && fee.min_fee.is_none_or(|min| min.is_zero())
+            }
+            FeeStructure::Tiered(fee) => fee.tiers.iter().all(|t| t.fee_structure.is_free()),
+            FeeStructure::Composite(fee) => fee.components.iter().all(FeeStructure::is_free),
+        }
+    }
+
     pub fn compute_fee(&self, amount: &Decimal) -> Decimal {
         match self {
             FeeStructure::Fixed(fee) => {
@@ -53,8 +172,70 @@ impl FeeStructure {
                 }
             }
             FeeStructure::Variable(fee) => fee.compute_fee(amount),
+            FeeStructure::Tiered(fee) => fee.compute_fee(amount),
+            FeeStructure::Composite(fee) => fee.compute_fee(amount),
         }
     }
+
+    /// Checks fee values are in range and rounds them to the optimizer precision.
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        let fee_structure = match self {
+            FeeStructure::Fixed(fee) => {
+                if fee.fee_amount < Decimal::ZERO {
+                    return Err(OptimizeError::InvalidInput(format!(
+                        "Invalid fee_amount ({}). Must be positive",
+                        fee.fee_amount
+                    )));
+                }
+
+                FeeStructure::Fixed(FeeStructureFixed {
+                    fee_amount: fee.fee_amount.round_dp(AMOUNT_DECIMALS),
+                })
+            }
+            FeeStructure::Variable(fee) => {
+                if !(Decimal::ZERO..=Decimal::ONE).contains(&fee.fee_rate) {
+                    return Err(OptimizeError::InvalidInput(format!(
+                        "Invalid fee_rate ({}). Must be in [0, 1] range",
+                        fee.fee_rate
+                    )));
+                }
+
+                for (name, value) in [("min_fee", fee.min_fee), ("max_fee", fee.max_fee)] {
+                    if let Some(value) = value
+                        && value < Decimal::ZERO
+                    {
+                        return Err(OptimizeError::InvalidInput(format!(
+                            "Invalid {name} ({value}). Must be positive"
+                        )));
+                    }
+                }
+
+                FeeStructure::Variable(FeeStructureVariable {
+                    min_fee: fee.min_fee.map(|f| f.round_dp(AMOUNT_DECIMALS)),
+                    max_fee: fee.max_fee.map(|f| f.round_dp(AMOUNT_DECIMALS)),
+                    fee_rate: fee.fee_rate.round_dp(PERCENTAGE_DECIMALS),
+                })
+            }
+            FeeStructure::Tiered(fee) => FeeStructure::Tiered(fee.validated()?),
+            FeeStructure::Composite(fee) => {
+                if fee.components.is_empty() {
+                    return Err(OptimizeError::InvalidInput(
+                        "Invalid composite fee. Must have at least one component".to_string(),
+                    ));
+                }
+
+                FeeStructure::Composite(FeeStructureComposite {
+                    components: fee
+                        .components
+                        .iter()
+                        .map(FeeStructure::validated)
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+        };
+
+        Ok(fee_structure)
+    }
 }
 
 impl Default for FeeStructure {
@@ -93,3 +274,254 @@ impl FeeStructureVariable {
             .round_dp(AMOUNT_DECIMALS)
     }
 }
+
+/// Fee charged by amount brackets: an order pays the fee of the first tier
+/// whose upper bound covers its amount.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeStructureTiered {
+    pub tiers: Vec<FeeTier>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeTier {
+    /// Inclusive upper bound of the order amounts in this tier. Only the last
+    /// tier may be unbounded.
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub up_to: Option<Decimal>,
+    pub fee_structure: FeeStructure,
+}
+
+impl FeeStructureTiered {
+    pub fn compute_fee(&self, amount: &Decimal) -> Decimal {
+        // Amounts above the last bound fall in the last tier
+        self.tiers
+            .iter()
+            .find(|t| t.up_to.is_none_or(|up_to| *amount <= up_to))
+            .or(self.tiers.last())
+            .map(|t| t.fee_structure.compute_fee(amount))
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    fn validated(&self) -> Result<Self, OptimizeError> {
+        if self.tiers.is_empty() {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid tiered fee. Must have at least one tier".to_string(),
+            ));
+        }
+
+        let mut last_bound = Decimal::ZERO;
+        let mut tiers = Vec::with_capacity(self.tiers.len());
+        for (i, tier) in self.tiers.iter().enumerate() {
+            match tier.up_to {
+                Some(up_to) if up_to <= last_bound => {
+                    return Err(OptimizeError::InvalidInput(format!(
+                        "Invalid tier bound ({up_to}). Must be positive and increasing"
+                    )));
+                }
+                Some(up_to) => last_bound = up_to,
+                None if i + 1 < self.tiers.len() => {
+                    return Err(OptimizeError::InvalidInput(
+                        "Invalid tiered fee. Only the last tier may be unbounded".to_string(),
+                    ));
+                }
+                None => {}
+            }
+
+            tiers.push(FeeTier {
+                up_to: tier.up_to.map(|b| b.round_dp(AMOUNT_DECIMALS)),
+                fee_structure: tier.fee_structure.validated()?,
+            });
+        }
+
+        Ok(Self { tiers })
+    }
+}
+
+/// Fee made of several parts charged together, e.g. a fixed fee plus a rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeStructureComposite {
+    pub components: Vec<FeeStructure>,
+}
+
+impl FeeStructureComposite {
+    pub fn compute_fee(&self, amount: &Decimal) -> Decimal {
+        self.components
+            .iter()
+            .map(|c| c.compute_fee(amount))
+            .sum::<Decimal>()
+            .round_dp(AMOUNT_DECIMALS)
+    }
+}
+
+/// Drift allowed around a target weight before an asset is rebalanced, e.g.
+/// the "5/25" rule is an absolute band of 0.05 and a relative band of 0.25.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToleranceBand {
+    /// Maximum drift of the weight, in weight units (in [0..1] range)
+    #[serde(default)]
+    pub absolute: Option<Decimal>,
+    /// Maximum drift of the weight, as a rate of the target weight
+    #[serde(default)]
+    pub relative: Option<Decimal>,
+}
+
+impl ToleranceBand {
+    /// Checks band values are in range and rounds them to the optimizer precision.
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        if let Some(absolute) = self.absolute
+            && !(Decimal::ZERO..=Decimal::ONE).contains(&absolute)
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid absolute tolerance band ({absolute}). Must be in [0, 1] range"
+            )));
+        }
+
+        if let Some(relative) = self.relative
+            && relative < Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid relative tolerance band ({relative}). Must be zero or positive"
+            )));
+        }
+
+        Ok(Self {
+            absolute: self.absolute.map(|b| b.round_dp(PERCENTAGE_DECIMALS)),
+            relative: self.relative.map(|b| b.round_dp(PERCENTAGE_DECIMALS)),
+        })
+    }
+
+    /// Half-width of the band around `target_weight`: the tighter of the
+    /// absolute and relative bounds.
+    pub fn width(&self, target_weight: Decimal) -> Decimal {
+        let relative = self.relative.map(|r| r * target_weight);
+        match (self.absolute, relative) {
+            (Some(a), Some(r)) => a.min(r),
+            (Some(w), None) | (None, Some(w)) => w,
+            (None, None) => Decimal::ZERO,
+        }
+        .round_dp(PERCENTAGE_DECIMALS)
+    }
+
+    pub fn contains(&self, weight: Decimal, target_weight: Decimal) -> bool {
+        (weight - target_weight).abs() <= self.width(target_weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn fixed(fee_amount: Decimal) -> FeeStructure {
+        FeeStructure::Fixed(FeeStructureFixed { fee_amount })
+    }
+
+    fn variable(fee_rate: Decimal) -> FeeStructure {
+        FeeStructure::Variable(FeeStructureVariable {
+            min_fee: None,
+            max_fee: None,
+            fee_rate,
+        })
+    }
+
+    #[test]
+    fn it_computes_tiered_and_composite_fees() {
+        // 2€ up to 1000€, 0.1% above
+        let tiered = FeeStructure::Tiered(FeeStructureTiered {
+            tiers: vec![
+                FeeTier {
+                    up_to: Some(dec!(1000)),
+                    fee_structure: fixed(dec!(2)),
+                },
+                FeeTier {
+                    up_to: None,
+                    fee_structure: variable(dec!(0.001)),
+                },
+            ],
+        });
+        assert_eq!(tiered.compute_fee(&dec!(0)), dec!(0));
+        assert_eq!(tiered.compute_fee(&dec!(500)), dec!(2));
+        assert_eq!(tiered.compute_fee(&dec!(1000)), dec!(2));
+        assert_eq!(tiered.compute_fee(&dec!(5000)), dec!(5));
+
+        // 1€ plus 0.25%
+        let composite = FeeStructure::Composite(FeeStructureComposite {
+            components: vec![fixed(dec!(1)), variable(dec!(0.0025))],
+        });
+        assert_eq!(composite.compute_fee(&dec!(1000)), dec!(3.5));
+
+        let parsed: FeeStructure = serde_json::from_value(serde_json::json!({
+            "type": "tiered",
+            "tiers": [
+                { "upTo": 1000., "feeStructure": { "type": "fixed", "feeAmount": 2. } },
+                { "feeStructure": { "type": "variable", "feeRate": 0.001, "minFee": 0., "maxFee": 100. } }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(parsed.compute_fee(&dec!(5000)), dec!(5));
+    }
+
+    #[test]
+    fn it_checks_weights_against_tolerance_bands() {
+        // 5/25 rule
+        let band = ToleranceBand {
+            absolute: Some(dec!(0.05)),
+            relative: Some(dec!(0.25)),
+        };
+        assert_eq!(band.width(dec!(0.6)), dec!(0.05));
+        assert_eq!(band.width(dec!(0.1)), dec!(0.025));
+        assert!(band.contains(dec!(0.64), dec!(0.6)));
+        assert!(!band.contains(dec!(0.66), dec!(0.6)));
+        assert!(band.contains(dec!(0.08), dec!(0.1)));
+        assert!(!band.contains(dec!(0.07), dec!(0.1)));
+
+        assert!(!ToleranceBand::default().contains(dec!(0.61), dec!(0.6)));
+        let invalid = ToleranceBand {
+            absolute: Some(dec!(1.5)),
+            relative: None,
+        };
+        assert!(invalid.validated().is_err());
+    }
+
+    #[test]
+    fn it_rejects_invalid_tiers() {
+        let tiered = |bounds: &[Option<Decimal>]| {
+            FeeStructure::Tiered(FeeStructureTiered {
+                tiers: bounds
+                    .iter()
+                    .map(|up_to| FeeTier {
+                        up_to: *up_to,
+                        fee_structure: fixed(dec!(1)),
+                    })
+                    .collect(),
+            })
+        };
+
+        assert!(tiered(&[Some(dec!(100)), None]).validated().is_ok());
+        assert!(
+            tiered(&[Some(dec!(100)), Some(dec!(200))])
+                .validated()
+                .is_ok()
+        );
+        assert!(tiered(&[]).validated().is_err());
+        assert!(tiered(&[None, Some(dec!(100))]).validated().is_err());
+        assert!(
+            tiered(&[Some(dec!(200)), Some(dec!(100))])
+                .validated()
+                .is_err()
+        );
+        assert!(tiered(&[Some(dec!(0))]).validated().is_err());
+
+        let empty = FeeStructure::Composite(FeeStructureComposite { components: vec![] });
+        assert!(empty.validated().is_err());
+        let nested = FeeStructure::Composite(FeeStructureComposite {
+            components: vec![variable(dec!(2))],
+        });
+        assert!(nested.validated().is_err());
+    }
+}
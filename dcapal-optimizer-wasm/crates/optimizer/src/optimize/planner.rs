@@ -0,0 +1,384 @@
+//! Multi-period DCA planning.
+//!
+//! Splits a recurring contribution across several purchase dates so that
+//! fixed order fees are not paid on every asset at every period. Each period
+//! is an [`advanced`] problem: the planner tries buying the most underweight
+//! assets only, deferring the others and keeping their budget as cash, and
+//! picks the cheapest plan whose drift stays within the bound. Periods are
+//! planned one after the other, with prices assumed constant.
+
+use std::collections::{HashMap, HashSet};
+
+use log::debug;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{OptimizeError, advanced};
+use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS};
+
+/// Largest number of planned periods, i.e. ten years of monthly contributions.
+pub const MAX_PERIODS: u32 = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemOptions {
+    /// Problem of the first period. Its `budget` is the contribution added
+    /// at every period.
+    #[serde(flatten)]
+    pub advanced: advanced::ProblemOptions,
+    pub periods: u32,
+    /// Largest distance of an asset weight from its target tolerated at the
+    /// end of every period, in weight units.
+    pub max_drift: Decimal,
+}
+
+impl ProblemOptions {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        if !(1..=MAX_PERIODS).contains(&self.periods) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid periods ({}). Must be in [1, {MAX_PERIODS}] range",
+                self.periods
+            )));
+        }
+
+        if !(Decimal::ZERO..=Decimal::ONE).contains(&self.max_drift) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid max_drift ({}). Must be in [0, 1] range",
+                self.max_drift
+            )));
+        }
+
+        let advanced = self.advanced.validated()?;
+        if advanced.budget < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid contribution ({}). Must be non-negative",
+                advanced.budget
+            )));
+        }
+
+        Ok(Self {
+            advanced,
+            periods: self.periods,
+            max_drift: self.max_drift.round_dp(PERCENTAGE_DECIMALS),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Solution {
+    pub periods: Vec<PlannedPeriod>,
+    pub total_fees: Decimal,
+    /// Whether every period ends within the drift bound.
+    pub is_within_bound: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedPeriod {
+    /// Cash available: the contribution plus the cash left by the previous
+    /// period.
+    pub budget: Decimal,
+    /// Trades of the period, by asset id. Deferred assets are missing.
+    pub trades: HashMap<String, PlannedTrade>,
+    pub fees: Decimal,
+    /// Cash carried to the next period.
+    pub cash_left: Decimal,
+    /// Largest distance of an asset weight from its target at period end.
+    pub max_drift: Decimal,
+    pub is_within_bound: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedTrade {
+    /// Shares bought, negative if sold.
+    pub shares: Decimal,
+    /// Traded amount, negative if sold.
+    pub amount: Decimal,
+    pub fees: Decimal,
+}
+
+pub struct Problem {
+    pub(crate) options: ProblemOptions,
+}
+
+impl Problem {
+    pub fn new(options: ProblemOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn solve(&self) -> Result<Solution, OptimizeError> {
+        let contribution = self.options.advanced.budget;
+        let mut options = self.options.advanced.clone();
+        let mut periods = Vec::with_capacity(self.options.periods as usize);
+
+        for period in 0..self.options.periods {
+            let solution = self.plan_period(&options)?;
+            let planned = planned_period(&options, &solution, self.options.max_drift);
+
+            debug!("[Planner] period={period} planned={planned:?}");
+
+            for (aid, asset) in &mut options.assets {
+                let shares = solution.assets[aid].shares;
+                let bought = shares - asset.shares;
+                if bought > Decimal::ZERO {
+                    asset.average_buy_price = asset.average_buy_price.map(|avg| {
+                        ((avg * asset.shares + asset.price * bought) / shares)
+                            .round_dp(AMOUNT_DECIMALS)
+                    });
+                }
+                asset.shares = shares;
+            }
+            options.budget = planned.cash_left + contribution;
+            options = options.validated()?;
+
+            periods.push(planned);
+        }
+
+        Ok(Solution {
+            total_fees: periods.iter().map(|p| p.fees).sum(),
+            is_within_bound: periods.iter().all(|p| p.is_within_bound),
+            periods,
+        })
+    }
+
+    /// Cheapest allocation of the period within the drift bound, or the one
+    /// with the lowest drift if none is.
+    fn plan_period(
+        &self,
+        options: &advanced::ProblemOptions,
+    ) -> Result<advanced::Solution, OptimizeError> {
+        let full = solve(options, HashSet::new())?;
+
+        // Candidates buy the `k` most underweight of the assets bought by
+        // the full allocation, from all of them down to none
+        let mut bought = full
+            .assets
+            .iter()
+            .filter(|(_, a)| a.shares > a.current_shares)
+            .map(|(aid, a)| (aid.clone(), a.current_weight - a.target_weight))
+            .collect::<Vec<_>>();
+        bought.sort_by(|(a_id, a), (b_id, b)| a.cmp(b).then_with(|| a_id.cmp(b_id)));
+
+        let mut candidates = vec![full];
+        for k in (0..bought.len()).rev() {
+            let deferred = bought[k..].iter().map(|(aid, _)| aid.clone()).collect();
+            candidates.push(solve(options, deferred)?);
+        }
+
+        let max_drift = self.options.max_drift;
+        let best = candidates
+            .into_iter()
+            .map(|s| (fees(&s), drift(&s), s))
+            .min_by(|(a_fees, a_drift, _), (b_fees, b_drift, _)| {
+                match (*a_drift <= max_drift, *b_drift <= max_drift) {
+                    (true, true) => a_fees.cmp(b_fees).then(a_drift.cmp(b_drift)),
+                    (false, false) => a_drift.cmp(b_drift).then(a_fees.cmp(b_fees)),
+                    (a_within, b_within) => b_within.cmp(&a_within),
+                }
+            })
+            .map(|(_, _, s)| s)
+            .unwrap();
+
+        Ok(best)
+    }
+}
+
+/// Solves the period leaving the `deferred` assets untraded. Their budget is
+/// left to the others.
+fn solve(
+    options: &advanced::ProblemOptions,
+    deferred: HashSet<String>,
+) -> Result<advanced::Solution, OptimizeError> {
+    let solution = advanced::Problem::with_frozen(options.clone(), deferred).solve();
+    if !solution.is_solved {
+        return Err(OptimizeError::Unsolved);
+    }
+
+    Ok(solution)
+}
+
+fn fees(solution: &advanced::Solution) -> Decimal {
+    solution.assets.values().map(|a| a.trade_fees).sum()
+}
+
+fn drift(solution: &advanced::Solution) -> Decimal {
+    solution
+        .assets
+        .values()
+        .map(|a| a.drift().abs())
+        .max()
+        .unwrap_or_default()
+}
+
+fn planned_period(
+    options: &advanced::ProblemOptions,
+    solution: &advanced::Solution,
+    max_drift: Decimal,
+) -> PlannedPeriod {
+    let trades = solution
+        .assets
+        .iter()
+        .filter(|(_, a)| a.shares != a.current_shares)
+        .map(|(aid, a)| {
+            let trade = PlannedTrade {
+                shares: a.shares - a.current_shares,
+                amount: a.get_allocated_amount(),
+                fees: a.trade_fees,
+            };
+            (aid.clone(), trade)
+        })
+        .collect();
+
+    let max_period_drift = drift(solution);
+    PlannedPeriod {
+        budget: options.budget,
+        trades,
+        fees: fees(solution),
+        cash_left: solution.budget_left,
+        max_drift: max_period_drift,
+        is_within_bound: max_period_drift <= max_drift,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn build_fixed_fee_portfolio(periods: u32, max_drift: Decimal) -> ProblemOptions {
+        let options: advanced::ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "A": {
+                    "symbol": "A",
+                    "shares": 480.,
+                    "price": 1.,
+                    "targetWeight": 0.5,
+                    "fees": null
+                },
+                "B": {
+                    "symbol": "B",
+                    "shares": 420.,
+                    "price": 1.,
+                    "targetWeight": 0.5,
+                    "fees": null
+                }
+            },
+            "budget": 100.,
+            "fees": {
+                "feeStructure": {
+                    "type": "fixed",
+                    "feeAmount": 1.
+                }
+            },
+            "isBuyOnly": true
+        }))
+        .unwrap();
+
+        ProblemOptions {
+            advanced: options,
+            periods,
+            max_drift,
+        }
+    }
+
+    #[test_log::test]
+    fn it_defers_purchases_within_drift_bound() {
+        // Given
+        let options = build_fixed_fee_portfolio(2, dec!(0.03));
+
+        // When
+        let solution = crate::optimize::solve_planner(&options).unwrap();
+
+        // Expect
+        assert!(solution.is_within_bound);
+        assert_eq!(solution.total_fees, dec!(3));
+
+        // Only the most underweight asset is bought, A is deferred
+        let first = &solution.periods[0];
+        assert_eq!(first.budget, dec!(100));
+        assert_eq!(first.trades.len(), 1);
+        assert_eq!(first.trades["B"].shares, dec!(80));
+        assert_eq!(first.fees, dec!(1));
+        assert_eq!(first.cash_left, dec!(20));
+        assert_eq!(first.max_drift, dec!(0.02));
+
+        // Deferring A again would exceed the bound
+        let second = &solution.periods[1];
+        assert_eq!(second.budget, dec!(120));
+        assert_eq!(second.trades.len(), 2);
+        assert_eq!(second.fees, dec!(2));
+    }
+
+    #[test_log::test]
+    fn it_buys_every_asset_with_a_tight_bound() {
+        // Given
+        let options = build_fixed_fee_portfolio(2, Decimal::ZERO);
+
+        // When
+        let solution = crate::optimize::solve_planner(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.periods[0].trades.len(), 2);
+        assert_eq!(solution.periods[0].max_drift, Decimal::ZERO);
+        assert!(solution.periods[0].is_within_bound);
+    }
+
+    #[test_log::test]
+    fn it_does_not_trade_deferred_free_assets() {
+        // Given
+        let mut options = build_fixed_fee_portfolio(1, dec!(0.03)).advanced;
+        options.assets.get_mut("B").unwrap().fees = Some(
+            serde_json::from_value(serde_json::json!({
+                "feeStructure": {
+                    "type": "fixed",
+                    "feeAmount": 0.
+                }
+            }))
+            .unwrap(),
+        );
+        let options = options.validated().unwrap();
+
+        // When
+        let solution = solve(&options, HashSet::from(["B".to_string()])).unwrap();
+
+        // Expect
+        let (a, b) = (&solution.assets["A"], &solution.assets["B"]);
+        assert_eq!(b.shares, b.current_shares);
+        assert_eq!(b.trade_fees, Decimal::ZERO);
+        assert!(a.shares > a.current_shares);
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_plans() {
+        // Given
+        let options = build_fixed_fee_portfolio(0, dec!(0.03));
+
+        // When
+        let res = crate::optimize::solve_planner(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        let options = build_fixed_fee_portfolio(MAX_PERIODS + 1, dec!(0.03));
+
+        // When
+        let res = crate::optimize::solve_planner(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        let options = build_fixed_fee_portfolio(2, dec!(1.5));
+
+        // When
+        let res = crate::optimize::solve_planner(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+    }
+}
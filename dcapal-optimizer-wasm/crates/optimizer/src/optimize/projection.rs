@@ -0,0 +1,531 @@
+//! Monte Carlo goal projection.
+//!
+//! Simulates the value of a portfolio split across asset classes, each with
+//! its own expected return and volatility, under a periodic contribution plan.
+//! Every path draws independent log-normal returns per class and period, and
+//! the portfolio is rebalanced to its target weights at each contribution.
+//! Reports percentile bands of the value at the end of every year and the
+//! probability of reaching a goal by the end of the projection.
+
+use std::collections::HashMap;
+
+use log::debug;
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{OptimizeError, backtest::PriceSample};
+use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS};
+
+/// Largest number of simulated paths.
+pub const MAX_PATHS: u32 = 10_000;
+/// Largest projection horizon, in years.
+pub const MAX_YEARS: u32 = 100;
+/// Largest number of contribution periods per year, i.e. daily.
+pub const MAX_PERIODS_PER_YEAR: u32 = 365;
+/// Largest number of periods simulated across all paths.
+pub const MAX_SIMULATED_PERIODS: u64 = 10_000_000;
+/// Largest yearly volatility, well above the one of the most volatile crypto
+/// assets.
+pub const MAX_VOLATILITY: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+/// Smallest number of samples a return model is estimated from.
+pub const MIN_ESTIMATE_SAMPLES: usize = 20;
+
+const PERCENTILES: [f64; 5] = [0.05, 0.25, 0.5, 0.75, 0.95];
+const SECONDS_PER_YEAR: f64 = 365.25 * 24. * 60. * 60.;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemOptions {
+    /// Asset classes, by id.
+    pub classes: HashMap<String, AssetClass>,
+    /// Portfolio value at the start of the projection.
+    pub initial_value: Decimal,
+    /// Amount contributed at the end of every period.
+    pub contribution: Decimal,
+    pub periods_per_year: u32,
+    pub years: u32,
+    /// Number of simulated paths.
+    pub paths: u32,
+    /// Seed of the random generator. Equal seeds replay the same paths.
+    pub seed: u64,
+    /// Portfolio value to reach by the end of the projection.
+    pub goal: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetClass {
+    pub target_weight: Decimal,
+    #[serde(flatten)]
+    pub returns: ReturnModel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReturnModel {
+    /// Mean yearly return, as a rate.
+    pub expected_return: Decimal,
+    /// Standard deviation of the yearly log return.
+    pub volatility: Decimal,
+}
+
+impl ReturnModel {
+    /// Estimates the yearly return model of a price series from the log
+    /// returns between its samples, however far apart.
+    pub fn estimate(series: &[PriceSample]) -> Result<Self, OptimizeError> {
+        let mut series = series.to_vec();
+        series.sort_by_key(|s| s.timestamp);
+        series.dedup_by_key(|s| s.timestamp);
+
+        if series.len() < MIN_ESTIMATE_SAMPLES {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Not enough prices to estimate returns ({} instead of at least {MIN_ESTIMATE_SAMPLES})",
+                series.len()
+            )));
+        }
+
+        if let Some(s) = series.iter().find(|s| s.price <= Decimal::ZERO) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid price at {} ({}). Must be positive",
+                s.timestamp, s.price
+            )));
+        }
+
+        // Log return and elapsed years between consecutive samples
+        let returns = series
+            .windows(2)
+            .map(|w| {
+                let ratio = w[1].price / w[0].price;
+                let elapsed = (w[1].timestamp - w[0].timestamp) as f64 / SECONDS_PER_YEAR;
+                (ratio.to_f64().unwrap().ln(), elapsed)
+            })
+            .collect::<Vec<_>>();
+
+        let years = returns.iter().map(|(_, dt)| dt).sum::<f64>();
+        let drift = returns.iter().map(|(r, _)| r).sum::<f64>() / years;
+        let variance = returns
+            .iter()
+            .map(|(r, dt)| (r - drift * dt).powi(2))
+            .sum::<f64>()
+            / years;
+
+        Ok(Self {
+            expected_return: to_percentage((drift + variance / 2.).exp() - 1.),
+            volatility: to_percentage(variance.sqrt()),
+        })
+    }
+
+    fn validated(&self, id: &str) -> Result<Self, OptimizeError> {
+        if self.expected_return <= Decimal::NEGATIVE_ONE {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid expected return for {id} ({}). Must be greater than -1",
+                self.expected_return
+            )));
+        }
+
+        if !(Decimal::ZERO..=MAX_VOLATILITY).contains(&self.volatility) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid volatility for {id} ({}). Must be in [0, {MAX_VOLATILITY}] range",
+                self.volatility
+            )));
+        }
+
+        Ok(Self {
+            expected_return: self.expected_return.round_dp(PERCENTAGE_DECIMALS),
+            volatility: self.volatility.round_dp(PERCENTAGE_DECIMALS),
+        })
+    }
+}
+
+impl ProblemOptions {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        if self.classes.is_empty() {
+            return Err(OptimizeError::InvalidInput(
+                "Missing asset classes".to_string(),
+            ));
+        }
+
+        let mut classes = HashMap::with_capacity(self.classes.len());
+        for (id, class) in &self.classes {
+            if class.target_weight < Decimal::ZERO {
+                return Err(OptimizeError::InvalidInput(format!(
+                    "Invalid target weight for {id} ({}). Must be non-negative",
+                    class.target_weight
+                )));
+            }
+
+            let class = AssetClass {
+                target_weight: class.target_weight.round_dp(PERCENTAGE_DECIMALS),
+                returns: class.returns.validated(id)?,
+            };
+            classes.insert(id.clone(), class);
+        }
+
+        let target_total = classes
+            .values()
+            .map(|c| c.target_weight)
+            .sum::<Decimal>()
+            .round_dp(AMOUNT_DECIMALS);
+
+        if target_total != Decimal::ONE {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid target weights. Sum must be equal to 1 ({target_total} instead)"
+            )));
+        }
+
+        if self.initial_value < Decimal::ZERO || self.contribution < Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid initial value ({}) or contribution ({}). Must be non-negative",
+                self.initial_value, self.contribution
+            )));
+        }
+
+        if !(1..=MAX_PERIODS_PER_YEAR).contains(&self.periods_per_year) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid periods per year ({}). Must be in [1, {MAX_PERIODS_PER_YEAR}] range",
+                self.periods_per_year
+            )));
+        }
+
+        if !(1..=MAX_YEARS).contains(&self.years) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid years ({}). Must be in [1, {MAX_YEARS}] range",
+                self.years
+            )));
+        }
+
+        if !(1..=MAX_PATHS).contains(&self.paths) {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid paths ({}). Must be in [1, {MAX_PATHS}] range",
+                self.paths
+            )));
+        }
+
+        let simulated =
+            u64::from(self.paths) * u64::from(self.years) * u64::from(self.periods_per_year);
+        if simulated > MAX_SIMULATED_PERIODS {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Too many simulated periods ({simulated}). Paths times years times periods per year must be at most {MAX_SIMULATED_PERIODS}"
+            )));
+        }
+
+        if let Some(goal) = self.goal
+            && goal <= Decimal::ZERO
+        {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid goal ({goal}). Must be positive"
+            )));
+        }
+
+        Ok(Self {
+            classes,
+            initial_value: self.initial_value.round_dp(AMOUNT_DECIMALS),
+            contribution: self.contribution.round_dp(AMOUNT_DECIMALS),
+            periods_per_year: self.periods_per_year,
+            years: self.years,
+            paths: self.paths,
+            seed: self.seed,
+            goal: self.goal.map(|g| g.round_dp(AMOUNT_DECIMALS)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Solution {
+    /// Value percentiles at the start and at the end of every year.
+    pub bands: Vec<ProjectionBand>,
+    /// Share of the paths whose final value reaches the goal, if any.
+    pub goal_probability: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectionBand {
+    pub year: u32,
+    /// Initial value plus the contributions made so far.
+    pub invested: Decimal,
+    pub p5: Decimal,
+    pub p25: Decimal,
+    pub p50: Decimal,
+    pub p75: Decimal,
+    pub p95: Decimal,
+}
+
+pub struct Problem {
+    pub(crate) options: ProblemOptions,
+}
+
+impl Problem {
+    pub fn new(options: ProblemOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn solve(&self) -> Result<Solution, OptimizeError> {
+        let options = &self.options;
+        let dt = 1. / f64::from(options.periods_per_year);
+        let contribution = options.contribution.to_f64().unwrap();
+
+        // Classes are drawn in id order, so that a seed replays the same paths
+        let mut classes = options.classes.iter().collect::<Vec<_>>();
+        classes.sort_by_key(|(id, _)| *id);
+        let classes = classes
+            .into_iter()
+            .map(|(_, c)| {
+                let volatility = c.returns.volatility.to_f64().unwrap();
+                let growth = c.returns.expected_return.to_f64().unwrap().ln_1p();
+                (
+                    c.target_weight.to_f64().unwrap(),
+                    (growth - volatility.powi(2) / 2.) * dt,
+                    volatility * dt.sqrt(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(options.seed);
+        let mut values = vec![options.initial_value.to_f64().unwrap(); options.paths as usize];
+        let mut bands = Vec::with_capacity(options.years as usize + 1);
+        bands.push(self.band(0, &values));
+
+        for year in 1..=options.years {
+            for _ in 0..options.periods_per_year {
+                for value in &mut values {
+                    let growth = classes
+                        .iter()
+                        .map(|(weight, drift, scale)| {
+                            weight * (drift + scale * standard_normal(&mut rng)).exp()
+                        })
+                        .sum::<f64>();
+                    *value = *value * growth + contribution;
+                }
+            }
+
+            let band = self.band(year, &values);
+            debug!("[Projection] year={year} band={band:?}");
+            bands.push(band);
+        }
+
+        let goal_probability = options.goal.map(|goal| {
+            let goal = goal.to_f64().unwrap();
+            let reached = values.iter().filter(|v| **v >= goal).count();
+            to_percentage(reached as f64 / values.len() as f64)
+        });
+
+        Ok(Solution {
+            bands,
+            goal_probability,
+        })
+    }
+
+    fn band(&self, year: u32, values: &[f64]) -> ProjectionBand {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let [p5, p25, p50, p75, p95] = PERCENTILES.map(|p| to_amount(percentile(&sorted, p)));
+
+        let periods = Decimal::from(year * self.options.periods_per_year);
+        ProjectionBand {
+            year,
+            invested: self.options.initial_value + self.options.contribution * periods,
+            p5,
+            p25,
+            p50,
+            p75,
+            p95,
+        }
+    }
+}
+
+/// Draws a standard normal sample with the Box-Muller transform.
+fn standard_normal(rng: &mut impl RngExt) -> f64 {
+    // Uniform samples in (0, 1], as the logarithm of 0 is undefined
+    let u1 = 1. - rng.random::<f64>();
+    let u2 = rng.random::<f64>();
+    (-2. * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+/// Linearly interpolated `p` percentile of the `sorted` values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn to_amount(value: f64) -> Decimal {
+    Decimal::from_f64(value)
+        .unwrap_or(Decimal::MAX)
+        .round_dp(AMOUNT_DECIMALS)
+}
+
+fn to_percentage(value: f64) -> Decimal {
+    Decimal::from_f64(value)
+        .unwrap_or_default()
+        .round_dp(PERCENTAGE_DECIMALS)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn build_projection(volatility: Decimal, goal: Option<Decimal>) -> ProblemOptions {
+        let class = |target_weight| AssetClass {
+            target_weight,
+            returns: ReturnModel {
+                expected_return: dec!(0.1),
+                volatility,
+            },
+        };
+
+        ProblemOptions {
+            classes: HashMap::from([
+                ("equity".to_string(), class(dec!(0.6))),
+                ("bonds".to_string(), class(dec!(0.4))),
+            ]),
+            initial_value: dec!(1000),
+            contribution: dec!(100),
+            periods_per_year: 1,
+            years: 2,
+            paths: 500,
+            seed: 42,
+            goal,
+        }
+    }
+
+    #[test_log::test]
+    fn it_compounds_expected_returns_without_volatility() {
+        // Given
+        let options = build_projection(Decimal::ZERO, Some(dec!(1400)));
+
+        // When
+        let solution = crate::optimize::solve_projection(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.bands.len(), 3);
+        assert_eq!(solution.bands[0].p50, dec!(1000));
+
+        // 1000 * 1.1 + 100 = 1200, then 1200 * 1.1 + 100 = 1420
+        let last = solution.bands.last().unwrap();
+        assert_eq!(last.invested, dec!(1200));
+        assert_eq!(last.p5, dec!(1420));
+        assert_eq!(last.p95, dec!(1420));
+        assert_eq!(solution.goal_probability, Some(Decimal::ONE));
+
+        // Given
+        let options = build_projection(Decimal::ZERO, Some(dec!(1500)));
+
+        // When
+        let solution = crate::optimize::solve_projection(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.goal_probability, Some(Decimal::ZERO));
+    }
+
+    #[test_log::test]
+    fn it_replays_paths_of_a_seed() {
+        // Given
+        let options = build_projection(dec!(0.2), Some(dec!(1420)));
+
+        // When
+        let first = crate::optimize::solve_projection(&options).unwrap();
+        let second = crate::optimize::solve_projection(&options).unwrap();
+
+        // Expect
+        let last = first.bands.last().unwrap();
+        assert_eq!(last.p50, second.bands.last().unwrap().p50);
+        assert_eq!(first.goal_probability, second.goal_probability);
+
+        // Bands widen over time around the expected value
+        let spread = |b: &ProjectionBand| b.p95 - b.p5;
+        assert!(spread(&first.bands[1]) < spread(last));
+        assert!(last.p5 < last.p25 && last.p25 < last.p50);
+        assert!(last.p50 < last.p75 && last.p75 < last.p95);
+        assert!(last.p5 < dec!(1420) && dec!(1420) < last.p95);
+
+        let probability = first.goal_probability.unwrap();
+        assert!(dec!(0.3) < probability && probability < dec!(0.7));
+    }
+
+    #[test_log::test]
+    fn it_estimates_returns_of_a_price_series() {
+        // Given: daily prices growing 10% a year
+        let series = (0..730)
+            .map(|day| PriceSample {
+                timestamp: day * 24 * 60 * 60,
+                price: Decimal::from_f64(100. * 1.1_f64.powf(day as f64 / 365.25)).unwrap(),
+            })
+            .collect::<Vec<_>>();
+
+        // When
+        let model = ReturnModel::estimate(&series).unwrap();
+
+        // Expect
+        assert!((model.expected_return - dec!(0.1)).abs() < dec!(0.001));
+        assert!(model.volatility < dec!(0.001));
+
+        // When
+        let res = ReturnModel::estimate(&series[..MIN_ESTIMATE_SAMPLES - 1]);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_projections() {
+        // Given
+        let mut options = build_projection(dec!(0.2), None);
+        options.classes.get_mut("bonds").unwrap().target_weight = dec!(0.5);
+
+        // When
+        let res = crate::optimize::solve_projection(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        let mut options = build_projection(dec!(0.2), None);
+        options.paths = 0;
+
+        // When
+        let res = crate::optimize::solve_projection(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        let mut options = build_projection(dec!(0.2), None);
+        options.periods_per_year = MAX_PERIODS_PER_YEAR + 1;
+
+        // When
+        let res = crate::optimize::solve_projection(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        let mut options = build_projection(dec!(0.2), None);
+        options.paths = MAX_PATHS;
+        options.years = MAX_YEARS;
+        options.periods_per_year = 12;
+
+        // When
+        let res = crate::optimize::solve_projection(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+    }
+
+    #[test_log::test]
+    fn it_accepts_the_volatility_of_crypto_assets() {
+        // Given
+        let options = build_projection(dec!(1.2), Some(dec!(1000)));
+
+        // When
+        let solution = crate::optimize::solve_projection(&options).unwrap();
+
+        // Expect
+        assert_eq!(solution.bands.len(), 3);
+        assert!(solution.goal_probability.is_some());
+    }
+}
@@ -2,6 +2,7 @@ use std::{cmp::Ordering, collections::HashMap};
 
 use rust_decimal::Decimal;
 
+use super::ToleranceBand;
 use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS};
 
 pub struct Problem {
@@ -12,6 +13,8 @@ pub struct Problem {
 pub struct ProblemOptions {
     pub current_pfolio_amount: Decimal,
     pub assets: HashMap<String, ProblemAsset>,
+    /// Drift tolerated around target weights, unless overridden by an asset.
+    pub tolerance_band: Option<ToleranceBand>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +24,8 @@ pub struct ProblemAsset {
     pub price: Decimal,
     pub target_weight: Decimal,
     pub is_whole_shares: bool,
+    /// Drift tolerated around the target weight, overriding the portfolio one.
+    pub tolerance_band: Option<ToleranceBand>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,16 +48,23 @@ pub struct Asset {
     pub shares: Decimal,
     pub amount: Decimal,
     pub weight: Decimal,
+    /// Whether a tolerance band applies and the current weight is outside it.
+    pub is_out_of_band: bool,
 }
 
 impl Asset {
-    pub fn new(asset: ProblemAsset, current_pfolio_amount: Decimal) -> Self {
+    pub fn new(
+        asset: ProblemAsset,
+        current_pfolio_amount: Decimal,
+        pfolio_band: Option<&ToleranceBand>,
+    ) -> Self {
         let ProblemAsset {
             symbol,
             shares,
             price,
             target_weight,
             is_whole_shares,
+            tolerance_band,
         } = asset;
 
         let pfolio_amount = current_pfolio_amount;
@@ -67,6 +79,10 @@ impl Asset {
         let target_amount = (target_weight * pfolio_amount).round_dp(AMOUNT_DECIMALS);
         let amount = current_amount;
         let weight = current_weight;
+        let is_out_of_band = tolerance_band
+            .as_ref()
+            .or(pfolio_band)
+            .is_some_and(|band| !band.contains(current_weight, target_weight));
 
         Asset {
             symbol,
@@ -80,6 +96,7 @@ impl Asset {
             shares,
             amount,
             weight,
+            is_out_of_band,
         }
     }
     pub fn get_allocated_amount(&self) -> Decimal {
@@ -90,11 +107,12 @@ impl Asset {
 impl Solution {
     pub fn new(options: ProblemOptions) -> Self {
         let pfolio_amount = options.current_pfolio_amount;
+        let band = options.tolerance_band.as_ref();
 
         let assets = options
             .assets
             .into_iter()
-            .map(|(aid, asset)| (aid, Asset::new(asset, pfolio_amount)))
+            .map(|(aid, asset)| (aid, Asset::new(asset, pfolio_amount, band)))
             .collect::<HashMap<_, _>>();
 
         Self {
@@ -117,6 +135,21 @@ impl Problem {
 
         calculate_allocation_amount(assets)
     }
+
+    /// Symbols of the assets whose weight drifted out of their tolerance band.
+    pub fn out_of_band_assets(&self) -> Vec<String> {
+        let solution = Solution::new(self.options.clone());
+
+        let mut symbols = solution
+            .assets
+            .into_values()
+            .filter(|a| a.is_out_of_band)
+            .map(|a| a.symbol)
+            .collect::<Vec<_>>();
+        symbols.sort();
+
+        symbols
+    }
 }
 
 pub fn calculate_allocation_amount(assets: Vec<&mut Asset>) -> Decimal {
@@ -153,7 +186,10 @@ mod tests {
 
     use crate::{
         AMOUNT_DECIMALS,
-        optimize::suggestions::{Problem, ProblemAsset, ProblemOptions},
+        optimize::{
+            ToleranceBand,
+            suggestions::{Problem, ProblemAsset, ProblemOptions},
+        },
     };
 
     #[test_log::test]
@@ -168,6 +204,28 @@ mod tests {
         assert_eq!(solution, dec!(72.5));
     }
 
+    #[test_log::test]
+    fn it_reports_assets_out_of_band() {
+        // Given -- VWCE and AGGH weigh 46.5% and 53.5%
+        let (mut problem, _assets) = build_60_40_portfolio_no_allocation();
+        assert!(problem.out_of_band_assets().is_empty());
+
+        problem.options.tolerance_band = Some(ToleranceBand {
+            absolute: Some(dec!(0.2)),
+            relative: None,
+        });
+        assert!(problem.out_of_band_assets().is_empty());
+
+        // When
+        problem.options.tolerance_band = Some(ToleranceBand {
+            absolute: Some(dec!(0.05)),
+            relative: Some(dec!(0.25)),
+        });
+
+        // Expect
+        assert_eq!(problem.out_of_band_assets(), vec!["AGGH", "VWCE"]);
+    }
+
     fn build_60_40_portfolio_no_allocation() -> (Problem, Vec<String>) {
         let vwce = "VWCE".to_string();
         let aggh = "AGGH".to_string();
@@ -180,6 +238,7 @@ mod tests {
                     price: dec!(100.0),
                     target_weight: dec!(0.6),
                     is_whole_shares: true,
+                    tolerance_band: None,
                 },
             ),
             (
@@ -190,6 +249,7 @@ mod tests {
                     price: dec!(5.0),
                     target_weight: dec!(0.4),
                     is_whole_shares: true,
+                    tolerance_band: None,
                 },
             ),
         ]);
@@ -202,6 +262,7 @@ mod tests {
         let options = ProblemOptions {
             current_pfolio_amount,
             assets,
+            tolerance_band: None,
         };
 
         (Problem::new(options), vec![vwce, aggh])
@@ -0,0 +1,461 @@
+//! Budget-sweep sensitivity analysis.
+//!
+//! Solves the [`advanced`] problem across a range of budgets, either on a
+//! regular grid or adaptively, refining the budgets around which the set of
+//! bought assets changes. Answers "how much do I need to invest to get every
+//! asset bought", accounting for whole shares and fees.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use log::debug;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{OptimizeError, advanced};
+use crate::AMOUNT_DECIMALS;
+
+/// Intervals of the initial grid refined by an adaptive sweep.
+const ADAPTIVE_INITIAL_STEPS: u32 = 4;
+
+/// Budgets solved by an adaptive sweep, unless set otherwise.
+pub const DEFAULT_MAX_POINTS: u32 = 64;
+
+/// Largest number of budgets a sweep may solve.
+pub const MAX_POINTS: u32 = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemOptions {
+    /// Problem solved at each budget. Its `budget` is the largest one swept.
+    #[serde(flatten)]
+    pub advanced: advanced::ProblemOptions,
+    /// Smallest budget swept.
+    #[serde(default)]
+    pub min_budget: Decimal,
+    #[serde(default)]
+    pub sampling: Sampling,
+}
+
+impl ProblemOptions {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        let min_budget = self.min_budget.round_dp(AMOUNT_DECIMALS);
+        let advanced = self.advanced.validated()?;
+        if min_budget < Decimal::ZERO || min_budget >= advanced.budget {
+            return Err(OptimizeError::InvalidInput(format!(
+                "Invalid budget range ({min_budget}, {}). Minimum budget must be non-negative and lower than budget",
+                advanced.budget
+            )));
+        }
+
+        if min_budget + advanced.current_pfolio_amount <= Decimal::ZERO {
+            return Err(OptimizeError::InvalidInput(
+                "Invalid minimum budget. Must be positive for an empty portfolio".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            advanced,
+            min_budget,
+            sampling: self.sampling.validated()?,
+        })
+    }
+
+    /// Whether `asset` is under its target weight at the minimum budget, so
+    /// that a full allocation must buy it.
+    fn needs_buy(&self, asset: &advanced::ProblemAsset) -> bool {
+        if asset.target_weight <= Decimal::ZERO {
+            return false;
+        }
+
+        let rate = asset
+            .price_ccy
+            .as_ref()
+            .map_or(Decimal::ONE, |ccy| self.advanced.fx_rates[ccy]);
+        let amount = (asset.price * rate).round_dp(AMOUNT_DECIMALS) * asset.shares;
+        let total = self.advanced.current_pfolio_amount + self.min_budget;
+
+        amount < asset.target_weight * total
+    }
+
+    /// The advanced problem at `budget`.
+    fn at(&self, budget: Decimal) -> advanced::ProblemOptions {
+        advanced::ProblemOptions {
+            budget,
+            ..self.advanced.clone()
+        }
+    }
+}
+
+/// Budgets at which the problem is solved.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Sampling {
+    /// `steps` equal intervals between the minimum budget and the budget.
+    Grid { steps: u32 },
+    /// A coarse grid, bisected where the bought assets change until
+    /// intervals are narrower than `resolution` or `max_points` are solved.
+    #[serde(rename_all = "camelCase")]
+    Adaptive {
+        resolution: Option<Decimal>,
+        max_points: u32,
+    },
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::Adaptive {
+            resolution: None,
+            max_points: DEFAULT_MAX_POINTS,
+        }
+    }
+}
+
+impl Sampling {
+    pub fn validated(&self) -> Result<Self, OptimizeError> {
+        match *self {
+            Self::Grid { steps } if !(1..MAX_POINTS).contains(&steps) => {
+                Err(OptimizeError::InvalidInput(format!(
+                    "Invalid grid sampling ({steps} steps). Must be in [1, {}] range",
+                    MAX_POINTS - 1
+                )))
+            }
+            Self::Adaptive { max_points, .. } if !(2..=MAX_POINTS).contains(&max_points) => {
+                Err(OptimizeError::InvalidInput(format!(
+                    "Invalid adaptive sampling ({max_points} points). Must solve between 2 and {MAX_POINTS} points"
+                )))
+            }
+            Self::Adaptive {
+                resolution: Some(resolution),
+                ..
+            } if resolution <= Decimal::ZERO => Err(OptimizeError::InvalidInput(format!(
+                "Invalid adaptive sampling resolution ({resolution}). Must be positive"
+            ))),
+            Self::Adaptive {
+                resolution,
+                max_points,
+            } => Ok(Self::Adaptive {
+                resolution: resolution.map(|r| r.round_dp(AMOUNT_DECIMALS)),
+                max_points,
+            }),
+            grid => Ok(grid),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Solution {
+    /// Outcome at each budget swept, by increasing budget.
+    pub points: Vec<SweepPoint>,
+    /// Smallest budget swept at which each asset is bought, by asset id.
+    /// Assets never bought are missing.
+    pub first_allocations: HashMap<String, Decimal>,
+    /// Smallest budget swept at which every asset under its target weight at
+    /// the minimum budget has been bought.
+    pub full_allocation_budget: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepPoint {
+    pub budget: Decimal,
+    /// Largest distance of an asset weight from its target.
+    pub max_drift: Decimal,
+    pub budget_left: Decimal,
+    /// Fees charged on all trades.
+    pub fees: Decimal,
+}
+
+/// Solved budget, with the assets it buys.
+struct Sample {
+    point: SweepPoint,
+    bought: BTreeSet<String>,
+}
+
+pub struct Problem {
+    pub(crate) options: ProblemOptions,
+}
+
+impl Problem {
+    pub fn new(options: ProblemOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn solve(&self) -> Result<Solution, OptimizeError> {
+        let min = self.options.min_budget;
+        let max = self.options.advanced.budget;
+
+        let mut samples = BTreeMap::new();
+        match self.options.sampling {
+            Sampling::Grid { steps } => {
+                for budget in grid(min, max, steps) {
+                    samples.insert(budget, self.sample(budget)?);
+                }
+            }
+            Sampling::Adaptive {
+                resolution,
+                max_points,
+            } => {
+                let resolution = resolution.unwrap_or_else(|| {
+                    ((max - min) / Decimal::ONE_THOUSAND)
+                        .round_dp(AMOUNT_DECIMALS)
+                        .max(Decimal::new(1, AMOUNT_DECIMALS))
+                });
+
+                for budget in grid(min, max, ADAPTIVE_INITIAL_STEPS.min(max_points - 1)) {
+                    samples.insert(budget, self.sample(budget)?);
+                }
+
+                while samples.len() < max_points as usize {
+                    let Some(budget) = next_bisection(&samples, resolution) else {
+                        break;
+                    };
+                    samples.insert(budget, self.sample(budget)?);
+                }
+            }
+        }
+
+        debug!("[Sweep] Solved {} points", samples.len());
+
+        let mut first_allocations = HashMap::new();
+        for (budget, sample) in &samples {
+            for aid in &sample.bought {
+                first_allocations.entry(aid.clone()).or_insert(*budget);
+            }
+        }
+
+        let full_allocation_budget = self
+            .options
+            .advanced
+            .assets
+            .iter()
+            .filter(|(_, asset)| self.options.needs_buy(asset))
+            .map(|(aid, _)| first_allocations.get(aid).copied())
+            .try_fold(min, |budget, first| first.map(|first| budget.max(first)));
+
+        Ok(Solution {
+            points: samples.into_values().map(|s| s.point).collect(),
+            first_allocations,
+            full_allocation_budget,
+        })
+    }
+
+    fn sample(&self, budget: Decimal) -> Result<Sample, OptimizeError> {
+        let solution = advanced::Problem::new(self.options.at(budget)).solve();
+        if !solution.is_solved {
+            return Err(OptimizeError::Unsolved);
+        }
+
+        let assets = solution.assets.iter();
+        let max_drift = assets
+            .clone()
+            .map(|(_, a)| a.drift().abs())
+            .max()
+            .unwrap_or_default();
+        let fees = assets.clone().map(|(_, a)| a.trade_fees).sum();
+        let bought = assets
+            .filter(|(_, a)| a.shares > a.current_shares)
+            .map(|(aid, _)| aid.clone())
+            .collect();
+
+        Ok(Sample {
+            point: SweepPoint {
+                budget,
+                max_drift,
+                budget_left: solution.budget_left,
+                fees,
+            },
+            bought,
+        })
+    }
+}
+
+/// `steps + 1` budgets evenly spaced from `min` to `max`.
+fn grid(min: Decimal, max: Decimal, steps: u32) -> Vec<Decimal> {
+    let step = (max - min) / Decimal::from(steps);
+    (0..=steps)
+        .map(|i| match i {
+            _ if i == steps => max,
+            i => (min + step * Decimal::from(i)).round_dp(AMOUNT_DECIMALS),
+        })
+        .collect()
+}
+
+/// Midpoint of the widest interval across which the bought assets change,
+/// if any is wider than `resolution`.
+fn next_bisection(samples: &BTreeMap<Decimal, Sample>, resolution: Decimal) -> Option<Decimal> {
+    samples
+        .iter()
+        .zip(samples.iter().skip(1))
+        .filter(|((lo, a), (hi, b))| a.bought != b.bought && **hi - **lo > resolution)
+        .map(|((lo, _), (hi, _))| (*lo, *hi))
+        .max_by_key(|(lo, hi)| (*hi - *lo, std::cmp::Reverse(*lo)))
+        .map(|(lo, hi)| ((lo + hi) / Decimal::TWO).round_dp(AMOUNT_DECIMALS))
+        .filter(|mid| !samples.contains_key(mid))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn build_whole_shares_portfolio(sampling: Sampling) -> ProblemOptions {
+        // A share is bought once its target amount covers the price: A from
+        // a budget of 166.67, B from 200
+        let options: advanced::ProblemOptions = serde_json::from_value(serde_json::json!({
+            "pfolioCcy": "eur",
+            "assets": {
+                "A": {
+                    "symbol": "A",
+                    "shares": 0.,
+                    "price": 100.,
+                    "targetWeight": 0.6,
+                    "isWholeShares": true,
+                    "fees": null
+                },
+                "B": {
+                    "symbol": "B",
+                    "shares": 0.,
+                    "price": 80.,
+                    "targetWeight": 0.4,
+                    "isWholeShares": true,
+                    "fees": null
+                }
+            },
+            "budget": 400.,
+            "isBuyOnly": true
+        }))
+        .unwrap();
+
+        ProblemOptions {
+            advanced: options,
+            min_budget: dec!(50),
+            sampling,
+        }
+    }
+
+    #[test_log::test]
+    fn it_sweeps_budgets_on_a_grid() {
+        // Given
+        let options = build_whole_shares_portfolio(Sampling::Grid { steps: 7 });
+
+        // When
+        let solution = crate::optimize::solve_sweep(&options).unwrap();
+
+        // Expect
+        let budgets = solution.points.iter().map(|p| p.budget).collect::<Vec<_>>();
+        assert_eq!(
+            budgets,
+            vec![
+                dec!(50),
+                dec!(100),
+                dec!(150),
+                dec!(200),
+                dec!(250),
+                dec!(300),
+                dec!(350),
+                dec!(400)
+            ]
+        );
+
+        let budget_150 = &solution.points[2];
+        assert_eq!(budget_150.budget_left, dec!(150));
+        assert_eq!(budget_150.max_drift, dec!(0.6));
+
+        let budget_200 = &solution.points[3];
+        assert_eq!(budget_200.budget_left, dec!(20));
+        assert_eq!(budget_200.max_drift, dec!(0.1));
+        assert!(solution.points.iter().all(|p| p.fees.is_zero()));
+
+        assert_eq!(solution.first_allocations["A"], dec!(200));
+        assert_eq!(solution.first_allocations["B"], dec!(200));
+        assert_eq!(solution.full_allocation_budget, Some(dec!(200)));
+    }
+
+    #[test_log::test]
+    fn it_refines_budgets_where_assets_are_first_bought() {
+        // Given
+        let options = build_whole_shares_portfolio(Sampling::Adaptive {
+            resolution: Some(dec!(1)),
+            max_points: 64,
+        });
+
+        // When
+        let solution = crate::optimize::solve_sweep(&options).unwrap();
+
+        // Expect
+        let a = solution.first_allocations["A"];
+        let b = solution.first_allocations["B"];
+        assert!(
+            (dec!(166.6667)..=dec!(167.6667)).contains(&a),
+            "A first bought at {a}"
+        );
+        assert!(
+            (dec!(200)..=dec!(201)).contains(&b),
+            "B first bought at {b}"
+        );
+        assert_eq!(solution.full_allocation_budget, Some(b));
+        assert!(solution.points.len() < 64);
+        assert!(solution.points.is_sorted_by_key(|p| p.budget));
+    }
+
+    #[test_log::test]
+    fn it_ignores_assets_not_needing_a_buy_for_full_allocation() {
+        // Given
+        let mut options = build_whole_shares_portfolio(Sampling::Grid { steps: 7 });
+        let mut unwanted = options.advanced.assets["B"].clone();
+        unwanted.symbol = "C".to_string();
+        unwanted.target_weight = Decimal::ZERO;
+        options.advanced.assets.insert("C".to_string(), unwanted);
+
+        // When
+        let solution = crate::optimize::solve_sweep(&options).unwrap();
+
+        // Expect
+        assert!(!solution.first_allocations.contains_key("C"));
+        assert_eq!(solution.full_allocation_budget, Some(dec!(200)));
+    }
+
+    #[test_log::test]
+    fn it_rejects_too_many_points() {
+        for sampling in [
+            Sampling::Grid { steps: MAX_POINTS },
+            Sampling::Adaptive {
+                resolution: None,
+                max_points: MAX_POINTS + 1,
+            },
+        ] {
+            // Given
+            let options = build_whole_shares_portfolio(sampling);
+
+            // When
+            let res = crate::optimize::solve_sweep(&options);
+
+            // Expect
+            assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+        }
+    }
+
+    #[test_log::test]
+    fn it_rejects_invalid_ranges() {
+        // Given
+        let mut options = build_whole_shares_portfolio(Sampling::Grid { steps: 4 });
+        options.min_budget = dec!(400);
+
+        // When
+        let res = crate::optimize::solve_sweep(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+
+        // Given
+        options.min_budget = Decimal::ZERO;
+
+        // When
+        let res = crate::optimize::solve_sweep(&options);
+
+        // Expect
+        assert!(matches!(res, Err(OptimizeError::InvalidInput(_))));
+    }
+}
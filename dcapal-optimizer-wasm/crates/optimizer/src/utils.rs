@@ -3,7 +3,7 @@ use std::sync::Once;
 use log::Level;
 use rust_decimal::{Decimal, prelude::*};
 
-use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS, SHARES_DECIMALS};
+use crate::{AMOUNT_DECIMALS, FX_RATE_DECIMALS, PERCENTAGE_DECIMALS, SHARES_DECIMALS};
 
 static INIT: Once = Once::new();
 
@@ -34,6 +34,18 @@ fn set_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// Milliseconds elapsed since the Unix epoch.
+pub fn now_millis() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    return js_sys::Date::now();
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        * 1000.;
+}
+
 pub fn parse_amount(amount: f64) -> Decimal {
     Decimal::from_f64(amount).unwrap().round_dp(AMOUNT_DECIMALS)
 }
@@ -47,3 +59,7 @@ pub fn parse_percentage(weight: f64) -> Decimal {
 pub fn parse_shares(shares: f64) -> Decimal {
     Decimal::from_f64(shares).unwrap().round_dp(SHARES_DECIMALS)
 }
+
+pub fn parse_fx_rate(rate: f64) -> Decimal {
+    Decimal::from_f64(rate).unwrap().round_dp(FX_RATE_DECIMALS)
+}
@@ -7,8 +7,11 @@ mod utils_macros;
 use std::{fs::File, io::BufReader, path::Path};
 
 use dcapal_optimizer_wasm::{
-    JsAdvancedOptions, JsProblemOptions,
-    optimize::advanced::{self, Solution},
+    JsAdvancedOptions, JsExactOptions, JsProblemOptions,
+    optimize::{
+        advanced::{self, Solution},
+        exact,
+    },
 };
 use error::{Error, Result};
 use glob::glob;
@@ -34,13 +37,13 @@ fn test_runner() -> anyhow::Result<()> {
         info!("==> 💿  Running scenario {:?}", path.file_name().unwrap());
 
         let scenario = read_scenario_from_file(path)?;
-        let (options, expect) = scenario.split();
+        let (options, expect) = scenario.split()?;
 
         // Build problem from options and solve it
         let res = match options {
             JsProblemOptions::Advanced(o) => build_solve_advanced(o),
-            JsProblemOptions::Basic(_) => todo!(),
-            JsProblemOptions::Analyze(_) => todo!("Analyze not implemented"),
+            JsProblemOptions::Exact(o) => build_solve_exact(o),
+            _ => anyhow::bail!("Unsupported problem kind in scenario {path:?}"),
         };
 
         info!("==> 🔬  Checking expectations");
@@ -55,7 +58,6 @@ fn test_runner() -> anyhow::Result<()> {
             (Expect::Solved(expected), TestSolution::Advanced(sol)) => {
                 check_expect_advanced(&expected, &sol)
             }
-            (Expect::Solved(_), TestSolution::Basic) => todo!(),
             (Expect::BuildError, _) => unreachable!(),
         }
     }
@@ -80,6 +82,15 @@ fn build_solve_advanced(options: JsAdvancedOptions) -> Result<TestSolution> {
     Ok(TestSolution::Advanced(solution))
 }
 
+fn build_solve_exact(options: JsExactOptions) -> Result<TestSolution> {
+    let options = exact::ProblemOptions::try_from(options).map_err(Error::BadProblemInput)?;
+
+    let problem = exact::Problem::new(options);
+    let solution = problem.solve();
+
+    Ok(TestSolution::Advanced(solution.allocation))
+}
+
 fn check_expect_advanced(expected: &ExpectedSolution, sol: &Solution) {
     assert!(sol.is_solved, "Expected solved problem: sol={sol:?}");
 
@@ -104,5 +115,4 @@ fn check_expect_advanced(expected: &ExpectedSolution, sol: &Solution) {
 #[derive(Debug, Clone)]
 pub enum TestSolution {
     Advanced(advanced::Solution),
-    Basic,
 }
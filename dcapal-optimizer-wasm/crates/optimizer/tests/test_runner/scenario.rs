@@ -1,7 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use dcapal_optimizer_wasm::{
-    JsAdvancedAsset, JsAdvancedOptions, JsProblemOptions, JsTransactionFees,
+    JsAdvancedAsset, JsAdvancedOptions, JsExactOptions, JsProblemOptions, JsTransactionFees,
 };
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -20,31 +20,42 @@ pub struct Scenario {
 }
 
 impl Scenario {
-    pub fn split(self) -> (JsProblemOptions, Expect) {
-        let expect = self.expect;
+    pub fn split(self) -> anyhow::Result<(JsProblemOptions, Expect)> {
         let options = match self.algorithm {
-            Algorithm::Basic => todo!(),
-            Algorithm::Advanced => {
-                let budget = self.budget.to_f64().unwrap();
-                let assets = self
-                    .portfolio
-                    .assets
-                    .into_iter()
-                    .map(|a| (a.symbol.clone(), a.into()))
-                    .collect::<_>();
-
-                JsProblemOptions::Advanced(JsAdvancedOptions {
-                    budget,
-                    pfolio_ccy: self.portfolio.quote_ccy,
-                    assets,
-                    fees: self.portfolio.fees,
-                    is_buy_only: self.is_buy_only,
-                    use_all_budget: self.use_all_budget,
-                })
-            }
+            Algorithm::Basic => anyhow::bail!("Basic scenarios are not supported"),
+            Algorithm::Advanced => JsProblemOptions::Advanced(self.advanced_options()),
+            Algorithm::Exact => JsProblemOptions::Exact(JsExactOptions {
+                options: self.advanced_options(),
+                max_nodes: None,
+                max_millis: None,
+            }),
         };
 
-        (options, expect)
+        Ok((options, self.expect))
+    }
+
+    fn advanced_options(&self) -> JsAdvancedOptions {
+        let budget = self.budget.to_f64().unwrap();
+        let assets = self
+            .portfolio
+            .assets
+            .iter()
+            .map(|a| (a.symbol.clone(), a.into()))
+            .collect::<_>();
+
+        JsAdvancedOptions {
+            budget,
+            pfolio_ccy: self.portfolio.quote_ccy.clone(),
+            assets,
+            fees: self.portfolio.fees.clone(),
+            is_buy_only: self.is_buy_only,
+            use_all_budget: self.use_all_budget,
+            capital_gains_tax: None,
+            tolerance_band: None,
+            groups: Vec::new(),
+            fx_rates: HashMap::new(),
+            fx_fees: HashMap::new(),
+        }
     }
 }
 
@@ -53,6 +64,7 @@ impl Scenario {
 pub enum Algorithm {
     Basic,
     Advanced,
+    Exact,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,15 +128,21 @@ pub struct AssetSolution {
     pub weight: Option<Decimal>,
 }
 
-impl From<Asset> for JsAdvancedAsset {
-    fn from(value: Asset) -> Self {
+impl From<&Asset> for JsAdvancedAsset {
+    fn from(value: &Asset) -> Self {
         JsAdvancedAsset {
-            symbol: value.symbol,
+            symbol: value.symbol.clone(),
             shares: value.qty.to_f64().unwrap(),
             price: value.price.to_f64().unwrap(),
             target_weight: value.target_weight.to_f64().unwrap() / 100.,
             is_whole_shares: value.aclass.is_whole_shares(),
-            fees: value.fees,
+            fees: value.fees.clone(),
+            quantity_step: None,
+            min_quantity: None,
+            min_order_amount: None,
+            average_buy_price: None,
+            tolerance_band: None,
+            price_ccy: None,
         }
     }
 }